@@ -62,10 +62,7 @@ pub enum Repeat {
     Fixed { number: usize },
     /// A variable number of repetitions determined by
     /// (dependent on ) another word and limited
-    Dependent {
-        bit_spec: Box<BitSpec>,
-        limit: usize,
-    },
+    Dependent { count: RepeatCount, limit: usize },
     /// A variable number of repetitions. Each element is provided
     /// by a `provider` which needs to implement the
     /// `Iterator` trait and is determined elsewhere.
@@ -92,11 +89,36 @@ impl fmt::Display for Repeat {
         match self {
             Repeat::None => write!(f, ""),
             Repeat::Fixed { number } => write!(f, "{number}"),
-            Repeat::Dependent { bit_spec, limit } => write!(f, "({bit_spec})<={limit}"),
+            Repeat::Dependent { count, limit } => write!(f, "({count})<={limit}"),
             Repeat::Variable { limit, .. } => write!(f, "<={limit}"),
         }
     }
 }
+
+/// Where the number of repeats of a dependent repeat is found.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub enum RepeatCount {
+    /// The bits holding the number of repeats, e.g. `(3[])`.
+    BitSpec(Box<BitSpec>),
+    /// The name of the field or label holding the number of repeats, e.g. `(n_stations)`.
+    Symbol(String),
+}
+
+impl From<BitSpec> for RepeatCount {
+    fn from(bit_spec: BitSpec) -> Self {
+        RepeatCount::BitSpec(Box::new(bit_spec))
+    }
+}
+
+impl fmt::Display for RepeatCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepeatCount::BitSpec(bit_spec) => write!(f, "{bit_spec}"),
+            RepeatCount::Symbol(symbol) => write!(f, "{symbol}"),
+        }
+    }
+}
+
 // The range of bytes covering a whole bit spec.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum WordRange {
@@ -108,7 +130,7 @@ pub enum WordRange {
     /// ```
     /// The word range is 3 to 4, but this span needs to be multiplied by
     /// the number in `1[]`.
-    Dependent(usize, usize, BitSpec),
+    Dependent(usize, usize, RepeatCount),
     // A variable number of repeats. All that is is know is the limit.
     Variable(usize),
 }
@@ -124,6 +146,11 @@ pub struct BitSpec {
     pub end: Option<Word>,
     /// How the word is repeated, if at all.
     pub repeat: Repeat,
+    /// The symbol that other bit specs can use to follow this one.
+    pub label: Option<String>,
+    /// The symbol of the bit spec that this one follows. The word indexes
+    /// are then relative to the first word after the followed bit spec.
+    pub follows: Option<String>,
 }
 
 impl BitSpec {
@@ -133,9 +160,19 @@ impl BitSpec {
             start: word.clone(),
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         }
     }
 
+    /// True if both bit specs refer to the same bits, regardless of their labels.
+    pub fn same_bits(&self, other: &BitSpec) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && self.repeat == other.repeat
+            && self.follows == other.follows
+    }
+
     /// Get the max size in bytes of an array that could
     /// contain the bit specification.
     pub fn max_size(&self) -> usize {
@@ -159,41 +196,47 @@ impl BitSpec {
 
     /// Determine how many bytes this bit spec would need. It returns an
     /// optional tuple with the fixed size of the element and an
-    /// optional BitSpec showing where the multiplier is located.
+    /// optional RepeatCount showing where the multiplier is located.
     /// If the size cannot be determined (e.g. the bit spec is a
     /// variabvle repeat) the `None` is returned
-    pub fn size(&self) -> Option<(usize, Option<BitSpec>)> {
+    pub fn size(&self) -> Option<(usize, Option<RepeatCount>)> {
         match self {
             BitSpec {
                 start: _,
                 end: None,
                 repeat: Repeat::None,
+                ..
             } => Some((1, None)),
             BitSpec {
                 start,
                 end: Some(end),
                 repeat: Repeat::None,
+                ..
             } => Some((end.index - start.index + 1, None)),
             BitSpec {
                 start: _,
                 end: None,
                 repeat: Repeat::Fixed { number },
+                ..
             } => Some((*number, None)),
             BitSpec {
                 start,
                 end: Some(end),
                 repeat: Repeat::Fixed { number },
+                ..
             } => Some(((end.index - start.index + 1) * number, None)),
             BitSpec {
                 start: _,
                 end: None,
-                repeat: Repeat::Dependent { bit_spec, .. },
-            } => Some((1, Some((**bit_spec).clone()))),
+                repeat: Repeat::Dependent { count, .. },
+                ..
+            } => Some((1, Some(count.clone()))),
             BitSpec {
                 start,
                 end: Some(end),
-                repeat: Repeat::Dependent { bit_spec, .. },
-            } => Some((end.index - start.index + 1, Some((**bit_spec).clone()))),
+                repeat: Repeat::Dependent { count, .. },
+                ..
+            } => Some((end.index - start.index + 1, Some(count.clone()))),
             BitSpec {
                 repeat: Repeat::Variable { .. },
                 ..
@@ -210,18 +253,21 @@ impl BitSpec {
             Repeat::Fixed { number } => {
                 WordRange::Fixed(start, start + ((end - start + 1) * number) - 1)
             }
-            Repeat::Dependent { bit_spec, .. } => {
-                WordRange::Dependent(start, end, (**bit_spec).clone())
-            }
+            Repeat::Dependent { count, .. } => WordRange::Dependent(start, end, count.clone()),
             Repeat::Variable { limit } => WordRange::Variable(*limit),
             Repeat::None => WordRange::Fixed(start, end),
         }
     }
 
     /// Suggest a type for a variable to hold the value
-    /// specified by the bit spec.
-
+    /// specified by the bit spec, assuming words of 8 bits.
     pub fn suggested_word_type(&self) -> String {
+        self.suggested_type(8)
+    }
+
+    /// Suggest a type for a variable to hold the value
+    /// specified by the bit spec for words of `word_size` bits.
+    pub fn suggested_type(&self, word_size: usize) -> String {
         // First cover the special case of a literal. Handling this
         // first reduces the complexity of the match statements.
         if let BitSpec {
@@ -233,7 +279,7 @@ impl BitSpec {
             ..
         } = self
         {
-            return "u8".to_string();
+            return format!("u{word_size}");
         };
 
         // Now cover the rest of the cases
@@ -246,12 +292,15 @@ impl BitSpec {
                     },
                 end: None,
                 ..
-            } => "bool",
+            } => "bool".to_string(),
             BitSpec {
                 start: Word { .. },
                 end: None,
                 ..
-            } => "u8",
+            } => format!("u{word_size}"),
+            BitSpec { end: Some(_), .. } if self.spans_partial_words() => {
+                spanning_type(self.word_range_bit_count(word_size))
+            }
             BitSpec {
                 start: Word {
                     index: start_index, ..
@@ -260,15 +309,115 @@ impl BitSpec {
                     index: end_index, ..
                 }),
                 ..
-            } => match end_index - start_index + 1 {
-                2 => "u16",
-                4 => "u32",
-                8 => "u64",
-                16 => "u128",
+            } => match (end_index - start_index + 1) * word_size {
+                16 => "u16",
+                32 => "u32",
+                64 => "u64",
+                128 => "u128",
                 _ => "usize",
-            },
+            }
+            .to_string(),
         }
-        .to_string()
+    }
+
+    /// For a word range, the bit in its first word that it starts from and the bit in its
+    /// last word that it ends at, e.g. `(4, 2)` for `3[4]..6[2]`. A whole word starts from
+    /// bit 0 or ends at the last bit of the word. Returns `None` if the bit spec is not a
+    /// word range or uses a literal.
+    pub fn word_range_bits(&self, word_size: usize) -> Option<(u16, u16)> {
+        let end = self.end.as_ref()?;
+        let start_bit = match self.start.bit_range {
+            BitRange::Single(position) | BitRange::Range(position, _) => position,
+            BitRange::WholeWord => 0,
+            BitRange::Literal(_) => return None,
+        };
+        let end_bit = match end.bit_range {
+            BitRange::Single(position) | BitRange::Range(_, position) => position,
+            BitRange::WholeWord => word_size as u16 - 1,
+            BitRange::Literal(_) => return None,
+        };
+
+        Some((start_bit, end_bit))
+    }
+
+    /// The number of bits in one element of a word range, e.g. 23 for `3[4]..6[2]`
+    /// with words of 8 bits.
+    pub fn word_range_bit_count(&self, word_size: usize) -> Option<usize> {
+        let (start_bit, end_bit) = self.word_range_bits(word_size)?;
+        let end_index = self.end.as_ref()?.index;
+        let words = end_index.checked_sub(self.start.index)? + 1;
+
+        (words * word_size + end_bit as usize + 1).checked_sub(word_size + start_bit as usize)
+    }
+
+    /// As `suggested_type`, but for a device that sends the most significant bits of a
+    /// word range first. See `word_range_bits_be`.
+    pub fn suggested_type_be(&self, word_size: usize) -> String {
+        if self.spans_partial_words() {
+            spanning_type(self.word_range_bit_count_be(word_size))
+        } else {
+            self.suggested_type(word_size)
+        }
+    }
+
+    /// For a word range on a big endian device, where the first word holds the most
+    /// significant bits, the bit in its first word that it runs up to from bit 0 and the
+    /// bit in its last word that it runs from up to the last bit of the word, e.g. `(3, 0)`
+    /// for `0[0..3]..1[]`. A whole word runs up to the last bit or from bit 0. Returns
+    /// `None` if the bit spec is not a word range or uses a literal.
+    pub fn word_range_bits_be(&self, word_size: usize) -> Option<(u16, u16)> {
+        let end = self.end.as_ref()?;
+        let start_bit = match self.start.bit_range {
+            BitRange::Single(position) | BitRange::Range(_, position) => position,
+            BitRange::WholeWord => word_size as u16 - 1,
+            BitRange::Literal(_) => return None,
+        };
+        let end_bit = match end.bit_range {
+            BitRange::Single(position) | BitRange::Range(position, _) => position,
+            BitRange::WholeWord => 0,
+            BitRange::Literal(_) => return None,
+        };
+
+        Some((start_bit, end_bit))
+    }
+
+    /// As `word_range_bit_count`, but for a big endian device, e.g. 12 for `0[0..3]..1[]`
+    /// with words of 8 bits.
+    pub fn word_range_bit_count_be(&self, word_size: usize) -> Option<usize> {
+        let (start_bit, end_bit) = self.word_range_bits_be(word_size)?;
+        let end_index = self.end.as_ref()?.index;
+        let words = end_index.checked_sub(self.start.index)? + 1;
+
+        (words * word_size + start_bit as usize + 1).checked_sub(word_size + end_bit as usize)
+    }
+
+    /// True if the bit spec is a word range that does not start or end on a word
+    /// boundary, e.g. `0[4]..1[7]`.
+    pub fn spans_partial_words(&self) -> bool {
+        matches!(
+            &self.end,
+            Some(end) if !matches!(
+                (&self.start.bit_range, &end.bit_range),
+                (BitRange::WholeWord, BitRange::WholeWord)
+                    | (BitRange::Literal(_), _)
+                    | (_, BitRange::Literal(_))
+            )
+        )
+    }
+
+    /// The highest bit position used in any of the words of the
+    /// bit spec, or `None` if only whole words are used.
+    pub fn highest_bit_position(&self) -> Option<u16> {
+        let word_position = |word: &Word| match word.bit_range {
+            BitRange::Single(position) => Some(position),
+            BitRange::Range(start, end) => Some(start.max(end)),
+            BitRange::WholeWord | BitRange::Literal(_) => None,
+        };
+
+        let start_position = word_position(&self.start);
+        let end_position = self.end.as_ref().and_then(word_position);
+
+        start_position.max(end_position)
     }
 
     pub fn literal_value(&self) -> Option<String> {
@@ -280,11 +429,33 @@ impl BitSpec {
     }
 }
 
+/// Gives the canonical form of the bit spec, which parses back to the same bit spec.
+/// Bits in the first word are given without the word, e.g. `4` rather than `0[4]`, and
+/// limits are always given with `<=`, so `(1[])<10` is shown as `(1[])<=9`.
 impl fmt::Display for BitSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let BitSpec { start, end, repeat } = self;
-
-        let s = format!("{start}");
+        let BitSpec {
+            start,
+            end,
+            repeat,
+            label,
+            follows,
+        } = self;
+
+        let l = match label {
+            Some(label) => format!("{label}:"),
+            None => String::new(),
+        };
+        let s = match follows {
+            Some(follows) => format!("(>{follows})[{}]", start.bit_range),
+            None if start.index == 0
+                && end.is_none()
+                && matches!(start.bit_range, BitRange::Single(_) | BitRange::Range(..)) =>
+            {
+                format!("{}", start.bit_range)
+            }
+            None => format!("{start}"),
+        };
         let e = match end {
             Some(word) => format!("..{word}"),
             None => String::new(),
@@ -293,10 +464,21 @@ impl fmt::Display for BitSpec {
             Repeat::None => String::new(),
             _ => format!(";{repeat}"),
         };
-        write!(f, "{s}{e}{r}")
+        write!(f, "{l}{s}{e}{r}")
     }
 }
 
+// The smallest unsigned type that holds the bits of a word range spanning partial words
+fn spanning_type(bits: Option<usize>) -> String {
+    match bits.unwrap_or_default() {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        _ => "u64",
+    }
+    .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,6 +499,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let bit_spec = BitSpec::from_word(&word);
@@ -373,6 +557,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let upper_bit_spec = BitSpec {
@@ -382,6 +568,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         assert!(upper_bit_spec > lower_bit_spec);
@@ -399,6 +587,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let upper_bit_spec = BitSpec {
@@ -411,6 +601,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         assert!(upper_bit_spec > lower_bit_spec);
@@ -425,6 +617,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let upper_bit_spec = BitSpec {
@@ -434,6 +628,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         assert!(upper_bit_spec > lower_bit_spec);
@@ -448,6 +644,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let upper_bit_spec = BitSpec {
@@ -457,6 +655,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         assert!(upper_bit_spec > lower_bit_spec);
@@ -472,7 +672,7 @@ mod tests {
 
         let repeat = Repeat::Dependent {
             limit: 5,
-            bit_spec: Box::new(parse("5[]").unwrap()),
+            count: RepeatCount::from(parse("5[]").unwrap()),
             // word: Word {
             //     index: 5,
             //     bit_range: BitRange::WholeWord,
@@ -482,7 +682,7 @@ mod tests {
 
         let repeat = Repeat::Dependent {
             limit: 6,
-            bit_spec: Box::new(parse("5[]").unwrap()),
+            count: RepeatCount::from(parse("5[]").unwrap()),
             // word: Word {
             //     index: 5,
             //     bit_range: BitRange::WholeWord,
@@ -503,7 +703,9 @@ mod tests {
                     bit_range: BitRange::Single(4)
                 },
                 end: None,
-                repeat: Repeat::None
+                repeat: Repeat::None,
+                label: None,
+                follows: None,
             }
             .to_string(),
             "6[4]"
@@ -519,7 +721,9 @@ mod tests {
                     index: 8,
                     bit_range: BitRange::Range(1, 6)
                 }),
-                repeat: Repeat::None
+                repeat: Repeat::None,
+                label: None,
+                follows: None,
             }
             .to_string(),
             "6[4]..8[1..6]"
@@ -535,7 +739,9 @@ mod tests {
                     index: 8,
                     bit_range: BitRange::Range(1, 6)
                 }),
-                repeat: Repeat::Fixed { number: 10 }
+                repeat: Repeat::Fixed { number: 10 },
+                label: None,
+                follows: None,
             }
             .to_string(),
             "6[]..8[1..6];10"
@@ -553,12 +759,14 @@ mod tests {
                 }),
                 repeat: Repeat::Dependent {
                     limit: 10,
-                    bit_spec: Box::new(parse("3[]").unwrap()),
+                    count: RepeatCount::from(parse("3[]").unwrap()),
                     // word: Word {
                     //     index: 3,
                     //     bit_range: BitRange::WholeWord
                     // }
-                }
+                },
+                label: None,
+                follows: None,
             }
             .to_string(),
             "6[]..8[];(3[])<=10"
@@ -574,11 +782,99 @@ mod tests {
                     index: 8,
                     bit_range: BitRange::WholeWord
                 }),
-                repeat: Repeat::Variable { limit: 15 }
+                repeat: Repeat::Variable { limit: 15 },
+                label: None,
+                follows: None,
             }
             .to_string(),
             "6[]..8[];<=15"
         );
+
+        assert_eq!(
+            parse("count:(>stations)[2..5]").unwrap().to_string(),
+            "count:(>stations)[2..5]"
+        );
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let canonical = |bit_spec: &str| parse(bit_spec).unwrap().to_string();
+
+        assert_eq!(canonical("4"), "4");
+        assert_eq!(canonical("0[4]"), "4");
+        assert_eq!(canonical("[3..5]"), "3..5");
+        assert_eq!(canonical("flag:[7];3"), "flag:7;3");
+        assert_eq!(canonical("[]"), "0[]");
+        assert_eq!(canonical("[0xBA]"), "0[0xBA]");
+        assert_eq!(canonical("2[0X1f]"), "2[0x1f]");
+        assert_eq!(canonical("[4]..1[2]"), "0[4]..1[2]");
+        assert_eq!(canonical("2[]..4[];(1[])<10"), "2[]..4[];(1[])<=9");
+        assert_eq!(canonical("2[];(n_items)<=4"), "2[];(n_items)<=4");
+        assert_eq!(canonical("2[];(4)<3"), "2[];(4)<=2");
+        assert_eq!(canonical("2[];<20"), "2[];<=19");
+        // Word ranges given as a number of words are shown with their last word
+        assert_eq!(canonical("3[]*4;10"), "3[]..6[];10");
+        assert_eq!(canonical("(>stations)[]*2"), "(>stations)[]..1[]");
+    }
+
+    #[test]
+    fn test_word_range_bits() {
+        let bit_spec = parse("3[4]..6[2]").unwrap();
+        assert_eq!(bit_spec.word_range_bits(8), Some((4, 2)));
+        assert_eq!(bit_spec.word_range_bit_count(8), Some(23));
+        assert!(bit_spec.spans_partial_words());
+
+        let bit_spec = parse("3[]..4[0..3]").unwrap();
+        assert_eq!(bit_spec.word_range_bits(8), Some((0, 3)));
+        assert_eq!(bit_spec.word_range_bit_count(8), Some(12));
+        assert!(bit_spec.spans_partial_words());
+
+        let bit_spec = parse("3[]..4[]").unwrap();
+        assert_eq!(bit_spec.word_range_bits(16), Some((0, 15)));
+        assert_eq!(bit_spec.word_range_bit_count(16), Some(32));
+        assert!(!bit_spec.spans_partial_words());
+
+        assert_eq!(parse("3[4]").unwrap().word_range_bits(8), None);
+        assert_eq!(parse("3[0x1F]..4[]").unwrap().word_range_bits(8), None);
+    }
+
+    #[test]
+    fn test_word_range_bits_be() {
+        let bit_spec = parse("0[0..3]..1[]").unwrap();
+        assert_eq!(bit_spec.word_range_bits_be(8), Some((3, 0)));
+        assert_eq!(bit_spec.word_range_bit_count_be(8), Some(12));
+        assert_eq!(bit_spec.suggested_type_be(8), "u16");
+
+        let bit_spec = parse("3[]..5[4..7]").unwrap();
+        assert_eq!(bit_spec.word_range_bits_be(8), Some((7, 4)));
+        assert_eq!(bit_spec.word_range_bit_count_be(8), Some(20));
+        assert_eq!(bit_spec.suggested_type_be(8), "u32");
+
+        let bit_spec = parse("3[]..4[]").unwrap();
+        assert_eq!(bit_spec.word_range_bits_be(16), Some((15, 0)));
+        assert_eq!(bit_spec.word_range_bit_count_be(16), Some(32));
+        assert_eq!(bit_spec.suggested_type_be(16), "u32");
+
+        assert_eq!(parse("3[4]").unwrap().word_range_bits_be(8), None);
+    }
+
+    #[test]
+    fn test_suggested_type_for_spanning_fields() {
+        assert_eq!(parse("0[4]..1[7]").unwrap().suggested_type(8), "u16");
+        assert_eq!(parse("0[]..2[3]").unwrap().suggested_type(8), "u32");
+        assert_eq!(parse("0[2]..1[1]").unwrap().suggested_type(8), "u8");
+        assert_eq!(parse("0[4]..5[]").unwrap().suggested_type(8), "u64");
+        assert_eq!(parse("0[4]..1[]").unwrap().suggested_type(16), "u32");
+    }
+
+    #[test]
+    fn test_same_bits() {
+        let bit_spec = parse("count:3[]").unwrap();
+        assert!(bit_spec.same_bits(&parse("3[]").unwrap()));
+        assert!(!bit_spec.same_bits(&parse("4[]").unwrap()));
+        assert!(!parse("0[]")
+            .unwrap()
+            .same_bits(&parse("(>stations)[]").unwrap()));
     }
 
     #[test]
@@ -590,6 +886,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         assert_eq!(bit_spec.suggested_word_type(), "bool");
@@ -601,6 +899,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec.suggested_word_type(), "u8");
 
@@ -611,6 +911,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec.suggested_word_type(), "u8");
 
@@ -624,6 +926,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec.suggested_word_type(), "u16");
 
@@ -637,6 +941,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec.suggested_word_type(), "u64");
 
@@ -647,12 +953,34 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec.suggested_word_type(), "u8");
 
         //todo!("more tests");
     }
 
+    #[test]
+    fn test_suggested_type_for_word_size() {
+        assert_eq!(parse("4").unwrap().suggested_type(16), "bool");
+        assert_eq!(parse("4[0..11]").unwrap().suggested_type(16), "u16");
+        assert_eq!(parse("4[]").unwrap().suggested_type(16), "u16");
+        assert_eq!(parse("4[]..5[]").unwrap().suggested_type(16), "u32");
+        assert_eq!(parse("4[]..7[]").unwrap().suggested_type(16), "u64");
+        assert_eq!(parse("4[]").unwrap().suggested_type(32), "u32");
+        assert_eq!(parse("4[]..5[]").unwrap().suggested_type(32), "u64");
+        assert_eq!(parse("4[]..6[]").unwrap().suggested_type(32), "usize");
+    }
+
+    #[test]
+    fn test_highest_bit_position() {
+        assert_eq!(parse("4").unwrap().highest_bit_position(), Some(4));
+        assert_eq!(parse("2[3..14]").unwrap().highest_bit_position(), Some(14));
+        assert_eq!(parse("2[]..3[]").unwrap().highest_bit_position(), None);
+        assert_eq!(parse("2[]").unwrap().highest_bit_position(), None);
+    }
+
     #[test]
     fn test_size_with_bit() {
         let bit_spec = parse("3").unwrap();
@@ -692,7 +1020,7 @@ mod tests {
             size,
             (
                 1,
-                Some(expected_bit_spec),
+                Some(RepeatCount::from(expected_bit_spec)),
                 // Some(Word {
                 //     index: 1,
                 //     bit_range: BitRange::WholeWord,
@@ -720,7 +1048,7 @@ mod tests {
             size,
             (
                 4,
-                Some(expected_bit_spec),
+                Some(RepeatCount::from(expected_bit_spec)),
                 // Some(Word {
                 //     index: 1,
                 //     bit_range: BitRange::WholeWord,
@@ -790,7 +1118,7 @@ mod tests {
             panic!("Word range is not variable")
         };
 
-        let expected_dependent_bit_spec = parse("5[]").unwrap();
+        let expected_dependent_bit_spec = RepeatCount::from(parse("5[]").unwrap());
         assert_eq!(start, 6);
         assert_eq!(end, 6);
         assert_eq!(
@@ -814,4 +1142,109 @@ mod tests {
         let bit_spec = parse("3[0x0010]").unwrap();
         assert_eq!(Some("0x0010".to_string()), bit_spec.literal_value());
     }
+
+    mod round_trip {
+        use super::*;
+
+        use proptest::prelude::*;
+
+        fn symbol() -> impl Strategy<Value = String> {
+            "[a-zA-Z_][a-zA-Z0-9_]{0,8}"
+        }
+
+        fn bit_range() -> impl Strategy<Value = BitRange> {
+            prop_oneof![
+                any::<u16>().prop_map(BitRange::Single),
+                any::<(u16, u16)>().prop_map(|(start, end)| BitRange::Range(start, end)),
+                Just(BitRange::WholeWord),
+                "0x[0-9a-fA-F_]{1,6}".prop_map(BitRange::Literal),
+                "0b[01]{1,4}(_[01]{1,4}){0,2}".prop_map(BitRange::Literal),
+            ]
+        }
+
+        fn word() -> impl Strategy<Value = Word> {
+            (any::<u16>(), bit_range()).prop_map(|(index, bit_range)| Word {
+                index: index.into(),
+                bit_range,
+            })
+        }
+
+        fn repeat(count: BoxedStrategy<RepeatCount>) -> impl Strategy<Value = Repeat> {
+            prop_oneof![
+                Just(Repeat::None),
+                any::<u16>().prop_map(|number| Repeat::Fixed {
+                    number: number.into()
+                }),
+                (count, any::<u16>()).prop_map(|(count, limit)| Repeat::Dependent {
+                    count,
+                    limit: limit.into()
+                }),
+                any::<u16>().prop_map(|limit| Repeat::Variable {
+                    limit: limit.into()
+                }),
+            ]
+        }
+
+        // The end of the word range, either as the last word or as a number of words.
+        fn range_end() -> impl Strategy<Value = Option<Result<Word, u16>>> {
+            prop_oneof![
+                Just(None),
+                word().prop_map(|word| Some(Ok(word))),
+                (1..=u16::MAX).prop_map(|words| Some(Err(words))),
+            ]
+        }
+
+        fn bit_spec_with(count: BoxedStrategy<RepeatCount>) -> BoxedStrategy<BitSpec> {
+            (
+                proptest::option::of(symbol()),
+                proptest::option::of(symbol()),
+                word(),
+                range_end(),
+                repeat(count),
+            )
+                .prop_map(|(label, follows, mut start, end, repeat)| {
+                    if follows.is_some() {
+                        start.index = 0;
+                    }
+                    let end = match end {
+                        None => None,
+                        Some(Ok(word)) => Some(word),
+                        Some(Err(words)) => {
+                            start.bit_range = BitRange::WholeWord;
+                            // The last word has to be a valid word index
+                            let words = (words as usize).min(u16::MAX as usize + 1 - start.index);
+                            Some(Word {
+                                index: start.index + words - 1,
+                                bit_range: BitRange::WholeWord,
+                            })
+                        }
+                    };
+
+                    BitSpec {
+                        start,
+                        end,
+                        repeat,
+                        label,
+                        follows,
+                    }
+                })
+                .boxed()
+        }
+
+        fn bit_spec() -> impl Strategy<Value = BitSpec> {
+            let symbol_count = symbol().prop_map(RepeatCount::Symbol).boxed();
+            let count = prop_oneof![
+                symbol_count.clone(),
+                bit_spec_with(symbol_count).prop_map(RepeatCount::from),
+            ];
+            bit_spec_with(count.boxed())
+        }
+
+        proptest! {
+            #[test]
+            fn test_display_parses_back(bit_spec in bit_spec()) {
+                prop_assert_eq!(parse(&bit_spec.to_string()), Ok(bit_spec));
+            }
+        }
+    }
 }
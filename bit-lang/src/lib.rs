@@ -73,7 +73,8 @@
 //!```text
 //! 3[4]..6[2]
 //!```
-//! Refers to all the bits from bit 4 in word 3 to bit 2 in word 6.
+//! Refers to all the bits from bit 4 in word 3 to bit 2 in word 6, i.e. bits 4 to 7 of word 3,
+//! all of words 4 and 5 and bits 0 to 2 of word 6.
 //!
 //!
 //! As before an empty bit range refers to the whole word:
@@ -90,6 +91,14 @@
 //! Here the consective bits in words 3 to 5 as well as the first 4 bits in word 6. This covers
 //! cases where, for instance, a number is specified is less than a multiple of the word size.
 //!
+//! A range of whole words can also be given by its first word and the number of words:
+//!```text
+//! 3[]*4 == 3[]..6[]
+//!```
+//! For instance, a u32 in 8 bit words starting at word 3. This can be used wherever a word range
+//! can, including in repeats such as `3[]*2;(0[])<=10`. Both forms give the same bit spec, which
+//! is displayed with its last word, i.e. as `3[]..6[]`.
+//!
 //! ## Repeating Words
 //!
 //! To specify that as word repeats there are a number of options:
@@ -127,6 +136,12 @@
 //! 3[];(2[])<=48
 //!```
 //!
+//! Instead of its bits, the word containing the number of repeats can also be referred to by its
+//! symbol, i.e. a label or, when used in ddgen, the name of a field:
+//!```text
+//! 3[];(n_values)<=48
+//!```
+//!
 //! ### Variable Number of Repeats
 //! The number of words is variable and only a limit is known.
 //!
@@ -161,6 +176,16 @@
 //! 5[0b1101_0001]
 //! ```
 //!
+//! ## Labels and Following Bit Specs
+//! The position of a word after a dependent repeat is not fixed, as it depends on the number of
+//! repeats. A bit spec can be given a label and other bit specs can then follow it:
+//! ```text
+//! stations:2[]..3[];(1[])<=20
+//! (>stations)[0..3]
+//! ```
+//! The follows index `(>stations)` takes the place of the word index. Any word indexes after it,
+//! such as the `1` in `(>stations)[]..1[]`, are relative to the first word after the followed bit spec.
+//!
 //! # Example Code
 //! ```
 //! use bit_lang::{BitRange, BitSpec,  Repeat, Word};
@@ -173,20 +198,26 @@
 //!     assert_eq!( bit_spec.start.bit_range, BitRange::Range(3,7));
 //! }
 //! ```
+//!
+//! A bit spec that cannot be parsed gives an [`Error`] with the character position at which
+//! parsing stopped and what was expected there:
+//! ```
+//! let err = bit_lang::parse("3[0..5").unwrap_err();
+//!
+//! assert_eq!(err.position, 6);
+//! assert_eq!(err.expected, "`]`");
+//! ```
+//!
+//! Displaying a [`BitSpec`] gives its canonical form, which parses back to the same bit spec:
+//! ```
+//! let bit_spec = bit_lang::parse("[3];(n_items)<10").unwrap();
+//!
+//! assert_eq!(bit_spec.to_string(), "3;(n_items)<=9");
+//! assert_eq!(bit_lang::parse("3;(n_items)<=9").unwrap(), bit_spec);
+//! ```
 
 // TODO:
 // - Provide a function that can be directly used with #[serde(deserialize_with = "??")]
-// - The user needs to calculate the position of a variable word which can be complicated, espacially
-//   if this is after a varaible repeatiing group. A posible solution would be to to assign
-//   a symbolic name to a bit spec and then have a notation that the position of the word(group) is
-//   after that. Prosposal example:
-//       frequencies: 4[]..5[];(3[])<20
-//       station_count: 0[];after frequencies
-//       [0]..1[];(station_count)<10 after station_count
-//   The positions are adapted based on what was before (this raises the question as to why
-//   use abolution positions at all?).
-//   If using something like ddgen then the symbolic names do not need to be specified, but
-//   are the same as the field name..#[allow(dead_code)]
 // TODO
 // Update the docs to include the new variable repeats
 
@@ -195,29 +226,51 @@ pub mod parser;
 
 use std::fmt::Display;
 
-pub use bit_spec::{BitRange, BitSpec, Repeat, Word};
+pub use bit_spec::{BitRange, BitSpec, Repeat, RepeatCount, Word};
 
+/// An error in a bit specification, giving where in the bit spec it was found and what
+/// was expected there.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Error {
-    ParseError,
-    IllFormed,
+pub struct Error {
+    /// The character offset in the bit spec at which the error was found.
+    pub position: usize,
+    /// A description of what was expected at `position`.
+    pub expected: String,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error in bit specification")
+        write!(
+            f,
+            "Error in bit specification at position {}: expected {}",
+            self.position, self.expected
+        )
     }
 }
 
 /// Parse the bit-lang specification and return a BitSpec.
 pub fn parse(bit_spec_string: &str) -> Result<BitSpec, Error> {
-    let (remaining, bit_spec) = parser::bit_spec(bit_spec_string).map_err(|_| Error::ParseError)?;
-
-    // All characters in the bit spec shoudl have been consumed
+    let position = |remaining: &str| bit_spec_string.chars().count() - remaining.chars().count();
+
+    let (remaining, bit_spec) = parser::bit_spec(bit_spec_string).map_err(|err| match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => Error {
+            position: position(err.remaining),
+            expected: err.expected.unwrap_or("a word").to_string(),
+        },
+        nom::Err::Incomplete(_) => Error {
+            position: bit_spec_string.chars().count(),
+            expected: "more input".to_string(),
+        },
+    })?;
+
+    // All characters in the bit spec should have been consumed
     if remaining.is_empty() {
         Ok(bit_spec)
     } else {
-        Err(Error::IllFormed)
+        Err(Error {
+            position: position(remaining),
+            expected: "the end of the bit spec".to_string(),
+        })
     }
 }
 
@@ -236,6 +289,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -248,6 +303,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -260,6 +317,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -272,6 +331,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -284,6 +345,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
     }
@@ -304,6 +367,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::Fixed { number: 48 },
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
     }
@@ -313,13 +378,15 @@ mod tests {
         let data = "4[]..7[];(3[])<49";
         let bit_spec = parse(data).unwrap();
         let expected_repeat = Repeat::Dependent {
-            bit_spec: Box::new(BitSpec {
+            count: RepeatCount::from(BitSpec {
                 start: Word {
                     index: 3,
                     bit_range: BitRange::WholeWord,
                 },
                 end: None,
                 repeat: Repeat::None,
+                label: None,
+                follows: None,
             }),
             limit: 48,
         };
@@ -333,6 +400,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: expected_repeat,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
     }
@@ -353,6 +422,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -368,6 +439,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -383,6 +456,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -395,6 +470,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
 
@@ -410,6 +487,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(bit_spec, expected);
     }
@@ -457,4 +536,34 @@ mod tests {
 
         assert!(parse("3[];(2[])10").is_err());
     }
+
+    #[test]
+    fn test_error_position() {
+        let expect = |position: usize, expected: &str| Error {
+            position,
+            expected: expected.to_string(),
+        };
+
+        assert_eq!(
+            parse("3[];(2[])10"),
+            Err(expect(9, "a condition (`<` or `<=`)"))
+        );
+        assert_eq!(parse("3[0..5"), Err(expect(6, "`]`")));
+        assert_eq!(parse("3[]..4[0xBA"), Err(expect(11, "`]`")));
+        assert_eq!(parse("3[]..;5"), Err(expect(5, "a word")));
+        assert_eq!(parse("3[];"), Err(expect(4, "a repeat")));
+        assert_eq!(parse("3[];(2[]<4"), Err(expect(8, "`)`")));
+        assert_eq!(parse("3[];(2[])<"), Err(expect(10, "a limit")));
+        assert_eq!(parse("3[];<0"), Err(expect(5, "a limit above 0")));
+        assert_eq!(parse("(>)[]"), Err(expect(2, "a symbol")));
+        assert_eq!(parse("(>count)5"), Err(expect(8, "`[`")));
+        assert_eq!(parse("3[0..2]*2"), Err(expect(0, "a whole word to multiply")));
+        assert_eq!(parse("3[]x"), Err(expect(3, "the end of the bit spec")));
+        assert_eq!(parse("abcd"), Err(expect(0, "a word")));
+
+        assert_eq!(
+            parse("3[0..5").unwrap_err().to_string(),
+            "Error in bit specification at position 6: expected `]`"
+        );
+    }
 }
@@ -5,14 +5,56 @@ use nom::{
     bytes::complete::tag,
     character::complete::u16 as u16_parser,
     //character::complete::u8 as u8_parser,
-    character::complete::{char, one_of},
-    combinator::{map, opt, recognize, value},
+    character::complete::{alpha1, alphanumeric1, char, one_of},
+    combinator::{cut, map, opt, peek, recognize, value, verify},
+    error::{context, ContextError, ErrorKind, ParseError},
     multi::{many0, many1},
     //number::complete::{i32, u8},
-    sequence::{delimited, preceded, separated_pair, tuple},
-    IResult,
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    InputLength,
 };
 
+/// Why a bit spec could not be parsed. Of the alternatives tried, the error that got
+/// furthest into the bit spec is kept as it is the most likely to be what was meant.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError<I> {
+    /// The part of the bit spec that was not parsed.
+    pub remaining: I,
+    /// What was expected at the start of `remaining`, if known.
+    pub expected: Option<&'static str>,
+}
+
+impl<I: InputLength> ParseError<I> for SyntaxError<I> {
+    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
+        SyntaxError {
+            remaining: input,
+            expected: None,
+        }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        let (length, other_length) = (self.remaining.input_len(), other.remaining.input_len());
+        if other_length < length || (other_length == length && self.expected.is_none()) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<I> ContextError<I> for SyntaxError<I> {
+    fn add_context(_input: I, context: &'static str, mut other: Self) -> Self {
+        other.expected.get_or_insert(context);
+        other
+    }
+}
+
+type IResult<I, O> = nom::IResult<I, O, SyntaxError<I>>;
+
 // Parse functions follow ...
 
 fn index(input: &str) -> IResult<&str, u16> {
@@ -37,8 +79,12 @@ fn bit_range(input: &str) -> IResult<&str, BitRange> {
 }
 
 fn fully_qualified_word(input: &str) -> IResult<&str, Word> {
-    let (remaining, (index, _, bit_range, _)) =
-        tuple((opt(index), tag("["), opt(bit_range), tag("]")))(input)?;
+    let (remaining, (index, _, bit_range, _)) = tuple((
+        opt(index),
+        tag("["),
+        opt(bit_range),
+        cut(context("`]`", tag("]"))),
+    ))(input)?;
 
     let completed_bit_range = match bit_range {
         Some(bit_range) => bit_range,
@@ -73,7 +119,7 @@ fn literal_word(input: &str) -> IResult<&str, Word> {
     let (input, index) = opt(index)(input)?;
     let (input, _) = tag("[")(input)?;
     let (input, literal) = literal(input)?;
-    let (remaining, _) = tag("]")(input)?;
+    let (remaining, _) = cut(context("`]`", tag("]")))(input)?;
 
     Ok((
         remaining,
@@ -115,46 +161,53 @@ fn fixed_repeat(input: &str) -> IResult<&str, Repeat> {
 // }
 
 // <variable> ::= "(" (<bit_spec> | <symbol>) ")"
-// <symbol> currently not supported
-fn dependent_bit_spec(input: &str) -> IResult<&str, BitSpec> {
+fn repeat_count(input: &str) -> IResult<&str, RepeatCount> {
     // TODO see if  we can also use take_until() to solve ambiguity
-    let (remaining, bit_spec) = delimited(char('('), bit_spec, char(')'))(input)?;
-    Ok((remaining, bit_spec))
+    let (remaining, count) = delimited(
+        char('('),
+        cut(context(
+            "a bit spec or symbol",
+            alt((
+                map(bit_spec, RepeatCount::from),
+                map(symbol, RepeatCount::Symbol),
+            )),
+        )),
+        cut(context("`)`", char(')'))),
+    )(input)?;
+    Ok((remaining, count))
+}
+
+// limit = condition number
+// The limit is the largest number of repeats, so `<10` gives the same limit as `<=9`.
+fn limit(input: &str) -> IResult<&str, usize> {
+    let (input, condition) = condition(input)?;
+    let (remaining, limit) = cut(context("a limit", u16_parser))(input)?;
+
+    match condition {
+        Condition::Lte => Ok((remaining, limit.into())),
+        Condition::Lt if limit > 0 => Ok((remaining, (limit - 1).into())),
+        Condition::Lt => Err(nom::Err::Failure(SyntaxError {
+            remaining: input,
+            expected: Some("a limit above 0"),
+        })),
+    }
 }
 
 // variable_repeat = variable_word condition limit;
 fn dependent_repeat(input: &str) -> IResult<&str, Repeat> {
-    let (remaining, (bit_spec, condition, limit)) =
-        tuple((dependent_bit_spec, condition, u16_parser))(input)?;
+    let (remaining, (count, limit)) = pair(
+        repeat_count,
+        cut(context("a condition (`<` or `<=`)", limit)),
+    )(input)?;
 
-    let adjusted_limit = match condition {
-        Condition::Lte => limit,
-        Condition::Lt => limit - 1,
-    };
-    Ok((
-        remaining,
-        Repeat::Dependent {
-            bit_spec: Box::new(bit_spec),
-            //word,
-            limit: adjusted_limit.into(),
-        },
-    ))
+    Ok((remaining, Repeat::Dependent { count, limit }))
 }
 
 // Example:  3[];<100
 fn variable_repeat(input: &str) -> IResult<&str, Repeat> {
-    let (remaining, (condition, limit)) = tuple((condition, u16_parser))(input)?;
+    let (remaining, limit) = limit(input)?;
 
-    let adjusted_limit = match condition {
-        Condition::Lte => limit,
-        Condition::Lt => limit - 1,
-    };
-    Ok((
-        remaining,
-        Repeat::Variable {
-            limit: adjusted_limit.into(),
-        },
-    ))
+    Ok((remaining, Repeat::Variable { limit }))
 }
 
 // repeat = ";" (fixed_repeat  | variable_repeat)  ;
@@ -162,7 +215,10 @@ fn repeat(input: &str) -> IResult<&str, Repeat> {
     //let (remaining, (_, repeat)) = tuple((tag(";"), alt((variable_repeat, fixed_repeat))))(input)?;
     let (remaining, repeat) = preceded(
         tag(";"),
-        alt((dependent_repeat, variable_repeat, fixed_repeat)),
+        cut(context(
+            "a repeat",
+            alt((dependent_repeat, variable_repeat, fixed_repeat)),
+        )),
     )(input)?;
 
     Ok((remaining, repeat))
@@ -215,15 +271,125 @@ fn literal(input: &str) -> IResult<&str, String> {
     Ok((remaining, literal))
 }
 
+// symbol = (letter | "_") {letter | digit | "_"}
+fn symbol(input: &str) -> IResult<&str, String> {
+    let (remaining, symbol) = recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0(alt((alphanumeric1, tag("_")))),
+    ))(input)?;
+
+    Ok((remaining, symbol.to_string()))
+}
+
+// label = symbol ":"
+fn label(input: &str) -> IResult<&str, String> {
+    terminated(symbol, char(':'))(input)
+}
+
+// follows_index = "(>" symbol ")"
+fn follows_index(input: &str) -> IResult<&str, String> {
+    preceded(
+        tag("(>"),
+        cut(terminated(
+            context("a symbol", symbol),
+            context("`)`", char(')')),
+        )),
+    )(input)
+}
+
+// A word that follows another bit spec. Its position is only given by the followed
+// bit spec, so it cannot also have an index.
+fn following_word(input: &str) -> IResult<&str, (Option<String>, Word)> {
+    let (remaining, (follows, word)) = pair(
+        follows_index,
+        cut(context(
+            "`[`",
+            preceded(peek(char('[')), alt((literal_word, fully_qualified_word))),
+        )),
+    )(input)?;
+
+    Ok((remaining, (Some(follows), word)))
+}
+
+fn start_word(input: &str) -> IResult<&str, (Option<String>, Word)> {
+    alt((following_word, map(word, |word| (None, word))))(input)
+}
+
+// The end of a word range, either given as the last word or as the number of words
+enum RangeEnd {
+    Word(Word),
+    Words(u16),
+}
+
+// range_end = ".." word | "*" number
+fn range_end(input: &str) -> IResult<&str, RangeEnd> {
+    alt((
+        map(
+            preceded(tag(".."), cut(context("a word", word))),
+            RangeEnd::Word,
+        ),
+        map(
+            preceded(
+                char('*'),
+                cut(context(
+                    "a number of words",
+                    verify(u16_parser, |words| *words > 0),
+                )),
+            ),
+            RangeEnd::Words,
+        ),
+    ))(input)
+}
+
 // This is the top level parser
-// word_range = word [".." word] [repeat]
+// bit_spec = [label] (follows_index | word) [range_end] [repeat]
 pub fn bit_spec(input: &str) -> IResult<&str, BitSpec> {
-    let (remaining, (start, end, repeat)) =
-        //tuple((word, opt(preceded(tag(".."), word)), opt(repeat)))(input)?;
-        tuple((word, opt(preceded(tag(".."), word)), 
-               map(opt(repeat), |r| r.unwrap_or(Repeat::None))))(input)?;
+    let (input, label) = opt(label)(input)?;
+    let (after_start, (follows, start)) = context("a word", start_word)(input)?;
+    let (remaining, (end, repeat)) = pair(
+        opt(range_end),
+        map(opt(repeat), |r| r.unwrap_or(Repeat::None)),
+    )(after_start)?;
+
+    // A number of words is held as the last word of the range, so that `3[]*4` and
+    // `3[]..6[]` give the same bit spec
+    let end = match end {
+        None => None,
+        Some(RangeEnd::Word(word)) => Some(word),
+        // Only whole words can be multiplied
+        Some(RangeEnd::Words(words))
+            if start.bit_range == BitRange::WholeWord
+                && start.index + words as usize - 1 <= u16::MAX as usize =>
+        {
+            Some(Word {
+                index: start.index + words as usize - 1,
+                bit_range: BitRange::WholeWord,
+            })
+        }
+        Some(RangeEnd::Words(_)) if start.bit_range == BitRange::WholeWord => {
+            return Err(nom::Err::Failure(SyntaxError {
+                remaining: input,
+                expected: Some("a number of words that ends at a valid word index"),
+            }))
+        }
+        Some(RangeEnd::Words(_)) => {
+            return Err(nom::Err::Failure(SyntaxError {
+                remaining: input,
+                expected: Some("a whole word to multiply"),
+            }))
+        }
+    };
 
-    Ok((remaining, BitSpec { start, end, repeat }))
+    Ok((
+        remaining,
+        BitSpec {
+            start,
+            end,
+            repeat,
+            label,
+            follows,
+        },
+    ))
 }
 
 #[cfg(test)]
@@ -360,10 +526,12 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let expected = Repeat::Dependent {
-            bit_spec: Box::new(expected_dependent_bit_spec),
+            count: RepeatCount::from(expected_dependent_bit_spec),
             limit: 48,
         };
         assert_eq!(r, expected);
@@ -389,10 +557,12 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let expected = Repeat::Dependent {
-            bit_spec: Box::new(expected_dependent_bit_spec),
+            count: RepeatCount::from(expected_dependent_bit_spec),
             limit: 48,
         };
         assert_eq!(r, expected);
@@ -409,10 +579,12 @@ mod tests {
             },
             end: None,
             repeat: Repeat::Fixed { number: 2 },
+            label: None,
+            follows: None,
         };
 
         let expected = Repeat::Dependent {
-            bit_spec: Box::new(expected_dependent_bit_spec),
+            count: RepeatCount::from(expected_dependent_bit_spec),
             limit: 48,
         };
         assert_eq!(r, expected);
@@ -437,10 +609,12 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(
             Repeat::Dependent {
-                bit_spec: Box::new(expected_dependent_bit_spec),
+                count: RepeatCount::from(expected_dependent_bit_spec),
                 limit: 48
             }
             .to_string(),
@@ -460,10 +634,12 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(
             Repeat::Dependent {
-                bit_spec: Box::new(expected_dependent_bit_spec),
+                count: RepeatCount::from(expected_dependent_bit_spec),
                 limit: 48
             }
             .to_string(),
@@ -480,10 +656,12 @@ mod tests {
             },
             end: None,
             repeat: Repeat::Fixed { number: 2 },
+            label: None,
+            follows: None,
         };
         assert_eq!(
             Repeat::Dependent {
-                bit_spec: Box::new(expected_dependent_bit_spec),
+                count: RepeatCount::from(expected_dependent_bit_spec),
                 limit: 48
             }
             .to_string(),
@@ -503,10 +681,12 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let expected = Repeat::Dependent {
-            bit_spec: Box::new(dependent_bit_spec),
+            count: RepeatCount::from(dependent_bit_spec),
             limit: 48,
         };
         assert_eq!(r, expected);
@@ -523,15 +703,31 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
 
         let expected = Repeat::Dependent {
-            bit_spec: Box::new(expected_bit_spec),
+            count: RepeatCount::from(expected_bit_spec),
             limit: 48,
         };
         assert_eq!(r, expected);
     }
 
+    #[test]
+    fn test_dependent_repeat_with_symbol() {
+        let data = "(a_count)<7";
+        let (_, r) = dependent_repeat(data).unwrap();
+        let expected = Repeat::Dependent {
+            count: RepeatCount::Symbol("a_count".to_string()),
+            limit: 6,
+        };
+        assert_eq!(r, expected);
+
+        let (_, r) = bit_spec("1[]..2[];(n_stations)<=20").unwrap();
+        assert_eq!(r.to_string(), "1[]..2[];(n_stations)<=20");
+    }
+
     #[test]
     fn test_fixed_repeat() {
         let data = "48";
@@ -578,6 +774,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -590,6 +788,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -602,6 +802,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -614,6 +816,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -626,6 +830,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
     }
@@ -646,6 +852,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::Fixed { number: 48 },
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -653,13 +861,15 @@ mod tests {
         let (_, r) = bit_spec(data).unwrap();
 
         let repeat = Repeat::Dependent {
-            bit_spec: Box::new(BitSpec {
+            count: RepeatCount::from(BitSpec {
                 start: Word {
                     index: 3,
                     bit_range: BitRange::WholeWord,
                 },
                 end: None,
                 repeat: Repeat::None,
+                label: None,
+                follows: None,
             }),
             limit: 48,
         };
@@ -673,6 +883,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: repeat,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
     }
@@ -693,6 +905,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -708,6 +922,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -723,6 +939,8 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -735,6 +953,8 @@ mod tests {
             },
             end: None,
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
 
@@ -750,6 +970,8 @@ mod tests {
                 bit_range: BitRange::Range(0, 5),
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
         };
         assert_eq!(r, expected);
     }
@@ -769,8 +991,96 @@ mod tests {
                 bit_range: BitRange::WholeWord,
             }),
             repeat: Repeat::None,
+            label: None,
+            follows: None,
+        };
+        assert_eq!(r, expected);
+    }
+
+    #[test]
+    fn test_bit_spec_multiplied() {
+        let data = "3[]*4";
+        let (_, r) = bit_spec(data).unwrap();
+        let expected = BitSpec {
+            start: Word {
+                index: 3,
+                bit_range: BitRange::WholeWord,
+            },
+            end: Some(Word {
+                index: 6,
+                bit_range: BitRange::WholeWord,
+            }),
+            repeat: Repeat::None,
+            label: None,
+            follows: None,
+        };
+        assert_eq!(r, expected);
+        assert_eq!(r, bit_spec("3[]..6[]").unwrap().1);
+
+        let data = "[]*2;(0[]*2)<=8";
+        let (_, r) = bit_spec(data).unwrap();
+        assert_eq!(r.end.unwrap().index, 1);
+        let Repeat::Dependent {
+            count: RepeatCount::BitSpec(count),
+            limit: 8,
+        } = r.repeat
+        else {
+            panic!("Expected a dependent repeat")
+        };
+        assert_eq!(count.end.unwrap().index, 1);
+
+        let (_, r) = bit_spec("(>values)[]*4").unwrap();
+        assert_eq!(r.follows, Some("values".to_string()));
+        assert_eq!(r.end.unwrap().index, 3);
+
+        // Only whole words can be multiplied
+        assert!(bit_spec("3[0..3]*2").is_err());
+        assert!(crate::parse("3[]*0").is_err());
+        assert!(crate::parse("65535[]*2").is_err());
+    }
+
+    #[test]
+    fn test_bit_spec_with_label() {
+        let data = "stations:2[]..3[];(1[])<8";
+        let (_, r) = bit_spec(data).unwrap();
+        assert_eq!(r.label, Some("stations".to_string()));
+        assert_eq!(r.follows, None);
+        assert_eq!(r.start.index, 2);
+        assert_eq!(r.end.unwrap().index, 3);
+
+        // Symbols cannot start with a digit
+        assert!(crate::parse("1stations:0[]").is_err());
+    }
+
+    #[test]
+    fn test_bit_spec_following() {
+        let data = "(>stations)[]";
+        let (_, r) = bit_spec(data).unwrap();
+        let expected = BitSpec {
+            start: Word {
+                index: 0,
+                bit_range: BitRange::WholeWord,
+            },
+            end: None,
+            repeat: Repeat::None,
+            label: None,
+            follows: Some("stations".to_string()),
         };
         assert_eq!(r, expected);
+
+        let data = "count:(>stations)[2..5]";
+        let (_, r) = bit_spec(data).unwrap();
+        assert_eq!(r.label, Some("count".to_string()));
+        assert_eq!(r.follows, Some("stations".to_string()));
+        assert_eq!(r.start.bit_range, BitRange::Range(2, 5));
+
+        let data = "(>count)[]..1[];(0[])<10";
+        let (_, r) = bit_spec(data).unwrap();
+        assert_eq!(r.follows, Some("count".to_string()));
+        assert_eq!(r.end.unwrap().index, 1);
+
+        // The position is given by the followed bit spec, so no index is allowed
+        assert!(crate::parse("(>stations)3[]").is_err());
     }
 
     #[test]
@@ -3,7 +3,7 @@
 pub trait Bits {
     fn bit(&self, position: usize) -> bool;
 
-    fn field(&self, start: usize, end: usize) -> u8;
+    fn field(&self, start: usize, end: usize) -> Self;
 
     fn modify_bit(&mut self, position: usize, state: bool);
 
@@ -11,52 +11,81 @@ pub trait Bits {
     ///
     /// Warning: Attempting to modify the whole word or having end less then start
     /// will cause the function to panic!
-    fn modify_field(&mut self, value: u8, start: usize, end: usize);
+    fn modify_field(&mut self, value: Self, start: usize, end: usize);
+
+    /// The bits from `low` to `high`, which can also be the whole word. Used for fields
+    /// spanning more than one word.
+    fn spanned_bits(&self, low: usize, high: usize) -> u64;
+
+    /// Set the bits from `low` to `high` to the lowest bits of `bits` and return the
+    /// bits that have not been used. Used for fields spanning more than one word.
+    fn modify_spanned_bits(&mut self, bits: u64, low: usize, high: usize) -> u64;
 
     // Get a boolean from a bit position
     //fn deserialize_bit(&mut self, source: u8, position: usize);
 }
 
-impl Bits for u8 {
-    /// Get a boolean from a bit position
-    fn bit(&self, position: usize) -> bool {
-        let mask: u8 = 1 << position;
-        (self & mask) > 0
-    }
-
-    fn field(&self, start: usize, end: usize) -> u8 {
-        let mut mask: u8 = 0;
-
-        for count in start..=end {
-            let b = 1 << count;
-            mask |= b;
-        }
-
-        let v = self & mask;
-        v >> start
-    }
-
-    fn modify_bit(&mut self, position: usize, state: bool) {
-        let mut mask: u8 = 1 << position;
-
-        if state {
-            // setting the bit
-            *self |= mask
-        } else {
-            // clear the bit{
-            mask = !mask;
-            *self &= mask
-        };
-    }
-
-    fn modify_field(&mut self, value: u8, start: usize, end: usize) {
-        let mask = ((1 << (end - start + 1)) - 1) << start;
-        let cleared_bits = *self & !mask;
-        let new_bits = value << start;
-        *self = cleared_bits | new_bits;
-    }
+// The bit manipulation is the same for all the word sizes supported.
+macro_rules! impl_bits {
+    ($($word:ty),*) => {
+        $(
+            impl Bits for $word {
+                /// Get a boolean from a bit position
+                fn bit(&self, position: usize) -> bool {
+                    let mask: $word = 1 << position;
+                    (self & mask) > 0
+                }
+
+                fn field(&self, start: usize, end: usize) -> $word {
+                    let mut mask: $word = 0;
+
+                    for count in start..=end {
+                        let b = 1 << count;
+                        mask |= b;
+                    }
+
+                    let v = self & mask;
+                    v >> start
+                }
+
+                fn modify_bit(&mut self, position: usize, state: bool) {
+                    let mut mask: $word = 1 << position;
+
+                    if state {
+                        // setting the bit
+                        *self |= mask
+                    } else {
+                        // clear the bit{
+                        mask = !mask;
+                        *self &= mask
+                    };
+                }
+
+                fn modify_field(&mut self, value: $word, start: usize, end: usize) {
+                    let mask = ((1 << (end - start + 1)) - 1) << start;
+                    let cleared_bits = *self & !mask;
+                    let new_bits = value << start;
+                    *self = cleared_bits | new_bits;
+                }
+
+                fn spanned_bits(&self, low: usize, high: usize) -> u64 {
+                    let mask = <$word>::MAX >> (<$word>::BITS as usize - (high - low + 1));
+                    ((self >> low) & mask) as u64
+                }
+
+                fn modify_spanned_bits(&mut self, bits: u64, low: usize, high: usize) -> u64 {
+                    let width = high - low + 1;
+                    let mask = <$word>::MAX >> (<$word>::BITS as usize - width);
+                    *self = (*self & !(mask << low)) | ((bits as $word & mask) << low);
+                    bits.checked_shr(width as u32).unwrap_or(0)
+                }
+            }
+        )*
+    };
 }
 
+impl_bits!(u8, u16, u32);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +129,60 @@ mod tests {
         source.modify_field(0b010, 0, 2);
         assert_eq!(source, 0b1111_0010);
     }
+
+    #[test]
+    fn bit_u16() {
+        let source: u16 = 0b1000_0001_0011_0111;
+
+        assert!(source.bit(0));
+        assert!(!source.bit(3));
+        assert!(source.bit(8));
+        assert!(source.bit(15));
+    }
+
+    #[test]
+    fn field_u16() {
+        let source: u16 = 0b1010_1100_0011_0111;
+
+        assert_eq!(0b1010_1100, source.field(8, 15));
+        assert_eq!(0b1100_0011, source.field(4, 11));
+    }
+
+    #[test]
+    fn modify_field_u16() {
+        let mut source: u16 = 0;
+
+        source.modify_field(0b1_1111_1111, 4, 12);
+        assert_eq!(source, 0b0001_1111_1111_0000);
+        source.modify_bit(15, true);
+        assert_eq!(source, 0b1001_1111_1111_0000);
+    }
+
+    #[test]
+    fn modify_field_u32() {
+        let mut source: u32 = 0xFFFF_FFFF;
+
+        source.modify_field(0, 8, 23);
+        assert_eq!(source, 0xFF00_00FF);
+        assert_eq!(0xFF, source.field(24, 31));
+    }
+
+    #[test]
+    fn spanned_bits() {
+        let mut source: u8 = 0b1010_0110;
+
+        assert_eq!(0b1010, source.spanned_bits(4, 7));
+        assert_eq!(0b1010_0110, source.spanned_bits(0, 7));
+
+        let left = source.modify_spanned_bits(0b1_1001, 4, 7);
+        assert_eq!(source, 0b1001_0110);
+        assert_eq!(left, 0b1);
+        let left = source.modify_spanned_bits(0x1234, 0, 7);
+        assert_eq!(source, 0x34);
+        assert_eq!(left, 0x12);
+
+        let mut source: u32 = 0;
+        assert_eq!(source.modify_spanned_bits(u64::MAX, 0, 31), 0xFFFF_FFFF);
+        assert_eq!(source, 0xFFFF_FFFF);
+    }
 }
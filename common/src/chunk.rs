@@ -0,0 +1,62 @@
+/// Up to `N` words of a larger transfer that is sent over several invocations of a
+/// command. The chunk is the provider for the data field of commands with chunked
+/// flow control.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "W: serde::Serialize",
+        deserialize = "W: serde::Deserialize<'de> + Copy + Default"
+    ))
+)]
+pub struct Chunk<W, const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    words: [W; N],
+    len: usize,
+    position: usize,
+}
+
+impl<W: Copy + Default, const N: usize> Chunk<W, N> {
+    /// Takes the next `N` words, or all the words that are left, from `data`.
+    pub fn fill(data: &mut impl Iterator<Item = W>) -> Self {
+        let mut chunk = Self::default();
+        for (word, value) in chunk.words.iter_mut().zip(data) {
+            *word = value;
+            chunk.len += 1;
+        }
+        chunk
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<W: Copy + Default, const N: usize> Default for Chunk<W, N> {
+    fn default() -> Self {
+        Self {
+            words: [W::default(); N],
+            len: 0,
+            position: 0,
+        }
+    }
+}
+
+impl<W: Copy, const N: usize> Iterator for Chunk<W, N> {
+    type Item = W;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position < self.len {
+            self.position += 1;
+            Some(self.words[self.position - 1])
+        } else {
+            None
+        }
+    }
+}
@@ -4,7 +4,15 @@ use crate::error::DeviceError;
 //     fn deserialize(&self) -> Result<T, DeviceError>;
 // }
 
-pub trait Deserialize<T> {
+/// W is the type of the device word, e.g. `u16` for a device with 16 bit words.
+pub trait Deserialize<T, W = u8> {
     //fn deserialize_old(&self) -> Result<T, DeviceError>;
-    fn deserialize(buf: &[u8]) -> Result<T, DeviceError>;
+    fn deserialize(buf: &[W]) -> Result<T, DeviceError>;
+}
+
+/// The response of commands that do not have one. Nothing is read for it.
+impl<W> Deserialize<(), W> for () {
+    fn deserialize(_buf: &[W]) -> Result<(), DeviceError> {
+        Ok(())
+    }
 }
@@ -1,8 +1,36 @@
+use core::convert::Infallible;
+
+/// `H` is the header of a command whose header can report errors from the device
+/// itself. For all other commands `H` is `Infallible`, so `Device` cannot occur.
 #[derive(Copy, Clone, Debug)]
-pub enum DeviceError {
+pub enum DeviceError<H = Infallible> {
     EnumConversion,
     BitPositionOutOfRange,
     CsAssert,
     Transmit,
     Receive,
+    OutOfRange,
+    Timeout,
+    NotReady,
+    /// The device reported an error in the header of its response
+    Device {
+        header: H,
+    },
+}
+
+impl DeviceError {
+    /// Converts the error for use by a command with the header `H`.
+    pub fn with_header<H>(self) -> DeviceError<H> {
+        match self {
+            DeviceError::EnumConversion => DeviceError::EnumConversion,
+            DeviceError::BitPositionOutOfRange => DeviceError::BitPositionOutOfRange,
+            DeviceError::CsAssert => DeviceError::CsAssert,
+            DeviceError::Transmit => DeviceError::Transmit,
+            DeviceError::Receive => DeviceError::Receive,
+            DeviceError::OutOfRange => DeviceError::OutOfRange,
+            DeviceError::Timeout => DeviceError::Timeout,
+            DeviceError::NotReady => DeviceError::NotReady,
+            DeviceError::Device { header } => match header {},
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{I2c, Operation};
+
+use crate::{
+    command::Command,
+    deserialize::Deserialize,
+    poll::{PollLimit, Unbounded},
+    ready_pin::WaitReady,
+    serialize::Serialize,
+    DeviceError,
+};
+
+/// The I2C counterpart of `Transmit`. Requests and responses are serialized
+/// in the same way, only the transport is different. I2C is byte orientated
+/// so the device words are always `u8`.
+pub trait I2cTransmit<I2C, RESP>: Serialize + Command
+where
+    I2C: I2c,
+    RESP: Deserialize<RESP>,
+{
+    fn transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize>(
+        &self,
+        i2c: &mut I2C,
+        address: u8,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [u8; 1] = [self.opcode()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+        let mut response_buf = [0_u8; RESP_MAX_LEN];
+
+        // A read of zero bytes is not possible on an I2C bus, so
+        // commands without a response are only written.
+        if RESP_MAX_LEN == 0 {
+            i2c.transaction(
+                address,
+                &mut [Operation::Write(&opcode), Operation::Write(&data[0..size])],
+            )
+        } else {
+            i2c.transaction(
+                address,
+                &mut [
+                    Operation::Write(&opcode),
+                    Operation::Write(&data[0..size]),
+                    Operation::Read(&mut response_buf),
+                ],
+            )
+        }
+        .map_err(|_| DeviceError::Transmit)?;
+
+        let response = RESP::deserialize(&response_buf[0..])?;
+
+        Ok(response)
+    }
+
+    fn timed_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize, DELAY: DelayNs>(
+        &self,
+        i2c: &mut I2C,
+        address: u8,
+        delay: &mut DELAY,
+        delay_us: u32,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [u8; 1] = [self.opcode()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [0_u8; RESP_MAX_LEN];
+
+        i2c.transaction(
+            address,
+            &mut [Operation::Write(&opcode), Operation::Write(&data[0..size])],
+        )
+        .map_err(|_| DeviceError::Transmit)?;
+
+        delay.delay_us(delay_us);
+
+        if RESP_MAX_LEN > 0 {
+            i2c.read(address, &mut response_buf)
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    fn ready_pin_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize, READY: WaitReady>(
+        &self,
+        i2c: &mut I2C,
+        address: u8,
+        ready: &mut READY,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [u8; 1] = [self.opcode()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [0_u8; RESP_MAX_LEN];
+
+        i2c.transaction(
+            address,
+            &mut [Operation::Write(&opcode), Operation::Write(&data[0..size])],
+        )
+        .map_err(|_| DeviceError::Transmit)?;
+
+        ready.wait()?;
+
+        if RESP_MAX_LEN > 0 {
+            i2c.read(address, &mut response_buf)
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    fn polled_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        HEADER: Deserialize<HEADER>,
+        const STATUS_HEADER_LEN: usize,
+    >(
+        &self,
+        i2c: &mut I2C,
+        address: u8,
+        status_fn: fn(HEADER) -> bool,
+    ) -> Result<RESP, DeviceError> {
+        self.limited_polled_transmit::<REQ_MAX_LEN, RESP_MAX_LEN, HEADER, STATUS_HEADER_LEN, _>(
+            i2c,
+            address,
+            status_fn,
+            &mut Unbounded,
+        )
+    }
+
+    fn limited_polled_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        HEADER: Deserialize<HEADER>,
+        const STATUS_HEADER_LEN: usize,
+        LIMIT: PollLimit,
+    >(
+        &self,
+        i2c: &mut I2C,
+        address: u8,
+        status_fn: fn(HEADER) -> bool,
+        limit: &mut LIMIT,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [u8; 1] = [self.opcode()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [0_u8; RESP_MAX_LEN];
+
+        // Write the request and read the first header
+        i2c.transaction(
+            address,
+            &mut [
+                Operation::Write(&opcode),
+                Operation::Write(&data[0..size]),
+                Operation::Read(&mut response_buf[0..STATUS_HEADER_LEN]),
+            ],
+        )
+        .map_err(|_| DeviceError::Transmit)?;
+
+        loop {
+            let header = HEADER::deserialize(&response_buf[0..STATUS_HEADER_LEN])
+                .map_err(|_| DeviceError::Receive)?;
+
+            if status_fn(header) {
+                // Each I2C read starts again with the status header, so the whole response
+                // is read once the header shows that it is ready
+                if RESP_MAX_LEN > STATUS_HEADER_LEN {
+                    i2c.read(address, &mut response_buf)
+                        .map_err(|_| DeviceError::Receive)?;
+                }
+                break;
+            } else {
+                // Repeat the read
+                limit.next_poll()?;
+                i2c.read(address, &mut response_buf[0..STATUS_HEADER_LEN])
+                    .map_err(|_| DeviceError::Receive)?;
+            }
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+}
@@ -1,11 +1,21 @@
 pub mod bits;
+pub mod chunk;
 pub mod command;
 pub mod deserialize;
 pub mod error;
+pub mod i2c_transmit;
+pub mod poll;
+pub mod ranged;
+pub mod ready_pin;
+pub mod register;
 pub mod request;
 pub mod response;
+pub mod scaled;
+#[cfg(feature = "serde")]
+pub mod serde_array;
 pub mod serialize;
 pub mod transmit;
+pub mod transmit_async;
 
 pub use crate::error::DeviceError;
 
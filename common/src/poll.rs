@@ -0,0 +1,87 @@
+use embedded_hal::delay::DelayNs;
+
+use crate::DeviceError;
+
+/// Limits how often the status header of a polled command is read.
+pub trait PollLimit {
+    /// Called before each repeated read of the status header. Returns
+    /// `DeviceError::Timeout` once the limit has been reached.
+    fn next_poll(&mut self) -> Result<(), DeviceError>;
+}
+
+/// Polls for as long as the device takes.
+pub struct Unbounded;
+
+impl PollLimit for Unbounded {
+    fn next_poll(&mut self) -> Result<(), DeviceError> {
+        Ok(())
+    }
+}
+
+/// Reads the status header at most `max_polls` times.
+pub struct MaxPolls {
+    polls: u32,
+    max_polls: u32,
+}
+
+impl MaxPolls {
+    pub fn new(max_polls: u32) -> Self {
+        // The first read of the header is done together with the request
+        Self {
+            polls: 1,
+            max_polls,
+        }
+    }
+}
+
+impl PollLimit for MaxPolls {
+    fn next_poll(&mut self) -> Result<(), DeviceError> {
+        if self.polls >= self.max_polls {
+            return Err(DeviceError::Timeout);
+        }
+        self.polls += 1;
+        Ok(())
+    }
+}
+
+/// Waits `poll_interval_us` microseconds between reads of the status header and
+/// gives up once `timeout_us` microseconds have been waited.
+pub struct PollTimeout<'a, DELAY> {
+    delay: &'a mut DELAY,
+    poll_interval_us: u32,
+    timeout_us: u32,
+    waited_us: u32,
+}
+
+impl<'a, DELAY> PollTimeout<'a, DELAY> {
+    pub fn new(delay: &'a mut DELAY, poll_interval_us: u32, timeout_us: u32) -> Self {
+        Self {
+            delay,
+            poll_interval_us,
+            timeout_us,
+            waited_us: 0,
+        }
+    }
+
+    /// Counts the next interval as waited, or returns `DeviceError::Timeout` if
+    /// that would take longer than the timeout.
+    pub fn start_interval(&mut self) -> Result<u32, DeviceError> {
+        if self.waited_us >= self.timeout_us {
+            return Err(DeviceError::Timeout);
+        }
+        self.waited_us = self.waited_us.saturating_add(self.poll_interval_us);
+        Ok(self.poll_interval_us)
+    }
+
+    pub fn delay(&mut self) -> &mut DELAY {
+        self.delay
+    }
+}
+
+impl<DELAY: DelayNs> PollLimit for PollTimeout<'_, DELAY> {
+    fn next_poll(&mut self) -> Result<(), DeviceError> {
+        let interval_us = self.start_interval()?;
+        self.delay.delay_us(interval_us);
+        Ok(())
+    }
+}
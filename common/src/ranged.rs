@@ -0,0 +1,56 @@
+use crate::DeviceError;
+
+/// A value that is limited to the range `MIN..=MAX`. Used for fields that are not
+/// enumerations, but still only accept some of the values their bits could hold.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct Ranged<T, const MIN: i128, const MAX: i128>(T);
+
+impl<T, const MIN: i128, const MAX: i128> Ranged<T, MIN, MAX>
+where
+    T: Copy + Into<i128>,
+{
+    pub const MIN: i128 = MIN;
+    pub const MAX: i128 = MAX;
+
+    /// Returns `DeviceError::OutOfRange` if the value is outside of the range.
+    pub fn new(value: T) -> Result<Self, DeviceError> {
+        if (MIN..=MAX).contains(&value.into()) {
+            Ok(Self(value))
+        } else {
+            Err(DeviceError::OutOfRange)
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.0
+    }
+}
+
+impl<T, const MIN: i128, const MAX: i128> Default for Ranged<T, MIN, MAX>
+where
+    T: TryFrom<i128>,
+{
+    /// The lowest value in the range.
+    fn default() -> Self {
+        match T::try_from(MIN) {
+            Ok(value) => Self(value),
+            Err(_) => panic!("The minimum of the range does not fit into the value type"),
+        }
+    }
+}
+
+/// Values are checked against the range, as for values read from the device.
+#[cfg(feature = "serde")]
+impl<'de, T, const MIN: i128, const MAX: i128> serde::Deserialize<'de> for Ranged<T, MIN, MAX>
+where
+    T: Copy + Into<i128> + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = T::deserialize(deserializer)?;
+        Self::new(value).map_err(|_| {
+            serde::de::Error::custom(format_args!("value is outside of the range {MIN}..={MAX}"))
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::InputPin;
+
+use crate::DeviceError;
+
+/// How often the ready pin is read when waiting with a timeout.
+pub const POLL_INTERVAL_US: u32 = 10;
+
+/// The level of the ready pin when the device is ready.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ActiveLevel {
+    High,
+    Low,
+}
+
+/// Waits until the device signals that its response is ready.
+pub trait WaitReady {
+    fn wait(&mut self) -> Result<(), DeviceError>;
+}
+
+/// A ready pin that is waited on for as long as the device takes.
+pub struct ReadyPin<'a, PIN> {
+    pin: &'a mut PIN,
+    active: ActiveLevel,
+}
+
+impl<'a, PIN: InputPin> ReadyPin<'a, PIN> {
+    pub fn new(pin: &'a mut PIN, active: ActiveLevel) -> Self {
+        Self { pin, active }
+    }
+
+    pub fn is_ready(&mut self) -> Result<bool, DeviceError> {
+        is_active(self.pin, self.active)
+    }
+
+    pub fn active(&self) -> ActiveLevel {
+        self.active
+    }
+
+    pub fn pin(&mut self) -> &mut PIN {
+        self.pin
+    }
+}
+
+impl<PIN: InputPin> WaitReady for ReadyPin<'_, PIN> {
+    fn wait(&mut self) -> Result<(), DeviceError> {
+        while !self.is_ready()? {}
+        Ok(())
+    }
+}
+
+/// A ready pin that returns `DeviceError::Timeout` if the device is not ready
+/// within `timeout_us` microseconds.
+pub struct ReadyPinWithTimeout<'a, PIN, DELAY> {
+    pin: &'a mut PIN,
+    active: ActiveLevel,
+    delay: &'a mut DELAY,
+    timeout_us: u32,
+}
+
+impl<'a, PIN: InputPin, DELAY> ReadyPinWithTimeout<'a, PIN, DELAY> {
+    pub fn new(
+        pin: &'a mut PIN,
+        active: ActiveLevel,
+        delay: &'a mut DELAY,
+        timeout_us: u32,
+    ) -> Self {
+        Self {
+            pin,
+            active,
+            delay,
+            timeout_us,
+        }
+    }
+
+    pub fn is_ready(&mut self) -> Result<bool, DeviceError> {
+        is_active(self.pin, self.active)
+    }
+
+    pub fn timeout_us(&self) -> u32 {
+        self.timeout_us
+    }
+
+    pub fn delay(&mut self) -> &mut DELAY {
+        self.delay
+    }
+}
+
+impl<PIN: InputPin, DELAY: DelayNs> WaitReady for ReadyPinWithTimeout<'_, PIN, DELAY> {
+    fn wait(&mut self) -> Result<(), DeviceError> {
+        let mut waited_us = 0;
+        while !self.is_ready()? {
+            if waited_us >= self.timeout_us {
+                return Err(DeviceError::Timeout);
+            }
+            self.delay.delay_us(POLL_INTERVAL_US);
+            waited_us += POLL_INTERVAL_US;
+        }
+        Ok(())
+    }
+}
+
+fn is_active<PIN: InputPin>(pin: &mut PIN, active: ActiveLevel) -> Result<bool, DeviceError> {
+    match active {
+        ActiveLevel::High => pin.is_high(),
+        ActiveLevel::Low => pin.is_low(),
+    }
+    .map_err(|_| DeviceError::Receive)
+}
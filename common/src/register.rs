@@ -0,0 +1,51 @@
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    command::Command, deserialize::Deserialize, i2c_transmit::I2cTransmit, serialize::Serialize,
+    transmit::Transmit, DeviceError,
+};
+
+/// Request to read a register. Only the register address is sent, the register
+/// itself is the response.
+pub struct RegisterRead {
+    address: u8,
+}
+
+impl RegisterRead {
+    pub fn new(address: u8) -> Self {
+        Self { address }
+    }
+}
+
+impl Command for RegisterRead {
+    fn opcode(&self) -> u8 {
+        self.address
+    }
+}
+
+impl<W: Copy + Default> Serialize<W> for RegisterRead {
+    fn serialize<const LEN: usize>(&self) -> (usize, [W; LEN], impl Iterator<Item = W>) {
+        (0, [W::default(); LEN], core::iter::empty::<W>())
+    }
+}
+
+impl<SPI, RESP, W> Transmit<SPI, RESP, W> for RegisterRead
+where
+    SPI: SpiDevice<W>,
+    RESP: Deserialize<RESP, W>,
+    W: Copy + Default + From<u8> + 'static,
+{
+}
+
+impl<I2C: I2c, RESP: Deserialize<RESP>> I2cTransmit<I2C, RESP> for RegisterRead {}
+
+/// The response to a register write. Nothing is read back from the device.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RegisterWritten;
+
+impl<W> Deserialize<Self, W> for RegisterWritten {
+    fn deserialize(_buf: &[W]) -> Result<Self, DeviceError> {
+        Ok(Self)
+    }
+}
@@ -4,25 +4,40 @@ pub trait RequestBit {
     fn serialize_bit(&mut self, source: bool, position: usize);
 }
 
-impl RequestBit for u8 {
-    fn serialize_bit(&mut self, source: bool, position: usize) {
-        self.modify_bit(position, source);
-    }
-}
-
 pub trait RequestField {
-    fn serialize_field(&mut self, source: u8, start: usize, end: usize);
+    fn serialize_field(&mut self, source: Self, start: usize, end: usize);
 }
 
-impl RequestField for u8 {
-    fn serialize_field(&mut self, source: u8, start: usize, end: usize) {
-        self.modify_field(source, start, end);
-    }
+macro_rules! impl_request_bits {
+    ($($word:ty),*) => {
+        $(
+            impl RequestBit for $word {
+                fn serialize_bit(&mut self, source: bool, position: usize) {
+                    self.modify_bit(position, source);
+                }
+            }
+
+            impl RequestField for $word {
+                fn serialize_field(&mut self, source: $word, start: usize, end: usize) {
+                    self.modify_field(source, start, end);
+                }
+            }
+        )*
+    };
 }
 
+impl_request_bits!(u8, u16, u32);
+
 pub trait RequestWord<T> {
     //data[1..2].serialize_word(self.a_u16);
     fn serialize_word(&mut self, source: T);
+
+    /// Serialize a word in big endian order. Values that fit
+    /// into a single word have no byte order so, by default,
+    /// this is the same as `serialize_word`.
+    fn serialize_word_be(&mut self, source: T) {
+        self.serialize_word(source);
+    }
 }
 
 impl RequestWord<u8> for u8 {
@@ -42,6 +57,11 @@ impl RequestWord<u16> for [u8] {
         self[0] = source.to_le_bytes()[0];
         self[1] = source.to_le_bytes()[1];
     }
+
+    fn serialize_word_be(&mut self, source: u16) {
+        self[0] = source.to_be_bytes()[0];
+        self[1] = source.to_be_bytes()[1];
+    }
 }
 
 impl RequestWord<i16> for [u8] {
@@ -49,6 +69,11 @@ impl RequestWord<i16> for [u8] {
         self[0] = source.to_le_bytes()[0];
         self[1] = source.to_le_bytes()[1];
     }
+
+    fn serialize_word_be(&mut self, source: i16) {
+        self[0] = source.to_be_bytes()[0];
+        self[1] = source.to_be_bytes()[1];
+    }
 }
 
 impl RequestWord<u32> for [u8] {
@@ -58,6 +83,10 @@ impl RequestWord<u32> for [u8] {
         self[2] = source.to_le_bytes()[2];
         self[3] = source.to_le_bytes()[3];
     }
+
+    fn serialize_word_be(&mut self, source: u32) {
+        self[0..4].copy_from_slice(&source.to_be_bytes());
+    }
 }
 
 impl RequestWord<i32> for [u8] {
@@ -67,16 +96,148 @@ impl RequestWord<i32> for [u8] {
         self[2] = source.to_le_bytes()[2];
         self[3] = source.to_le_bytes()[3];
     }
+
+    fn serialize_word_be(&mut self, source: i32) {
+        self[0..4].copy_from_slice(&source.to_be_bytes());
+    }
+}
+
+// Values that fit into a single 16 or 32 bit device word.
+macro_rules! impl_request_single_word {
+    ($word:ty => $($source:ty),*) => {
+        $(
+            impl RequestWord<$source> for $word {
+                fn serialize_word(&mut self, source: $source) {
+                    *self = source as $word;
+                }
+            }
+        )*
+    };
+}
+
+impl_request_single_word!(u16 => u16, i16);
+impl_request_single_word!(u32 => u32, i32);
+
+// Values spread over one or more 16 or 32 bit device words. The endian
+// determines the order of the words as well as the bytes in each word.
+macro_rules! impl_request_multi_word {
+    ($word:ty => $($source:ty),*) => {
+        $(
+            impl RequestWord<$source> for [$word] {
+                fn serialize_word(&mut self, source: $source) {
+                    let bytes = source.to_le_bytes();
+                    for (target, chunk) in self
+                        .iter_mut()
+                        .zip(bytes.chunks(core::mem::size_of::<$word>()))
+                    {
+                        *target = <$word>::from_le_bytes(chunk.try_into().unwrap());
+                    }
+                }
+
+                fn serialize_word_be(&mut self, source: $source) {
+                    let bytes = source.to_be_bytes();
+                    for (target, chunk) in self
+                        .iter_mut()
+                        .zip(bytes.chunks(core::mem::size_of::<$word>()))
+                    {
+                        *target = <$word>::from_be_bytes(chunk.try_into().unwrap());
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_request_multi_word!(u16 => u16, i16, u32, i32, u64, i64);
+impl_request_multi_word!(u32 => u32, i32, u64, i64);
+
+pub trait RequestSpanningField<T> {
+    // data[3..=6].serialize_spanning_field(self.a_u32, 4, 2);
+    /// Serialize into the bits from `start` in the first word to `end` in the last word,
+    /// with the first word holding the least significant bits.
+    fn serialize_spanning_field(&mut self, source: T, start: usize, end: usize);
+
+    /// Serialize into the bits from bit 0 to `start` in the first word and from `end` to
+    /// the last bit in the last word, with the first word holding the most significant bits.
+    fn serialize_spanning_field_be(&mut self, source: T, start: usize, end: usize);
+}
+
+// Put the bits of a field spanning the words into them, with the words taken in the
+// order given and the first of them getting the lowest bits. The first and last words
+// are given the bits from `first` and `last`, as (low, high) bit positions.
+fn serialize_spanning_bits<'a, W: Bits + 'a>(
+    words: impl Iterator<Item = (usize, &'a mut W)>,
+    len: usize,
+    mut bits: u64,
+    first: (usize, usize),
+    last: (usize, usize),
+) {
+    let last_bit = core::mem::size_of::<W>() * 8 - 1;
+    for (i, word) in words {
+        let (low, high) = match i {
+            0 => first,
+            i if i == len - 1 => last,
+            _ => (0, last_bit),
+        };
+        bits = word.modify_spanned_bits(bits, low, high);
+    }
+}
+
+// Put the bits of a little endian field from `start` in the first word to `end` in the last
+fn serialize_spanning_bits_le<W: Bits>(words: &mut [W], bits: u64, start: usize, end: usize) {
+    let (len, last_bit) = (words.len(), core::mem::size_of::<W>() * 8 - 1);
+    serialize_spanning_bits(
+        words.iter_mut().enumerate(),
+        len,
+        bits,
+        (start, last_bit),
+        (0, end),
+    );
+}
+
+// Put the bits of a big endian field from bit 0 to `start` in the first word and from
+// `end` in the last word
+fn serialize_spanning_bits_be<W: Bits>(words: &mut [W], bits: u64, start: usize, end: usize) {
+    let (len, last_bit) = (words.len(), core::mem::size_of::<W>() * 8 - 1);
+    let words = words.iter_mut().enumerate().rev();
+    serialize_spanning_bits(words, len, bits, (0, start), (end, last_bit));
+}
+
+macro_rules! impl_request_spanning_field {
+    ($word:ty => $($source:ty),*) => {
+        $(
+            impl RequestSpanningField<$source> for [$word] {
+                fn serialize_spanning_field(&mut self, source: $source, start: usize, end: usize) {
+                    serialize_spanning_bits_le(self, source as u64, start, end);
+                }
+
+                fn serialize_spanning_field_be(&mut self, source: $source, start: usize, end: usize) {
+                    serialize_spanning_bits_be(self, source as u64, start, end);
+                }
+            }
+        )*
+    };
 }
 
+impl_request_spanning_field!(u8 => u8, i8, u16, i16, u32, i32, u64, i64);
+impl_request_spanning_field!(u16 => u8, i8, u16, i16, u32, i32, u64, i64);
+impl_request_spanning_field!(u32 => u8, i8, u16, i16, u32, i32, u64, i64);
+
 pub trait RequestArray<T> {
     // Usage : data[5..=10].serialize_repeating_words(self.a_repeating_u16, self.a_count.into());
     fn serialize_repeating_words(&mut self, source: T, number: usize);
+
+    /// Serialize repeating words with each element in big endian order.
+    /// By default this is the same as `serialize_repeating_words` which
+    /// is correct for elements that fit into a single word.
+    fn serialize_repeating_words_be(&mut self, source: T, number: usize) {
+        self.serialize_repeating_words(source, number);
+    }
 }
 
 impl<const SOURCE_LEN: usize> RequestArray<[u8; SOURCE_LEN]> for [u8] {
     fn serialize_repeating_words(&mut self, source: [u8; SOURCE_LEN], number: usize) {
-        self.copy_from_slice(&source[0..number]);
+        self[0..number].copy_from_slice(&source[0..number]);
     }
 }
 
@@ -90,6 +251,14 @@ impl<const SOURCE_LEN: usize> RequestArray<[u16; SOURCE_LEN]> for [u8] {
             target_position += 1;
         }
     }
+
+    fn serialize_repeating_words_be(&mut self, source: [u16; SOURCE_LEN], number: usize) {
+        for (i, source_value) in source.iter().enumerate().take(number) {
+            let target_position = i * 2;
+            self[target_position..(target_position + 2)]
+                .copy_from_slice(&source_value.to_be_bytes());
+        }
+    }
 }
 
 impl<const SOURCE_LEN: usize> RequestArray<[u32; SOURCE_LEN]> for [u8] {
@@ -100,17 +269,52 @@ impl<const SOURCE_LEN: usize> RequestArray<[u32; SOURCE_LEN]> for [u8] {
                 .copy_from_slice(&source_value.to_le_bytes());
         }
     }
+
+    fn serialize_repeating_words_be(&mut self, source: [u32; SOURCE_LEN], number: usize) {
+        for (i, source_value) in source.iter().enumerate().take(number) {
+            let target_position = i * 4;
+            self[target_position..(target_position + 4)]
+                .copy_from_slice(&source_value.to_be_bytes());
+        }
+    }
+}
+
+// Repeating values in 16 or 32 bit device words. Each element takes up
+// as many words as are needed to hold it.
+macro_rules! impl_request_array {
+    ($word:ty => $($source:ty),*) => {
+        $(
+            impl<const SOURCE_LEN: usize> RequestArray<[$source; SOURCE_LEN]> for [$word] {
+                fn serialize_repeating_words(&mut self, source: [$source; SOURCE_LEN], number: usize) {
+                    let n = core::mem::size_of::<$source>() / core::mem::size_of::<$word>();
+                    for (i, source_value) in source.iter().enumerate().take(number) {
+                        self[(i * n)..((i + 1) * n)].serialize_word(*source_value);
+                    }
+                }
+
+                fn serialize_repeating_words_be(&mut self, source: [$source; SOURCE_LEN], number: usize) {
+                    let n = core::mem::size_of::<$source>() / core::mem::size_of::<$word>();
+                    for (i, source_value) in source.iter().enumerate().take(number) {
+                        self[(i * n)..((i + 1) * n)].serialize_word_be(*source_value);
+                    }
+                }
+            }
+        )*
+    };
 }
 
-pub trait RequestStruct<T: Serialize> {
+impl_request_array!(u16 => u16, i16, u32, i32, u64, i64);
+impl_request_array!(u32 => u32, i32, u64, i64);
+
+pub trait RequestStruct<T> {
     // Usage : data[0..].serialize_struct(self.a_struct, 0);
     fn serialize_struct<const TARGET_LEN: usize>(&mut self, source: T);
 }
 
-impl<T: Serialize> RequestStruct<T> for [u8] {
+impl<W: Copy, T: Serialize<W>> RequestStruct<T> for [W] {
     fn serialize_struct<const TARGET_LEN: usize>(&mut self, source: T) {
         // let (size, data): (usize, [u8; TARGET_LEN]) = source.serialize();
-        let (size, data, _): (usize, [u8; TARGET_LEN], _) = source.serialize();
+        let (size, data, _): (usize, [W; TARGET_LEN], _) = source.serialize();
         self.copy_from_slice(&data[0..size]);
     }
 }
@@ -121,7 +325,9 @@ mod tests {
 
     use crate::serialize::Serialize;
 
-    use super::{RequestArray, RequestBit, RequestField, RequestStruct, RequestWord};
+    use super::{
+        RequestArray, RequestBit, RequestField, RequestSpanningField, RequestStruct, RequestWord,
+    };
 
     #[test]
     fn test_serialize_bool() {
@@ -132,6 +338,44 @@ mod tests {
         assert_eq!(data, [0, 0, 0b0010_0000, 0]);
     }
 
+    #[test]
+    fn test_serialize_spanning_field() {
+        // 12 bits from bit 4 of word 0 to bit 7 of word 1, leaving the other bits as they were
+        let mut data = [0b0000_1010u8, 0];
+        data[0..=1].serialize_spanning_field(0xABCu16, 4, 7);
+        assert_eq!(data, [0xCA, 0xAB]);
+
+        // 20 bits from word 0 to bit 3 of word 2
+        let mut data = [0u8, 0, 0xF0];
+        data[0..=2].serialize_spanning_field(0xABCDEu32, 0, 3);
+        assert_eq!(data, [0xDE, 0xBC, 0xFA]);
+
+        let mut data = [0u8; 2];
+        data[0..=1].serialize_spanning_field(-2i16, 4, 7);
+        assert_eq!(data, [0xE0, 0xFF]);
+
+        let mut data = [0u16; 2];
+        data[0..=1].serialize_spanning_field(0xABCu16, 8, 3);
+        assert_eq!(data, [0xBC00, 0x000A]);
+    }
+
+    #[test]
+    fn test_serialize_spanning_field_be() {
+        // 12 bits from bit 0 to bit 3 of word 0 and all of word 1
+        let mut data = [0xF0u8, 0];
+        data[0..=1].serialize_spanning_field_be(0xABCu16, 3, 0);
+        assert_eq!(data, [0xFA, 0xBC]);
+
+        // 20 bits from word 0 to bits 4 to 7 of word 2
+        let mut data = [0u8, 0, 0x0F];
+        data[0..=2].serialize_spanning_field_be(0xABCDEu32, 7, 4);
+        assert_eq!(data, [0xAB, 0xCD, 0xEF]);
+
+        let mut data = [0u16, 0x00FF];
+        data[0..=1].serialize_spanning_field_be(0xABCu16, 3, 8);
+        assert_eq!(data, [0x000A, 0xBCFF]);
+    }
+
     #[test]
     fn test_serialize_word_u8() {
         let mut data = [0u8; 4];
@@ -196,6 +440,51 @@ mod tests {
         assert_eq!(data, [0, 0, 0xC1, 0xD4, 0x66, 0x81]);
     }
 
+    #[test]
+    fn test_serialize_word_u16_be() {
+        let mut data = [0u8; 4];
+        let w: u16 = 22222;
+        data[2..].serialize_word_be(w);
+
+        assert_eq!(data, [0, 0, 0x56, 0xCE]);
+    }
+
+    #[test]
+    fn test_serialize_word_i16_be() {
+        let mut data = [0u8; 4];
+        let w: i16 = -222;
+        data[2..].serialize_word_be(w);
+
+        assert_eq!(data, [0, 0, 0xFF, 0x22]);
+    }
+
+    #[test]
+    fn test_serialize_word_u32_be() {
+        let mut data = [0u8; 6];
+        let w: u32 = 2_123_967_295;
+        data[2..].serialize_word_be(w);
+
+        assert_eq!(data, [0, 0, 0x7E, 0x99, 0x2B, 0x3F]);
+    }
+
+    #[test]
+    fn test_serialize_word_i32_be() {
+        let mut data = [0u8; 6];
+        let w: i32 = -2_123_967_295;
+        data[2..].serialize_word_be(w);
+
+        assert_eq!(data, [0, 0, 0x81, 0x66, 0xD4, 0xC1]);
+    }
+
+    #[test]
+    fn test_serialize_word_u8_be() {
+        let mut data = [0u8; 4];
+        let w: u8 = 42;
+        data[2].serialize_word_be(w);
+
+        assert_eq!(data, [0, 0, 42, 0]);
+    }
+
     #[test]
     fn test_u8_array() {
         let source = [123u8, 33];
@@ -232,6 +521,66 @@ mod tests {
         assert_eq!(serial_data, expected_data);
     }
 
+    #[test]
+    fn test_u16_array_be() {
+        let source = [22222u16, 33333];
+        let mut serial_data = [0u8; 7];
+
+        serial_data[2..=5].serialize_repeating_words_be(source, 2);
+
+        let expected_data: [u8; 7] = [0, 0, 0x56, 0xCE, 0x82, 0x35, 0];
+
+        assert_eq!(serial_data, expected_data);
+    }
+
+    #[test]
+    fn test_u32_array_be() {
+        let source = [0x12345678u32, 0xFEDCBA98];
+        let mut serial_data = [0u8; 10];
+
+        serial_data[2..=9].serialize_repeating_words_be(source, 2);
+
+        let expected_data: [u8; 10] = [0, 0, 0x12, 0x34, 0x56, 0x78, 0xFE, 0xDC, 0xBA, 0x98];
+
+        assert_eq!(serial_data, expected_data);
+    }
+
+    #[test]
+    fn test_serialize_16_bit_words() {
+        let mut data = [0u16; 6];
+
+        data[0].serialize_bit(true, 12);
+        data[0].serialize_field(0x1FF, 0, 8);
+        data[1].serialize_word(0xABCDu16);
+        data[2..=3].serialize_word(0x12345678u32);
+        data[4..=5].serialize_word_be(0x12345678u32);
+
+        assert_eq!(data, [0x11FF, 0xABCD, 0x5678, 0x1234, 0x1234, 0x5678]);
+    }
+
+    #[test]
+    fn test_serialize_32_bit_words() {
+        let mut data = [0u32; 4];
+
+        data[0].serialize_field(0xFFFFF, 4, 23);
+        data[1].serialize_word(-2i32);
+        data[2..=3].serialize_word_be(0x0102030405060708u64);
+
+        assert_eq!(data, [0x00FFFFF0, 0xFFFFFFFE, 0x01020304, 0x05060708]);
+    }
+
+    #[test]
+    fn test_u32_array_16_bit_words() {
+        let source = [0x12345678u32, 0x9ABCDEF0];
+        let mut data = [0u16; 5];
+
+        data[1..].serialize_repeating_words(source, 2);
+        assert_eq!(data, [0, 0x5678, 0x1234, 0xDEF0, 0x9ABC]);
+
+        data[1..].serialize_repeating_words_be(source, 2);
+        assert_eq!(data, [0, 0x1234, 0x5678, 0x9ABC, 0xDEF0]);
+    }
+
     struct TestCommonStruct {
         c_bool: bool,
         c_test_field: CommonTestField,
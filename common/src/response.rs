@@ -1,5 +1,3 @@
-// TODO make the word size generic
-
 use crate::bits::Bits;
 
 pub trait ResponseBit {
@@ -7,26 +5,40 @@ pub trait ResponseBit {
     fn deserialize_bit(&self, position: usize) -> bool;
 }
 
-impl ResponseBit for u8 {
-    /// Get a bit as bool at a particular position
-    fn deserialize_bit(&self, position: usize) -> bool {
-        let mask: u8 = 1 << position;
-        (*self & mask) > 0
-    }
-}
-
 pub trait ResponseField {
-    fn deserialize_field(&self, start: usize, end: usize) -> u8;
+    fn deserialize_field(&self, start: usize, end: usize) -> Self;
 }
 
-impl ResponseField for u8 {
-    fn deserialize_field(&self, start: usize, end: usize) -> u8 {
-        self.field(start, end)
-    }
+macro_rules! impl_response_bits {
+    ($($word:ty),*) => {
+        $(
+            impl ResponseBit for $word {
+                /// Get a bit as bool at a particular position
+                fn deserialize_bit(&self, position: usize) -> bool {
+                    self.bit(position)
+                }
+            }
+
+            impl ResponseField for $word {
+                fn deserialize_field(&self, start: usize, end: usize) -> $word {
+                    self.field(start, end)
+                }
+            }
+        )*
+    };
 }
 
+impl_response_bits!(u8, u16, u32);
+
 pub trait ResponseWord<T> {
     fn deserialize_word(&self) -> T;
+
+    /// Deserialize a word in big endian order. Values that fit
+    /// into a single word have no byte order so, by default,
+    /// this is the same as `deserialize_word`.
+    fn deserialize_word_be(&self) -> T {
+        self.deserialize_word()
+    }
 }
 
 impl ResponseWord<u8> for u8 {
@@ -45,30 +57,204 @@ impl ResponseWord<u16> for [u8] {
     fn deserialize_word(&self) -> u16 {
         u16::from_le_bytes([self[0], self[1]])
     }
+
+    fn deserialize_word_be(&self) -> u16 {
+        u16::from_be_bytes([self[0], self[1]])
+    }
 }
 
 impl ResponseWord<i16> for [u8] {
     fn deserialize_word(&self) -> i16 {
         i16::from_le_bytes([self[0], self[1]])
     }
+
+    fn deserialize_word_be(&self) -> i16 {
+        i16::from_be_bytes([self[0], self[1]])
+    }
 }
 
 impl ResponseWord<u32> for [u8] {
     fn deserialize_word(&self) -> u32 {
-        u32::from_le_bytes([self[0], self[1], self[2], self[4]])
+        u32::from_le_bytes([self[0], self[1], self[2], self[3]])
+    }
+
+    fn deserialize_word_be(&self) -> u32 {
+        u32::from_be_bytes([self[0], self[1], self[2], self[3]])
     }
 }
 
 impl ResponseWord<i32> for [u8] {
     fn deserialize_word(&self) -> i32 {
-        i32::from_le_bytes([self[0], self[1], self[2], self[4]])
+        i32::from_le_bytes([self[0], self[1], self[2], self[3]])
+    }
+
+    fn deserialize_word_be(&self) -> i32 {
+        i32::from_be_bytes([self[0], self[1], self[2], self[3]])
     }
 }
 
+// Values that fit into a single 16 or 32 bit device word.
+macro_rules! impl_response_single_word {
+    ($word:ty => $($target:ty),*) => {
+        $(
+            impl ResponseWord<$target> for $word {
+                fn deserialize_word(&self) -> $target {
+                    *self as $target
+                }
+            }
+        )*
+    };
+}
+
+impl_response_single_word!(u16 => u16, i16);
+impl_response_single_word!(u32 => u32, i32);
+
+// Values spread over one or more 16 or 32 bit device words. The endian
+// determines the order of the words as well as the bytes in each word.
+macro_rules! impl_response_multi_word {
+    ($word:ty => $($target:ty),*) => {
+        $(
+            impl ResponseWord<$target> for [$word] {
+                fn deserialize_word(&self) -> $target {
+                    let mut bytes = [0u8; core::mem::size_of::<$target>()];
+                    for (chunk, word) in bytes
+                        .chunks_mut(core::mem::size_of::<$word>())
+                        .zip(self.iter())
+                    {
+                        chunk.copy_from_slice(&word.to_le_bytes());
+                    }
+                    <$target>::from_le_bytes(bytes)
+                }
+
+                fn deserialize_word_be(&self) -> $target {
+                    let mut bytes = [0u8; core::mem::size_of::<$target>()];
+                    for (chunk, word) in bytes
+                        .chunks_mut(core::mem::size_of::<$word>())
+                        .zip(self.iter())
+                    {
+                        chunk.copy_from_slice(&word.to_be_bytes());
+                    }
+                    <$target>::from_be_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_response_multi_word!(u16 => u16, i16, u32, i32, u64, i64);
+impl_response_multi_word!(u32 => u32, i32, u64, i64);
+
+pub trait ResponseSpanningField<T> {
+    //  a_u32: buf[3..=6].deserialize_spanning_field(4, 2),
+    /// Deserialize the bits from `start` in the first word to `end` in the last word,
+    /// with the first word holding the least significant bits.
+    fn deserialize_spanning_field(&self, start: usize, end: usize) -> T;
+
+    /// Deserialize the bits from bit 0 to `start` in the first word and from `end` to the
+    /// last bit in the last word, with the first word holding the most significant bits.
+    fn deserialize_spanning_field_be(&self, start: usize, end: usize) -> T;
+}
+
+// The bits of a field spanning the words, with the words taken in the order given and
+// the first of them holding the lowest bits. The first and last words hold the bits from
+// `first` and `last`, as (low, high) bit positions. Also gives the number of bits in the field.
+fn spanning_bits<'a, W: Bits + 'a>(
+    words: impl Iterator<Item = (usize, &'a W)>,
+    len: usize,
+    first: (usize, usize),
+    last: (usize, usize),
+) -> (u64, usize) {
+    let last_bit = core::mem::size_of::<W>() * 8 - 1;
+    let (mut bits, mut width) = (0u64, 0);
+    for (i, word) in words {
+        let (low, high) = match i {
+            0 => first,
+            i if i == len - 1 => last,
+            _ => (0, last_bit),
+        };
+        bits |= word
+            .spanned_bits(low, high)
+            .checked_shl(width as u32)
+            .unwrap_or(0);
+        width += high - low + 1;
+    }
+    (bits, width)
+}
+
+// The bits of a little endian field, from `start` in the first word to `end` in the last
+fn spanning_bits_le<W: Bits>(words: &[W], start: usize, end: usize) -> (u64, usize) {
+    let last_bit = core::mem::size_of::<W>() * 8 - 1;
+    spanning_bits(
+        words.iter().enumerate(),
+        words.len(),
+        (start, last_bit),
+        (0, end),
+    )
+}
+
+// The bits of a big endian field, from bit 0 to `start` in the first word and from `end`
+// in the last word
+fn spanning_bits_be<W: Bits>(words: &[W], start: usize, end: usize) -> (u64, usize) {
+    let last_bit = core::mem::size_of::<W>() * 8 - 1;
+    spanning_bits(
+        words.iter().enumerate().rev(),
+        words.len(),
+        (0, start),
+        (end, last_bit),
+    )
+}
+
+// Signed fields have the sign of their highest bit, so that a 12 bit field can hold
+// -2048 to 2047.
+fn sign_extend(bits: u64, width: usize) -> i64 {
+    let unused = 64 - width.min(64) as u32;
+    (bits << unused) as i64 >> unused
+}
+
+macro_rules! impl_response_spanning_field {
+    ($word:ty => $($target:ty),* ; $($signed_target:ty),*) => {
+        $(
+            impl ResponseSpanningField<$target> for [$word] {
+                fn deserialize_spanning_field(&self, start: usize, end: usize) -> $target {
+                    spanning_bits_le(self, start, end).0 as $target
+                }
+
+                fn deserialize_spanning_field_be(&self, start: usize, end: usize) -> $target {
+                    spanning_bits_be(self, start, end).0 as $target
+                }
+            }
+        )*
+        $(
+            impl ResponseSpanningField<$signed_target> for [$word] {
+                fn deserialize_spanning_field(&self, start: usize, end: usize) -> $signed_target {
+                    let (bits, width) = spanning_bits_le(self, start, end);
+                    sign_extend(bits, width) as $signed_target
+                }
+
+                fn deserialize_spanning_field_be(&self, start: usize, end: usize) -> $signed_target {
+                    let (bits, width) = spanning_bits_be(self, start, end);
+                    sign_extend(bits, width) as $signed_target
+                }
+            }
+        )*
+    };
+}
+
+impl_response_spanning_field!(u8 => u8, u16, u32, u64; i8, i16, i32, i64);
+impl_response_spanning_field!(u16 => u8, u16, u32, u64; i8, i16, i32, i64);
+impl_response_spanning_field!(u32 => u8, u16, u32, u64; i8, i16, i32, i64);
+
 pub trait ResponseArray<T> {
     //  a_repeating_u8: data[0..=1].deserialize_repeating_word(2),
     // self is the u8 stream
     fn deserialize_repeating_words(&self, number: usize) -> T;
+
+    /// Deserialize repeating words with each element in big endian order.
+    /// By default this is the same as `deserialize_repeating_words` which
+    /// is correct for elements that fit into a single word.
+    fn deserialize_repeating_words_be(&self, number: usize) -> T {
+        self.deserialize_repeating_words(number)
+    }
 }
 
 impl<const TARGET_LEN: usize> ResponseArray<[u8; TARGET_LEN]> for [u8] {
@@ -92,6 +278,16 @@ impl<const TARGET_LEN: usize> ResponseArray<[u16; TARGET_LEN]> for [u8] {
             .for_each(|(i, v)| target[i] = v);
         target
     }
+
+    fn deserialize_repeating_words_be(&self, number: usize) -> [u16; TARGET_LEN] {
+        let mut target = [0; TARGET_LEN];
+        self.chunks(2)
+            .take(number)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .enumerate()
+            .for_each(|(i, v)| target[i] = v);
+        target
+    }
 }
 
 impl<const TARGET_LEN: usize> ResponseArray<[u32; TARGET_LEN]> for [u8] {
@@ -105,6 +301,16 @@ impl<const TARGET_LEN: usize> ResponseArray<[u32; TARGET_LEN]> for [u8] {
 
         target
     }
+
+    fn deserialize_repeating_words_be(&self, number: usize) -> [u32; TARGET_LEN] {
+        let mut target = [0; TARGET_LEN];
+        self.chunks(4)
+            .take(number)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .enumerate()
+            .for_each(|(i, v)| target[i] = v);
+        target
+    }
 }
 
 impl<const TARGET_LEN: usize> ResponseArray<[i8; TARGET_LEN]> for [u8] {
@@ -131,6 +337,16 @@ impl<const TARGET_LEN: usize> ResponseArray<[i16; TARGET_LEN]> for [u8] {
 
         target
     }
+
+    fn deserialize_repeating_words_be(&self, number: usize) -> [i16; TARGET_LEN] {
+        let mut target = [0; TARGET_LEN];
+        self.chunks(2)
+            .take(number)
+            .map(|b| i16::from_be_bytes([b[0], b[1]]))
+            .enumerate()
+            .for_each(|(i, v)| target[i] = v);
+        target
+    }
 }
 
 impl<const TARGET_LEN: usize> ResponseArray<[i32; TARGET_LEN]> for [u8] {
@@ -144,8 +360,53 @@ impl<const TARGET_LEN: usize> ResponseArray<[i32; TARGET_LEN]> for [u8] {
             .for_each(|(i, v)| target[i] = v);
         target
     }
+
+    fn deserialize_repeating_words_be(&self, number: usize) -> [i32; TARGET_LEN] {
+        let mut target = [0; TARGET_LEN];
+        self.chunks(4)
+            .take(number)
+            .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .enumerate()
+            .for_each(|(i, v)| target[i] = v);
+        target
+    }
+}
+
+// Repeating values in 16 or 32 bit device words. Each element takes up
+// as many words as are needed to hold it.
+macro_rules! impl_response_array {
+    ($word:ty => $($target:ty),*) => {
+        $(
+            impl<const TARGET_LEN: usize> ResponseArray<[$target; TARGET_LEN]> for [$word] {
+                fn deserialize_repeating_words(&self, number: usize) -> [$target; TARGET_LEN] {
+                    let n = core::mem::size_of::<$target>() / core::mem::size_of::<$word>();
+                    let mut target = [0; TARGET_LEN];
+                    self.chunks(n)
+                        .take(number)
+                        .map(|w| w.deserialize_word())
+                        .enumerate()
+                        .for_each(|(i, v)| target[i] = v);
+                    target
+                }
+
+                fn deserialize_repeating_words_be(&self, number: usize) -> [$target; TARGET_LEN] {
+                    let n = core::mem::size_of::<$target>() / core::mem::size_of::<$word>();
+                    let mut target = [0; TARGET_LEN];
+                    self.chunks(n)
+                        .take(number)
+                        .map(|w| w.deserialize_word_be())
+                        .enumerate()
+                        .for_each(|(i, v)| target[i] = v);
+                    target
+                }
+            }
+        )*
+    };
 }
 
+impl_response_array!(u16 => u16, i16, u32, i32, u64, i64);
+impl_response_array!(u32 => u32, i32, u64, i64);
+
 #[cfg(test)]
 mod tests {
 
@@ -176,6 +437,134 @@ mod tests {
         assert_eq!(a_u16, 31313);
     }
 
+    #[test]
+    fn test_deserialize_word_u32() {
+        let data: [u8; 5] = [0xFF, 0x3F, 0x2B, 0x99, 0x7E];
+
+        let a_u32: u32 = data[1..=4].deserialize_word();
+        assert_eq!(a_u32, 2_123_967_295);
+        let a_i32: i32 = data[1..=4].deserialize_word();
+        assert_eq!(a_i32, 2_123_967_295);
+    }
+
+    #[test]
+    fn test_deserialize_spanning_field() {
+        // 12 bits from bit 4 of word 0 to bit 7 of word 1
+        let data = [0xCAu8, 0xAB];
+        let a_u16: u16 = data[0..=1].deserialize_spanning_field(4, 7);
+        assert_eq!(a_u16, 0xABC);
+        let an_i16: i16 = data[0..=1].deserialize_spanning_field(4, 7);
+        assert_eq!(an_i16, 0xABC - 0x1000);
+
+        // 20 bits from word 0 to bit 3 of word 2
+        let data = [0xDEu8, 0xBC, 0xFA];
+        let a_u32: u32 = data[0..=2].deserialize_spanning_field(0, 3);
+        assert_eq!(a_u32, 0xABCDE);
+
+        let data = [0xBC00u16, 0x000A];
+        let a_u16: u16 = data[0..=1].deserialize_spanning_field(8, 3);
+        assert_eq!(a_u16, 0xABC);
+    }
+
+    #[test]
+    fn test_deserialize_spanning_field_be() {
+        // 12 bits from bit 0 to bit 3 of word 0 and all of word 1
+        let data = [0xFAu8, 0xBC];
+        let a_u16: u16 = data[0..=1].deserialize_spanning_field_be(3, 0);
+        assert_eq!(a_u16, 0xABC);
+
+        // 20 bits from word 0 to bits 4 to 7 of word 2
+        let data = [0xABu8, 0xCD, 0xEF];
+        let a_u32: u32 = data[0..=2].deserialize_spanning_field_be(7, 4);
+        assert_eq!(a_u32, 0xABCDE);
+        let an_i32: i32 = data[0..=2].deserialize_spanning_field_be(7, 4);
+        assert_eq!(an_i32, 0xABCDE - 0x10_0000);
+
+        let data = [0x000Au16, 0xBCFF];
+        let a_u16: u16 = data[0..=1].deserialize_spanning_field_be(3, 8);
+        assert_eq!(a_u16, 0xABC);
+    }
+
+    #[test]
+    fn test_deserialize_word_be() {
+        // 5u8, 31313u16, 2_123_967_295u32
+        let data: [u8; 7] = [0x05, 0x7A, 0x51, 0x7E, 0x99, 0x2B, 0x3F];
+
+        let a_u8: u8 = data[0].deserialize_word_be();
+        assert_eq!(a_u8, 5);
+        let a_u16: u16 = data[1..=2].deserialize_word_be();
+        assert_eq!(a_u16, 31313);
+        let a_u32: u32 = data[3..=6].deserialize_word_be();
+        assert_eq!(a_u32, 2_123_967_295);
+    }
+
+    #[test]
+    fn test_deserialize_word_signed_be() {
+        let data: [u8; 6] = [0xFF, 0x22, 0x81, 0x66, 0xD4, 0xC1];
+
+        let a_i16: i16 = data[0..=1].deserialize_word_be();
+        assert_eq!(a_i16, -222);
+        let a_i32: i32 = data[2..=5].deserialize_word_be();
+        assert_eq!(a_i32, -2_123_967_295);
+    }
+
+    #[test]
+    fn test_arrays_be() {
+        let source: [u8; 12] = [
+            0x56, 0xCE, 0x82, 0x35, 0x12, 0x34, 0x56, 0x78, 0xFE, 0xDC, 0xBA, 0x98,
+        ];
+
+        let data_u16: [u16; 3] = source[0..4].deserialize_repeating_words_be(2);
+        assert_eq!(data_u16, [22222, 33333, 0]);
+
+        let data_u32: [u32; 2] = source[4..12].deserialize_repeating_words_be(2);
+        assert_eq!(data_u32, [0x12345678, 0xFEDCBA98]);
+
+        let data_i16: [i16; 2] = source[0..4].deserialize_repeating_words_be(2);
+        assert_eq!(data_i16, [22222, 33333u16 as i16]);
+    }
+
+    #[test]
+    fn test_deserialize_16_bit_words() {
+        let data: [u16; 6] = [0x11FF, 0xABCD, 0x5678, 0x1234, 0x1234, 0x5678];
+
+        assert!(data[0].deserialize_bit(12));
+        assert_eq!(data[0].deserialize_field(0, 8), 0x1FF);
+        let a_u16: u16 = data[1].deserialize_word();
+        assert_eq!(a_u16, 0xABCD);
+        let a_i16: i16 = data[1].deserialize_word();
+        assert_eq!(a_i16, 0xABCDu16 as i16);
+        let a_u32: u32 = data[2..=3].deserialize_word();
+        assert_eq!(a_u32, 0x12345678);
+        let a_u32: u32 = data[4..=5].deserialize_word_be();
+        assert_eq!(a_u32, 0x12345678);
+    }
+
+    #[test]
+    fn test_deserialize_32_bit_words() {
+        let data: [u32; 4] = [0x00FFFFF0, 0xFFFFFFFE, 0x01020304, 0x05060708];
+
+        assert_eq!(data[0].deserialize_field(4, 23), 0xFFFFF);
+        let a_i32: i32 = data[1].deserialize_word();
+        assert_eq!(a_i32, -2);
+        let a_u64: u64 = data[2..=3].deserialize_word_be();
+        assert_eq!(a_u64, 0x0102030405060708);
+    }
+
+    #[test]
+    fn test_arrays_16_bit_words() {
+        let source: [u16; 4] = [0x5678, 0x1234, 0xDEF0, 0x9ABC];
+
+        let data_u32: [u32; 3] = source.deserialize_repeating_words(2);
+        assert_eq!(data_u32, [0x12345678, 0x9ABCDEF0, 0]);
+
+        let data_u32: [u32; 2] = source.deserialize_repeating_words_be(2);
+        assert_eq!(data_u32, [0x56781234, 0xDEF09ABC]);
+
+        let data_u16: [u16; 4] = source.deserialize_repeating_words(3);
+        assert_eq!(data_u16, [0x5678, 0x1234, 0xDEF0, 0]);
+    }
+
     #[test]
     fn test_unsigned_arrays() {
         let expected_data_u8: [u8; 2] = [12, 13];
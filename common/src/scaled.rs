@@ -0,0 +1,84 @@
+use crate::DeviceError;
+
+/// Converts a physical value into the raw value of a scaled field, where
+/// `value = raw * scale + offset`. The raw value is rounded to the nearest integer,
+/// with halfway values rounded away from zero.
+///
+/// Returns `DeviceError::OutOfRange` if the rounded raw value is outside of
+/// `min..=max`, or if the value is not a number.
+pub fn to_raw(
+    value: f32,
+    scale: f32,
+    offset: f32,
+    min: i128,
+    max: i128,
+) -> Result<i128, DeviceError> {
+    let raw = (value - offset) / scale;
+    if raw.is_nan() {
+        return Err(DeviceError::OutOfRange);
+    }
+
+    // f32::round() is not available without std. The conversion saturates, so
+    // infinite values end up outside of the range.
+    let rounded = if raw < 0.0 {
+        (raw - 0.5) as i128
+    } else {
+        (raw + 0.5) as i128
+    };
+
+    if (min..=max).contains(&rounded) {
+        Ok(rounded)
+    } else {
+        Err(DeviceError::OutOfRange)
+    }
+}
+
+/// Converts the raw value of a scaled field into its physical value.
+pub fn from_raw(raw: i128, scale: f32, offset: f32) -> f32 {
+    raw as f32 * scale + offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_raw() {
+        // Antenna capacitance in units of 0.25 pF
+        assert!(matches!(to_raw(32.0, 0.25, 0.0, 0, 128), Ok(128)));
+        assert!(matches!(to_raw(1.1, 0.25, 0.0, 0, 128), Ok(4)));
+        assert!(matches!(to_raw(1.125, 0.25, 0.0, 0, 128), Ok(5)));
+        assert!(matches!(
+            to_raw(32.2, 0.25, 0.0, 0, 128),
+            Err(DeviceError::OutOfRange)
+        ));
+        assert!(matches!(
+            to_raw(-0.2, 0.25, 0.0, 0, 128),
+            Err(DeviceError::OutOfRange)
+        ));
+
+        // Temperature in units of 0.5 °C from -40 °C
+        assert!(matches!(to_raw(-40.0, 0.5, -40.0, 0, 255), Ok(0)));
+        assert!(matches!(to_raw(21.3, 0.5, -40.0, 0, 255), Ok(123)));
+
+        // Halves are rounded away from zero
+        assert!(matches!(to_raw(-1.25, 0.5, 0.0, -128, 127), Ok(-3)));
+        assert!(matches!(to_raw(1.25, 0.5, 0.0, -128, 127), Ok(3)));
+
+        assert!(matches!(
+            to_raw(f32::NAN, 0.5, 0.0, -128, 127),
+            Err(DeviceError::OutOfRange)
+        ));
+        assert!(matches!(
+            to_raw(f32::INFINITY, 0.5, 0.0, -128, 127),
+            Err(DeviceError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_from_raw() {
+        assert_eq!(from_raw(128, 0.25, 0.0), 32.0);
+        assert_eq!(from_raw(123, 0.5, -40.0), 21.5);
+        assert_eq!(from_raw(-3, 0.5, 0.0), -1.5);
+    }
+}
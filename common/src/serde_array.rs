@@ -0,0 +1,55 @@
+//! Serializes arrays of any length, as serde only implements its traits for arrays of up
+//! to 32 elements. Used for longer arrays with `#[serde(with = "crate::serde_array")]`.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+
+pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for element in array {
+        tuple.serialize_element(element)?;
+    }
+    tuple.end()
+}
+
+pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Copy + Default,
+{
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
+where
+    T: Deserialize<'de> + Copy + Default,
+{
+    type Value = [T; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an array of {N} elements")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<[T; N], A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut array = [T::default(); N];
+        for (index, element) in array.iter_mut().enumerate() {
+            *element = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(index, &self))?;
+        }
+        Ok(array)
+    }
+}
@@ -1,16 +1,42 @@
+use crate::DeviceError;
+
 /// LEN is size of the buffer array returned.
-pub trait Serialize {
+/// W is the type of the device word, e.g. `u16` for a device with 16 bit words.
+pub trait Serialize<W = u8> {
     /// Serializes a structure.
     /// It returns a tuple containing:
-    /// - The number of bytes need to represent the fixed size members and
+    /// - The number of words need to represent the fixed size members and
     ///   any repeating members that either have a fixed size or a dependent
-    ///   member that specifies their size. The number of bytes is to show
+    ///   member that specifies their size. The number of words is to show
     ///   what is actually valid.
-    /// - An array containing the serialized bytes for the above. Note that
-    ///   the array can be larger then the actual number of serialized bytes.
-    ///   The number of bytes is to show what is actually valid.   
+    /// - An array containing the serialized words for the above. Note that
+    ///   the array can be larger then the actual number of serialized words.
+    ///   The number of words is to show what is actually valid.   
     /// - An  iterator that can be used to loop though the rest of
     ///   the serialized data if a variable repeat is used in the bit spec.
     ///   If no variable repeat was used then this is empty .
-    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>);
+    fn serialize<const LEN: usize>(&self) -> (usize, [W; LEN], impl Iterator<Item = W>);
+
+    /// Checks that the members holding the number of repeats of dependent repeats are
+    /// within the limits of those repeats. This needs to be done before serializing
+    /// as the positions of the serialized words depend on these counts.
+    fn check_counts(&self) -> Result<(), DeviceError> {
+        Ok(())
+    }
+
+    /// Serializes a structure to be sent to the device. The counts are checked first and the
+    /// words of the provider, if any, are added after the serialized words. Returns the number
+    /// of valid words and the array holding them, or `DeviceError::OutOfRange` if the
+    /// provider has more words than fit into the array.
+    fn serialize_request<const LEN: usize>(&self) -> Result<(usize, [W; LEN]), DeviceError> {
+        self.check_counts()?;
+
+        let (mut size, mut data, provider) = self.serialize::<LEN>();
+        for provided_element in provider {
+            *data.get_mut(size).ok_or(DeviceError::OutOfRange)? = provided_element;
+            size += 1;
+        }
+
+        Ok((size, data))
+    }
 }
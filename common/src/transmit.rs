@@ -1,31 +1,43 @@
+use core::ops::Not;
+
+use embedded_hal::delay::DelayNs;
 use embedded_hal::spi::{Operation, SpiDevice};
 
-use crate::{command::Command, deserialize::Deserialize, serialize::Serialize, DeviceError};
+use crate::{
+    command::Command,
+    deserialize::Deserialize,
+    poll::{PollLimit, Unbounded},
+    ready_pin::{ActiveLevel, WaitReady},
+    serialize::Serialize,
+    DeviceError,
+};
 
-pub trait Transmit<SPI, RESP>: Serialize + Command
+/// W is the type of the device word that is transferred over the SPI bus.
+pub trait Transmit<SPI, RESP, W = u8>: Serialize<W> + Command
 where
-    SPI: SpiDevice,
-    RESP: Deserialize<RESP>,
+    SPI: SpiDevice<W>,
+    RESP: Deserialize<RESP, W>,
+    W: Copy + Default + From<u8> + 'static,
 {
     fn transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize>(
         &self,
         spi: &mut SPI,
     ) -> Result<RESP, DeviceError> {
-        let opcode: [u8; 1] = [self.opcode()];
+        let opcode: [W; 1] = [self.opcode().into()];
 
-        let (mut size, mut data, provider) = self.serialize::<REQ_MAX_LEN>();
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
 
-        for provided_element in provider {
-            data[size] = provided_element;
-            size += 1;
+        // Commands without a response are only written
+        if RESP_MAX_LEN == 0 {
+            spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+        } else {
+            spi.transaction(&mut [
+                Operation::Write(&opcode),
+                Operation::Write(&data[0..size]),
+                Operation::Read(&mut response_buf),
+            ])
         }
-        let mut response_buf = [0_u8; RESP_MAX_LEN];
-
-        spi.transaction(&mut [
-            Operation::Write(&opcode),
-            Operation::Write(&data[0..size]),
-            Operation::Read(&mut response_buf),
-        ])
         .map_err(|_| DeviceError::Transmit)?;
         // why not .map_err(DeviceError::Transmit)?
 
@@ -35,28 +47,147 @@ where
         Ok(response)
     }
 
+    /// Sends the request, waits for `delay_us` microseconds and then reads the response.
+    /// The request and the response are separate SPI transactions.
+    fn timed_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        delay_us: u32,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .map_err(|_| DeviceError::Transmit)?;
+
+        delay.delay_us(delay_us);
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    /// Sends the request and reads the response once the device signals, e.g. with a
+    /// ready pin, that the response is ready.
+    fn ready_pin_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize, READY: WaitReady>(
+        &self,
+        spi: &mut SPI,
+        ready: &mut READY,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .map_err(|_| DeviceError::Transmit)?;
+
+        ready.wait()?;
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    /// Sends the request and then reads single words until the device holds MISO at the
+    /// `active` level, which signals that the response is ready. Returns `DeviceError::NotReady`
+    /// if this has not happened after the first read and `max_retries` further reads.
+    ///
+    /// Each word is read in its own SPI transaction, so CS is deasserted between the reads.
+    /// The operations of an `SpiDevice` transaction are fixed before it starts, so a
+    /// transaction cannot end once MISO shows that the device is ready. Devices that signal
+    /// readiness on MISO keep doing so while CS is toggled.
+    fn miso_ready_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize>(
+        &self,
+        spi: &mut SPI,
+        active: ActiveLevel,
+        max_retries: u32,
+    ) -> Result<RESP, DeviceError>
+    where
+        W: PartialEq + Not<Output = W>,
+    {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .map_err(|_| DeviceError::Transmit)?;
+
+        let ready_word = match active {
+            ActiveLevel::Low => W::default(),
+            ActiveLevel::High => !W::default(),
+        };
+        let mut miso = [W::default(); 1];
+        let mut retries = 0;
+        loop {
+            spi.transaction(&mut [Operation::Read(&mut miso)])
+                .map_err(|_| DeviceError::Receive)?;
+            if miso[0] == ready_word {
+                break;
+            }
+            if retries == max_retries {
+                return Err(DeviceError::NotReady);
+            }
+            retries += 1;
+        }
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
     fn polled_transmit<
         const REQ_MAX_LEN: usize,
         const RESP_MAX_LEN: usize,
-        HEADER: Deserialize<HEADER>,
+        HEADER: Deserialize<HEADER, W>,
         const STATUS_HEADER_LEN: usize,
     >(
         &self,
         spi: &mut SPI,
-        //status_header: HEADER,
         status_fn: fn(HEADER) -> bool,
     ) -> Result<RESP, DeviceError> {
-        let opcode: [u8; 1] = [self.opcode()];
-
-        let (mut size, mut data, provider) = self.serialize::<REQ_MAX_LEN>();
-        // TODO Should the followng code be added to the serialize function? Signature
-        // would then be (size, data) = self.serialize::<REQ_MAX_LEN>()
-        for provided_element in provider {
-            data[size] = provided_element;
-            size += 1;
-        }
+        self.limited_polled_transmit::<REQ_MAX_LEN, RESP_MAX_LEN, HEADER, STATUS_HEADER_LEN, _>(
+            spi,
+            status_fn,
+            &mut Unbounded,
+        )
+    }
+
+    /// As `polled_transmit`, but the status header is only read again for as long as
+    /// `limit` allows.
+    fn limited_polled_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        HEADER: Deserialize<HEADER, W>,
+        const STATUS_HEADER_LEN: usize,
+        LIMIT: PollLimit,
+    >(
+        &self,
+        spi: &mut SPI,
+        status_fn: fn(HEADER) -> bool,
+        limit: &mut LIMIT,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
 
-        let mut response_buf = [0_u8; RESP_MAX_LEN];
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
 
         // Read the first header
         spi.transaction(&mut [
@@ -77,6 +208,7 @@ where
                 break;
             } else {
                 // Repeat the read
+                limit.next_poll()?;
                 spi.transaction(&mut [Operation::Read(&mut response_buf[0..STATUS_HEADER_LEN])])
                     .map_err(|_| DeviceError::Receive)?;
             }
@@ -0,0 +1,295 @@
+use core::ops::Not;
+
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::spi::{Operation, SpiDevice};
+
+use crate::{
+    command::Command,
+    deserialize::Deserialize,
+    poll::{MaxPolls, PollLimit, PollTimeout, Unbounded},
+    ready_pin::{ActiveLevel, ReadyPin, ReadyPinWithTimeout, POLL_INTERVAL_US},
+    register::RegisterRead,
+    serialize::Serialize,
+    DeviceError,
+};
+
+/// The async twin of `WaitReady`.
+#[allow(async_fn_in_trait)]
+pub trait AsyncWaitReady {
+    async fn wait(&mut self) -> Result<(), DeviceError>;
+}
+
+// Waiting on the pin, rather than reading it until it is active, lets other tasks run
+// until the device is ready.
+impl<PIN: InputPin + Wait> AsyncWaitReady for ReadyPin<'_, PIN> {
+    async fn wait(&mut self) -> Result<(), DeviceError> {
+        match self.active() {
+            ActiveLevel::High => self.pin().wait_for_high().await,
+            ActiveLevel::Low => self.pin().wait_for_low().await,
+        }
+        .map_err(|_| DeviceError::Receive)
+    }
+}
+
+// Unlike the blocking version the delay between reads of the pin lets other
+// tasks run.
+impl<PIN: InputPin, DELAY: DelayNs> AsyncWaitReady for ReadyPinWithTimeout<'_, PIN, DELAY> {
+    async fn wait(&mut self) -> Result<(), DeviceError> {
+        let mut waited_us = 0;
+        while !self.is_ready()? {
+            if waited_us >= self.timeout_us() {
+                return Err(DeviceError::Timeout);
+            }
+            self.delay().delay_us(POLL_INTERVAL_US).await;
+            waited_us += POLL_INTERVAL_US;
+        }
+        Ok(())
+    }
+}
+
+/// The async twin of `PollLimit`.
+#[allow(async_fn_in_trait)]
+pub trait AsyncPollLimit {
+    async fn next_poll(&mut self) -> Result<(), DeviceError>;
+}
+
+impl AsyncPollLimit for Unbounded {
+    async fn next_poll(&mut self) -> Result<(), DeviceError> {
+        Ok(())
+    }
+}
+
+impl AsyncPollLimit for MaxPolls {
+    async fn next_poll(&mut self) -> Result<(), DeviceError> {
+        PollLimit::next_poll(self)
+    }
+}
+
+impl<DELAY: DelayNs> AsyncPollLimit for PollTimeout<'_, DELAY> {
+    async fn next_poll(&mut self) -> Result<(), DeviceError> {
+        let interval_us = self.start_interval()?;
+        self.delay().delay_us(interval_us).await;
+        Ok(())
+    }
+}
+
+/// The async twin of `Transmit`, for use with executors such as Embassy.
+/// W is the type of the device word that is transferred over the SPI bus.
+#[allow(async_fn_in_trait)]
+pub trait AsyncTransmit<SPI, RESP, W = u8>: Serialize<W> + Command
+where
+    SPI: SpiDevice<W>,
+    RESP: Deserialize<RESP, W>,
+    W: Copy + Default + From<u8> + 'static,
+{
+    async fn transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize>(
+        &self,
+        spi: &mut SPI,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        // Commands without a response are only written
+        if RESP_MAX_LEN == 0 {
+            spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+                .await
+        } else {
+            spi.transaction(&mut [
+                Operation::Write(&opcode),
+                Operation::Write(&data[0..size]),
+                Operation::Read(&mut response_buf),
+            ])
+            .await
+        }
+        .map_err(|_| DeviceError::Transmit)?;
+
+        let response = RESP::deserialize(&response_buf[0..])?;
+
+        Ok(response)
+    }
+
+    async fn timed_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        delay_us: u32,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .await
+            .map_err(|_| DeviceError::Transmit)?;
+
+        delay.delay_us(delay_us).await;
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .await
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    async fn ready_pin_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        READY: AsyncWaitReady,
+    >(
+        &self,
+        spi: &mut SPI,
+        ready: &mut READY,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .await
+            .map_err(|_| DeviceError::Transmit)?;
+
+        ready.wait().await?;
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .await
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    async fn miso_ready_transmit<const REQ_MAX_LEN: usize, const RESP_MAX_LEN: usize>(
+        &self,
+        spi: &mut SPI,
+        active: ActiveLevel,
+        max_retries: u32,
+    ) -> Result<RESP, DeviceError>
+    where
+        W: PartialEq + Not<Output = W>,
+    {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        spi.transaction(&mut [Operation::Write(&opcode), Operation::Write(&data[0..size])])
+            .await
+            .map_err(|_| DeviceError::Transmit)?;
+
+        let ready_word = match active {
+            ActiveLevel::Low => W::default(),
+            ActiveLevel::High => !W::default(),
+        };
+        let mut miso = [W::default(); 1];
+        let mut retries = 0;
+        loop {
+            spi.transaction(&mut [Operation::Read(&mut miso)])
+                .await
+                .map_err(|_| DeviceError::Receive)?;
+            if miso[0] == ready_word {
+                break;
+            }
+            if retries == max_retries {
+                return Err(DeviceError::NotReady);
+            }
+            retries += 1;
+        }
+
+        if RESP_MAX_LEN > 0 {
+            spi.transaction(&mut [Operation::Read(&mut response_buf)])
+                .await
+                .map_err(|_| DeviceError::Receive)?;
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+
+    async fn polled_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        HEADER: Deserialize<HEADER, W>,
+        const STATUS_HEADER_LEN: usize,
+    >(
+        &self,
+        spi: &mut SPI,
+        status_fn: fn(HEADER) -> bool,
+    ) -> Result<RESP, DeviceError> {
+        self.limited_polled_transmit::<REQ_MAX_LEN, RESP_MAX_LEN, HEADER, STATUS_HEADER_LEN, _>(
+            spi,
+            status_fn,
+            &mut Unbounded,
+        )
+        .await
+    }
+
+    async fn limited_polled_transmit<
+        const REQ_MAX_LEN: usize,
+        const RESP_MAX_LEN: usize,
+        HEADER: Deserialize<HEADER, W>,
+        const STATUS_HEADER_LEN: usize,
+        LIMIT: AsyncPollLimit,
+    >(
+        &self,
+        spi: &mut SPI,
+        status_fn: fn(HEADER) -> bool,
+        limit: &mut LIMIT,
+    ) -> Result<RESP, DeviceError> {
+        let opcode: [W; 1] = [self.opcode().into()];
+
+        let (size, data) = self.serialize_request::<REQ_MAX_LEN>()?;
+
+        let mut response_buf = [W::default(); RESP_MAX_LEN];
+
+        // Read the first header
+        spi.transaction(&mut [
+            Operation::Write(&opcode),
+            Operation::Write(&data[0..size]),
+            Operation::Read(&mut response_buf[0..STATUS_HEADER_LEN]),
+        ])
+        .await
+        .map_err(|_| DeviceError::Transmit)?;
+
+        loop {
+            let header = HEADER::deserialize(&response_buf[0..STATUS_HEADER_LEN])
+                .map_err(|_| DeviceError::Receive)?;
+
+            if status_fn(header) {
+                // Read in the rest of the response
+                spi.transaction(&mut [Operation::Read(&mut response_buf[STATUS_HEADER_LEN..])])
+                    .await
+                    .map_err(|_| DeviceError::Transmit)?;
+                break;
+            } else {
+                // Repeat the read
+                limit.next_poll().await?;
+                spi.transaction(&mut [Operation::Read(&mut response_buf[0..STATUS_HEADER_LEN])])
+                    .await
+                    .map_err(|_| DeviceError::Receive)?;
+            }
+        }
+
+        RESP::deserialize(&response_buf)
+    }
+}
+
+// Kept here rather than with the blocking implementations in `register` as this
+// module is only compiled with the `async` feature.
+impl<SPI, RESP, W> AsyncTransmit<SPI, RESP, W> for RegisterRead
+where
+    SPI: SpiDevice<W>,
+    RESP: Deserialize<RESP, W>,
+    W: Copy + Default + From<u8> + 'static,
+{
+}
@@ -128,6 +128,25 @@ fn test_provider_request() {
     spi.done();
 }
 
+#[test]
+fn test_provider_too_long() {
+    // One more word than fits into the request
+    let request = ProviderTestRequest {
+        arg1: 8,
+        provided_field: Provider::new(10),
+    };
+
+    // Nothing is sent
+    let mut spi = SpiMock::new(&[]);
+
+    assert!(matches!(
+        request.send(&mut spi),
+        Err(DeviceError::OutOfRange)
+    ));
+
+    spi.done();
+}
+
 #[test]
 fn test_provider_to_see_if_i_have_it_right() {
     let mut provider = Provider::new(5);
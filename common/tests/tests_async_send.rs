@@ -0,0 +1,269 @@
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use common::ready_pin::{ActiveLevel, ReadyPin};
+use common::transmit_async::AsyncTransmit;
+use common::DeviceError;
+use common::{
+    command::Command,
+    deserialize::Deserialize,
+    request::RequestWord,
+    response::{ResponseBit, ResponseWord},
+    serialize::Serialize,
+};
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::digital::{
+    Mock as PinMock, State as PinState, Transaction as PinTransaction,
+};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const SIMPLE_REQUEST_OPCODE: u8 = 0x09;
+const POLLED_REQUEST_OPCODE: u8 = 0x10;
+const READY_REQUEST_OPCODE: u8 = 0x11;
+
+// The mocks never return pending, so the futures can be polled to completion
+// without a real executor.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+struct SimpleRequest {
+    arg1: u8,
+}
+
+impl SimpleRequest {
+    pub async fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<SimpleResponse, DeviceError> {
+        let response = self.transmit::<2, 1>(spi).await?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> AsyncTransmit<SPI, SimpleResponse> for SimpleRequest {}
+
+impl Command for SimpleRequest {
+    fn opcode(&self) -> u8 {
+        SIMPLE_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for SimpleRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct SimpleResponse {
+    status: bool,
+}
+
+impl Deserialize<Self> for SimpleResponse {
+    fn deserialize(buf: &[u8]) -> Result<SimpleResponse, DeviceError> {
+        let status = buf[0].deserialize_bit(0);
+
+        Ok(Self { status })
+    }
+}
+
+struct PolledRequest {
+    arg1: u8,
+}
+
+impl PolledRequest {
+    pub async fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<PolledResponse, DeviceError> {
+        let f = |h: StatusHeader| h.status;
+        let response = self
+            .polled_transmit::<2, 2, StatusHeader, 1>(spi, f)
+            .await?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> AsyncTransmit<SPI, PolledResponse> for PolledRequest {}
+
+impl Command for PolledRequest {
+    fn opcode(&self) -> u8 {
+        POLLED_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for PolledRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct PolledResponse {
+    status_header: StatusHeader,
+    some_data: u8,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct StatusHeader {
+    status: bool,
+}
+
+impl Deserialize<Self> for StatusHeader {
+    fn deserialize(buf: &[u8]) -> Result<StatusHeader, DeviceError> {
+        let status = buf[0].deserialize_bit(0);
+
+        Ok(Self { status })
+    }
+}
+
+impl Deserialize<Self> for PolledResponse {
+    fn deserialize(buf: &[u8]) -> Result<PolledResponse, DeviceError> {
+        let status_header = StatusHeader::deserialize(&buf[0..=0])?;
+        let some_data = buf[1].deserialize_word();
+
+        Ok(Self {
+            status_header,
+            some_data,
+        })
+    }
+}
+
+// flow_control = { type = "ready_pin", active = "low" }
+struct ReadyRequest {
+    arg1: u8,
+}
+
+impl ReadyRequest {
+    pub async fn send<SPI: SpiDevice, READY: InputPin + Wait>(
+        &self,
+        spi: &mut SPI,
+        ready: &mut READY,
+    ) -> Result<SimpleResponse, DeviceError> {
+        let mut ready = ReadyPin::new(ready, ActiveLevel::Low);
+        let response = self.ready_pin_transmit::<1, 1, _>(spi, &mut ready).await?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> AsyncTransmit<SPI, SimpleResponse> for ReadyRequest {}
+
+impl Command for ReadyRequest {
+    fn opcode(&self) -> u8 {
+        READY_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for ReadyRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[test]
+fn test_async_simple_request() {
+    let request = SimpleRequest { arg1: 8 };
+
+    let expected_response = SimpleResponse { status: true };
+
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x09),
+        SpiTransaction::write(8),
+        SpiTransaction::read_vec(vec![0b000_0001]),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = block_on(request.send(&mut spi)).unwrap();
+
+    assert_eq!(response, expected_response);
+
+    spi.done();
+}
+
+#[test]
+fn test_async_polled_request() {
+    let request = PolledRequest { arg1: 8 };
+
+    let expected_response = PolledResponse {
+        status_header: StatusHeader { status: true },
+        some_data: 0xAA,
+    };
+
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x10),
+        SpiTransaction::write(8),
+        SpiTransaction::read_vec(vec![0b000_0000]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0b000_0001]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0xAA]),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = block_on(request.send(&mut spi)).unwrap();
+
+    assert_eq!(response, expected_response);
+
+    spi.done();
+}
+
+#[test]
+fn test_async_ready_pin_request() {
+    let request = ReadyRequest { arg1: 8 };
+
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x11),
+        SpiTransaction::write(8),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read(0b000_0001),
+        SpiTransaction::transaction_end(),
+    ];
+    // The pin is waited on rather than read until it is low
+    let pin_expectations = [PinTransaction::wait_for_state(PinState::Low)];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut ready = PinMock::new(&pin_expectations);
+
+    let response = block_on(request.send(&mut spi, &mut ready)).unwrap();
+
+    assert_eq!(response, SimpleResponse { status: true });
+
+    spi.done();
+    ready.done();
+}
@@ -1,6 +1,6 @@
 use common::bits::Bits;
 
-use bit_lang::{BitRange, BitSpec, Repeat as WordRepeat, Word};
+use bit_lang::{BitRange, BitSpec, Repeat as WordRepeat, RepeatCount, Word};
 use common::response::{ResponseBit, ResponseField};
 use common::{error::DeviceError, response::ResponseArray};
 
@@ -39,7 +39,7 @@ fn test_enum_deserialize_field() {
 fn deserialize_bit() {
     let spec = bit_lang::parse("3[4]").unwrap();
 
-    let data = [
+    let data: [u8; 5] = [
         0b0000_0000,
         0b0000_0000,
         0b0000_0000,
@@ -56,6 +56,7 @@ fn deserialize_bit() {
                 },
             end: None,
             repeat: WordRepeat::None,
+            ..
         } => (index, n),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -92,6 +93,7 @@ fn test_deserialize_field() {
                 },
             end: None,
             repeat: WordRepeat::None,
+            ..
         } => (index, n, m),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -111,7 +113,7 @@ fn test_deserialize_field() {
 fn deserialize_whole_word() {
     let spec = bit_lang::parse("3[]").unwrap();
 
-    let data = [
+    let data: [u8; 5] = [
         0b0000_0000, // 0
         0b0000_0000, // 1
         0b0000_0000, // 2
@@ -156,6 +158,7 @@ fn deserialize_word_range_le_to_u16() {
                     bit_range: BitRange::WholeWord,
                 }),
             repeat: WordRepeat::None,
+            ..
         } => (v, w),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -278,6 +281,7 @@ fn deserialize_word_repeat() {
                 },
             end: None,
             repeat: WordRepeat::Fixed { number: r },
+            ..
         } => (w, r),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -318,7 +322,12 @@ fn deserialize_word_variable_repeat() {
                     bit_range: BitRange::WholeWord,
                 },
             end: None,
-            repeat: WordRepeat::Dependent { bit_spec, limit },
+            repeat:
+                WordRepeat::Dependent {
+                    count: RepeatCount::BitSpec(bit_spec),
+                    limit,
+                },
+            ..
         } => (w, bit_spec.start.index, limit),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -376,6 +385,7 @@ fn deserialize_word_range_u16_repeat() {
                     bit_range: BitRange::WholeWord,
                 }),
             repeat: WordRepeat::Fixed { number: r },
+            ..
         } => (w, v, r),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -432,9 +442,10 @@ fn deserialize_word_range_u16_variable_repeat() {
                 }),
             repeat:
                 WordRepeat::Dependent {
-                    bit_spec: counter_bit_spec,
+                    count: RepeatCount::BitSpec(counter_bit_spec),
                     limit,
                 },
+            ..
         } => (w, v, counter_bit_spec, limit),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -480,6 +491,7 @@ fn serialise_bit() {
                 },
             end: None,
             repeat: WordRepeat::None,
+            ..
         } => (index, n),
         _ => {
             assert!(false, "Unexpected bit spec found");
@@ -511,6 +523,7 @@ fn serialize_field() {
                 },
             end: None,
             repeat: WordRepeat::None,
+            ..
         } => (w, n, m),
         _ => {
             assert!(false, "Unexpected bit spec found");
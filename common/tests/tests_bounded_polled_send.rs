@@ -0,0 +1,195 @@
+use common::poll::{MaxPolls, PollTimeout};
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseBit,
+    response::ResponseWord, serialize::Serialize,
+};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const POLLED_REQUEST_OPCODE: u8 = 0x10;
+
+struct PolledRequest {
+    arg1: u8,
+}
+
+impl PolledRequest {
+    // flow_control = { type = "polled", on = "status_header", condition = "status", max_polls = 3 }
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<PolledResponse, DeviceError> {
+        let f = |h: StatusHeader| h.status;
+        let response = self.limited_polled_transmit::<2, 2, StatusHeader, 1, _>(
+            spi,
+            f,
+            &mut MaxPolls::new(3),
+        )?;
+
+        Ok(response)
+    }
+
+    // flow_control = { type = "polled", on = "status_header", condition = "status",
+    //                  poll_interval_us = 100, timeout_us = 200 }
+    pub fn send_with_timeout<SPI: SpiDevice, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<PolledResponse, DeviceError> {
+        let f = |h: StatusHeader| h.status;
+        let response = self.limited_polled_transmit::<2, 2, StatusHeader, 1, _>(
+            spi,
+            f,
+            &mut PollTimeout::new(delay, 100, 200),
+        )?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, PolledResponse> for PolledRequest {}
+
+impl Command for PolledRequest {
+    fn opcode(&self) -> u8 {
+        POLLED_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for PolledRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct PolledResponse {
+    status_header: StatusHeader,
+    some_data: u8,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct StatusHeader {
+    status: bool,
+}
+
+impl Deserialize<Self> for StatusHeader {
+    fn deserialize(buf: &[u8]) -> Result<StatusHeader, DeviceError> {
+        let status = buf[0].deserialize_bit(0);
+
+        Ok(Self { status })
+    }
+}
+
+impl Deserialize<Self> for PolledResponse {
+    fn deserialize(buf: &[u8]) -> Result<PolledResponse, DeviceError> {
+        let status_header = StatusHeader::deserialize(&buf[0..=0])?;
+        let some_data = buf[1].deserialize_word();
+
+        Ok(Self {
+            status_header,
+            some_data,
+        })
+    }
+}
+
+fn request_expectations(headers: &[u8]) -> Vec<SpiTransaction<u8>> {
+    let mut expectations = vec![
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x10),
+        SpiTransaction::write(8),
+        SpiTransaction::read_vec(vec![headers[0]]),
+        SpiTransaction::transaction_end(),
+    ];
+    for header in &headers[1..] {
+        expectations.extend([
+            SpiTransaction::transaction_start(),
+            SpiTransaction::read_vec(vec![*header]),
+            SpiTransaction::transaction_end(),
+        ]);
+    }
+    expectations
+}
+
+#[test]
+fn test_max_polls_ready() {
+    let request = PolledRequest { arg1: 8 };
+
+    let mut spi_expectations = request_expectations(&[0, 0, 1]);
+    spi_expectations.extend([
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0xAA]),
+        SpiTransaction::transaction_end(),
+    ]);
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = request.send(&mut spi).unwrap();
+
+    assert_eq!(
+        response,
+        PolledResponse {
+            status_header: StatusHeader { status: true },
+            some_data: 0xAA,
+        }
+    );
+
+    spi.done();
+}
+
+#[test]
+fn test_max_polls_exhausted() {
+    let request = PolledRequest { arg1: 8 };
+
+    let mut spi = SpiMock::new(&request_expectations(&[0, 0, 0]));
+
+    let result = request.send(&mut spi);
+
+    assert!(matches!(result, Err(DeviceError::Timeout)));
+
+    spi.done();
+}
+
+#[test]
+fn test_poll_timeout_ready() {
+    let request = PolledRequest { arg1: 8 };
+
+    let mut spi_expectations = request_expectations(&[0, 1]);
+    spi_expectations.extend([
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x55]),
+        SpiTransaction::transaction_end(),
+    ]);
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut delay = CheckedDelay::new(&[DelayTransaction::delay_us(100)]);
+
+    let response = request.send_with_timeout(&mut spi, &mut delay).unwrap();
+
+    assert_eq!(response.some_data, 0x55);
+
+    spi.done();
+    delay.done();
+}
+
+#[test]
+fn test_poll_timeout_exhausted() {
+    let request = PolledRequest { arg1: 8 };
+
+    let mut spi = SpiMock::new(&request_expectations(&[0, 0, 0]));
+    let mut delay = CheckedDelay::new(&[
+        DelayTransaction::delay_us(100),
+        DelayTransaction::delay_us(100),
+    ]);
+
+    let result = request.send_with_timeout(&mut spi, &mut delay);
+
+    assert!(matches!(result, Err(DeviceError::Timeout)));
+
+    spi.done();
+    delay.done();
+}
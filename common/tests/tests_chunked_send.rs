@@ -0,0 +1,190 @@
+use common::chunk::Chunk;
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseBit,
+    serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const LOAD_REQUEST_OPCODE: u8 = 0x04;
+
+// The chunk size is small to keep the test data short
+type LoadData = Chunk<u8, 2>;
+
+// Bit structure is:
+// arg1 = { bits = "0[0x00]" }
+// image_data = { bits = "1[];<=2", type = "load_data" }
+// with flow_control = { type = "chunked", chunk_size = 2, on = "status_header", condition = "cts" }
+#[derive(Debug, PartialEq, Default)]
+struct LoadRequest {
+    arg1: u8,
+    image_data: LoadData,
+}
+
+impl LoadRequest {
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<LoadResponse, DeviceError> {
+        let f = |h: StatusHeader| h.cts;
+        let response = self.polled_transmit::<3, 1, StatusHeader, 1>(spi, f)?;
+
+        Ok(response)
+    }
+
+    pub fn send_chunked<SPI: SpiDevice>(
+        &mut self,
+        spi: &mut SPI,
+        data: impl IntoIterator<Item = u8>,
+    ) -> Result<LoadResponse, DeviceError> {
+        let mut data = data.into_iter().peekable();
+        loop {
+            self.image_data = LoadData::fill(&mut data);
+            let response = self.send(spi)?;
+            if data.peek().is_none() {
+                return Ok(response);
+            }
+        }
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, LoadResponse> for LoadRequest {}
+
+impl Command for LoadRequest {
+    fn opcode(&self) -> u8 {
+        LOAD_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for LoadRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+
+        data[0].serialize_word(self.arg1);
+        let provider = self.image_data;
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct LoadResponse {
+    status_header: StatusHeader,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct StatusHeader {
+    cts: bool,
+}
+
+impl Deserialize<Self> for StatusHeader {
+    fn deserialize(buf: &[u8]) -> Result<StatusHeader, DeviceError> {
+        let cts = buf[0].deserialize_bit(7);
+
+        Ok(Self { cts })
+    }
+}
+
+impl Deserialize<Self> for LoadResponse {
+    fn deserialize(buf: &[u8]) -> Result<LoadResponse, DeviceError> {
+        let status_header = StatusHeader::deserialize(&buf[0..=0])?;
+
+        Ok(Self { status_header })
+    }
+}
+
+#[test]
+fn test_chunk_fill() {
+    let mut data = [1u8, 2, 3].into_iter();
+
+    let chunk = LoadData::fill(&mut data);
+    assert_eq!(chunk.len(), 2);
+    assert_eq!(chunk.collect::<Vec<u8>>(), vec![1, 2]);
+
+    let chunk = LoadData::fill(&mut data);
+    assert_eq!(chunk.collect::<Vec<u8>>(), vec![3]);
+
+    assert!(LoadData::fill(&mut data).is_empty());
+}
+
+#[test]
+fn test_chunked_send() {
+    let spi_expectations = [
+        // First chunk, the device is ready after one poll
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x04),
+        SpiTransaction::write_vec(vec![0x00, 0x01, 0x02]),
+        SpiTransaction::read_vec(vec![0x00]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x80]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![]),
+        SpiTransaction::transaction_end(),
+        // Second chunk
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x04),
+        SpiTransaction::write_vec(vec![0x00, 0x03, 0x04]),
+        SpiTransaction::read_vec(vec![0x80]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![]),
+        SpiTransaction::transaction_end(),
+        // Last, partly filled, chunk
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x04),
+        SpiTransaction::write_vec(vec![0x00, 0x05]),
+        SpiTransaction::read_vec(vec![0x80]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![]),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let image = [1u8, 2, 3, 4, 5];
+    let response = LoadRequest::default()
+        .send_chunked(&mut spi, image.iter().copied())
+        .unwrap();
+
+    assert_eq!(
+        response,
+        LoadResponse {
+            status_header: StatusHeader { cts: true }
+        }
+    );
+
+    spi.done();
+}
+
+#[test]
+fn test_chunked_send_without_data() {
+    // The command is sent once with an empty chunk, so that there is a response
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x04),
+        SpiTransaction::write_vec(vec![0x00]),
+        SpiTransaction::read_vec(vec![0x80]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![]),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = LoadRequest::default()
+        .send_chunked(&mut spi, core::iter::empty())
+        .unwrap();
+
+    assert_eq!(
+        response,
+        LoadResponse {
+            status_header: StatusHeader { cts: true }
+        }
+    );
+
+    spi.done();
+}
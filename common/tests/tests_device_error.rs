@@ -0,0 +1,133 @@
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseBit,
+    response::ResponseWord, serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const TUNE_REQUEST_OPCODE: u8 = 0x30;
+
+// flow_control = { type = "polled", on = "header", condition = "cts" }
+// errors       = ["err_cmd"]
+struct TuneRequest {
+    frequency: u8,
+}
+
+impl TuneRequest {
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<TuneResponse, DeviceError<Header>> {
+        let f = |h: Header| h.cts;
+        let response = self
+            .polled_transmit::<1, 2, Header, 1>(spi, f)
+            .map_err(DeviceError::with_header)?;
+
+        let h = response.header;
+        if h.err_cmd {
+            return Err(DeviceError::Device { header: h });
+        }
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, TuneResponse> for TuneRequest {}
+
+impl Command for TuneRequest {
+    fn opcode(&self) -> u8 {
+        TUNE_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for TuneRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.frequency);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Header {
+    cts: bool,
+    err_cmd: bool,
+}
+
+impl Deserialize<Self> for Header {
+    fn deserialize(buf: &[u8]) -> Result<Header, DeviceError> {
+        let cts = buf[0].deserialize_bit(7);
+        let err_cmd = buf[0].deserialize_bit(6);
+
+        Ok(Self { cts, err_cmd })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct TuneResponse {
+    header: Header,
+    rssi: u8,
+}
+
+impl Deserialize<Self> for TuneResponse {
+    fn deserialize(buf: &[u8]) -> Result<TuneResponse, DeviceError> {
+        let header = Header::deserialize(&buf[0..=0])?;
+        let rssi = buf[1].deserialize_word();
+
+        Ok(Self { header, rssi })
+    }
+}
+
+fn expectations(header: u8) -> [SpiTransaction<u8>; 8] {
+    [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x30),
+        SpiTransaction::write(0x42),
+        SpiTransaction::read_vec(vec![header]),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x17]),
+        SpiTransaction::transaction_end(),
+    ]
+}
+
+#[test]
+fn test_no_device_error() {
+    let mut spi = SpiMock::new(&expectations(0b1000_0000));
+
+    let response = TuneRequest { frequency: 0x42 }.send(&mut spi).unwrap();
+
+    assert_eq!(response.rssi, 0x17);
+
+    spi.done();
+}
+
+#[test]
+fn test_device_error() {
+    let mut spi = SpiMock::new(&expectations(0b1100_0000));
+
+    let result = TuneRequest { frequency: 0x42 }.send(&mut spi);
+
+    assert!(matches!(
+        result,
+        Err(DeviceError::Device {
+            header: Header {
+                cts: true,
+                err_cmd: true
+            }
+        })
+    ));
+
+    spi.done();
+}
+
+#[test]
+fn test_bus_error_with_header() {
+    let error: DeviceError<Header> = DeviceError::Timeout.with_header();
+
+    assert!(matches!(error, DeviceError::Timeout));
+}
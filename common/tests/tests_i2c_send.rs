@@ -0,0 +1,239 @@
+use common::i2c_transmit::I2cTransmit;
+use common::DeviceError;
+use common::{
+    command::Command,
+    deserialize::Deserialize,
+    request::RequestWord,
+    response::{ResponseBit, ResponseWord},
+    serialize::Serialize,
+};
+use embedded_hal::i2c::I2c;
+
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+
+const DEVICE_ADDRESS: u8 = 0x20;
+const SIMPLE_REQUEST_OPCODE: u8 = 0x09;
+const POLLED_REQUEST_OPCODE: u8 = 0x10;
+const WRITE_ONLY_REQUEST_OPCODE: u8 = 0x14;
+
+struct SimpleRequest {
+    arg1: u8,
+}
+
+impl SimpleRequest {
+    pub fn send<I2C: I2c>(&self, i2c: &mut I2C) -> Result<SimpleResponse, DeviceError> {
+        let response = self.transmit::<2, 1>(i2c, DEVICE_ADDRESS)?;
+
+        Ok(response)
+    }
+}
+
+impl<I2C: I2c> I2cTransmit<I2C, SimpleResponse> for SimpleRequest {}
+
+impl Command for SimpleRequest {
+    fn opcode(&self) -> u8 {
+        SIMPLE_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for SimpleRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct SimpleResponse {
+    status: bool,
+}
+
+impl Deserialize<Self> for SimpleResponse {
+    fn deserialize(buf: &[u8]) -> Result<SimpleResponse, DeviceError> {
+        let status = buf[0].deserialize_bit(0);
+
+        Ok(Self { status })
+    }
+}
+
+struct WriteOnlyRequest {
+    arg1: u8,
+}
+
+impl WriteOnlyRequest {
+    pub fn send<I2C: I2c>(&self, i2c: &mut I2C) -> Result<EmptyResponse, DeviceError> {
+        let response = self.transmit::<1, 0>(i2c, DEVICE_ADDRESS)?;
+
+        Ok(response)
+    }
+}
+
+impl<I2C: I2c> I2cTransmit<I2C, EmptyResponse> for WriteOnlyRequest {}
+
+impl Command for WriteOnlyRequest {
+    fn opcode(&self) -> u8 {
+        WRITE_ONLY_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for WriteOnlyRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct EmptyResponse {}
+
+impl Deserialize<Self> for EmptyResponse {
+    fn deserialize(_buf: &[u8]) -> Result<EmptyResponse, DeviceError> {
+        Ok(Self {})
+    }
+}
+
+struct PolledRequest {
+    arg1: u8,
+}
+
+impl PolledRequest {
+    pub fn send<I2C: I2c>(&self, i2c: &mut I2C) -> Result<PolledResponse, DeviceError> {
+        let f = |h: StatusHeader| h.status;
+        let response = self.polled_transmit::<2, 2, StatusHeader, 1>(i2c, DEVICE_ADDRESS, f)?;
+
+        Ok(response)
+    }
+}
+
+impl<I2C: I2c> I2cTransmit<I2C, PolledResponse> for PolledRequest {}
+
+impl Command for PolledRequest {
+    fn opcode(&self) -> u8 {
+        POLLED_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for PolledRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct PolledResponse {
+    status_header: StatusHeader,
+    some_data: u8,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct StatusHeader {
+    status: bool,
+}
+
+impl Deserialize<Self> for StatusHeader {
+    fn deserialize(buf: &[u8]) -> Result<StatusHeader, DeviceError> {
+        let status = buf[0].deserialize_bit(0);
+
+        Ok(Self { status })
+    }
+}
+
+impl Deserialize<Self> for PolledResponse {
+    fn deserialize(buf: &[u8]) -> Result<PolledResponse, DeviceError> {
+        let status_header = StatusHeader::deserialize(&buf[0..=0])?;
+        let some_data = buf[1].deserialize_word();
+
+        Ok(Self {
+            status_header,
+            some_data,
+        })
+    }
+}
+
+#[test]
+fn test_i2c_simple_request() {
+    let request = SimpleRequest { arg1: 8 };
+
+    let expected_response = SimpleResponse { status: true };
+
+    let i2c_expectations = [
+        I2cTransaction::transaction_start(DEVICE_ADDRESS),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x09]),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![8]),
+        I2cTransaction::read(DEVICE_ADDRESS, vec![0b000_0001]),
+        I2cTransaction::transaction_end(DEVICE_ADDRESS),
+    ];
+
+    let mut i2c = I2cMock::new(&i2c_expectations);
+
+    let response = request.send(&mut i2c).unwrap();
+
+    assert_eq!(response, expected_response);
+
+    i2c.done();
+}
+
+#[test]
+fn test_i2c_write_only_request() {
+    let request = WriteOnlyRequest { arg1: 0x55 };
+
+    let i2c_expectations = [
+        I2cTransaction::transaction_start(DEVICE_ADDRESS),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x14]),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x55]),
+        I2cTransaction::transaction_end(DEVICE_ADDRESS),
+    ];
+
+    let mut i2c = I2cMock::new(&i2c_expectations);
+
+    let response = request.send(&mut i2c).unwrap();
+
+    assert_eq!(response, EmptyResponse {});
+
+    i2c.done();
+}
+
+#[test]
+fn test_i2c_polled_request() {
+    let request = PolledRequest { arg1: 8 };
+
+    let expected_response = PolledResponse {
+        status_header: StatusHeader { status: true },
+        some_data: 0xAA,
+    };
+
+    let i2c_expectations = [
+        I2cTransaction::transaction_start(DEVICE_ADDRESS),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x10]),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![8]),
+        I2cTransaction::read(DEVICE_ADDRESS, vec![0b000_0000]),
+        I2cTransaction::transaction_end(DEVICE_ADDRESS),
+        I2cTransaction::read(DEVICE_ADDRESS, vec![0b000_0001]),
+        // The response is read again from the status header
+        I2cTransaction::read(DEVICE_ADDRESS, vec![0b000_0001, 0xAA]),
+    ];
+
+    let mut i2c = I2cMock::new(&i2c_expectations);
+
+    let response = request.send(&mut i2c).unwrap();
+
+    assert_eq!(response, expected_response);
+
+    i2c.done();
+}
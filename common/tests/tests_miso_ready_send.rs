@@ -0,0 +1,135 @@
+use common::ready_pin::ActiveLevel;
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseWord,
+    serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const CONVERT_REQUEST_OPCODE: u8 = 0x20;
+
+// flow_control = { type = "miso_ready", active = "low", max_retries = 3 }
+struct ConvertRequest {
+    channel: u8,
+}
+
+impl ConvertRequest {
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<ConvertResponse, DeviceError> {
+        let response = self.miso_ready_transmit::<1, 2>(spi, ActiveLevel::Low, 3)?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, ConvertResponse> for ConvertRequest {}
+
+impl Command for ConvertRequest {
+    fn opcode(&self) -> u8 {
+        CONVERT_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for ConvertRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.channel);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ConvertResponse {
+    value: u16,
+}
+
+impl Deserialize<Self> for ConvertResponse {
+    fn deserialize(buf: &[u8]) -> Result<ConvertResponse, DeviceError> {
+        let value = buf[0..=1].deserialize_word();
+
+        Ok(Self { value })
+    }
+}
+
+fn request_expectations() -> Vec<SpiTransaction<u8>> {
+    vec![
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x20),
+        SpiTransaction::write(0x01),
+        SpiTransaction::transaction_end(),
+    ]
+}
+
+// Each dummy word is read in its own transaction, so CS is deasserted between the reads
+fn miso_read(level: u8) -> [SpiTransaction<u8>; 3] {
+    [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read(level),
+        SpiTransaction::transaction_end(),
+    ]
+}
+
+#[test]
+fn test_miso_ready_request() {
+    let mut spi_expectations = request_expectations();
+    spi_expectations.extend(miso_read(0xFF));
+    spi_expectations.extend(miso_read(0x00));
+    spi_expectations.extend([
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x34, 0x12]),
+        SpiTransaction::transaction_end(),
+    ]);
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = ConvertRequest { channel: 1 }.send(&mut spi).unwrap();
+
+    assert_eq!(response, ConvertResponse { value: 0x1234 });
+
+    spi.done();
+}
+
+#[test]
+fn test_miso_ready_not_ready() {
+    // The first read and then the 3 retries
+    let mut spi_expectations = request_expectations();
+    for _ in 0..4 {
+        spi_expectations.extend(miso_read(0xFF));
+    }
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let result = ConvertRequest { channel: 1 }.send(&mut spi);
+
+    assert!(matches!(result, Err(DeviceError::NotReady)));
+
+    spi.done();
+}
+
+#[test]
+fn test_miso_ready_after_last_retry() {
+    let mut spi_expectations = request_expectations();
+    for _ in 0..3 {
+        spi_expectations.extend(miso_read(0xFF));
+    }
+    spi_expectations.extend(miso_read(0x00));
+    spi_expectations.extend([
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x34, 0x12]),
+        SpiTransaction::transaction_end(),
+    ]);
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = ConvertRequest { channel: 1 }.send(&mut spi).unwrap();
+
+    assert_eq!(response, ConvertResponse { value: 0x1234 });
+
+    spi.done();
+}
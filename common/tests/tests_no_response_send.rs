@@ -0,0 +1,130 @@
+use common::i2c_transmit::I2cTransmit;
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{command::Command, request::RequestWord, serialize::Serialize};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const DEVICE_ADDRESS: u8 = 0x48;
+const WRITE_CONFIG_REQUEST_OPCODE: u8 = 0x10;
+const RESET_REQUEST_OPCODE: u8 = 0x06;
+
+// A command without a response section
+struct WriteConfigRequest {
+    config: u8,
+}
+
+impl WriteConfigRequest {
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<(), DeviceError> {
+        self.transmit::<1, 0>(spi)
+    }
+
+    pub fn timed_send<SPI: SpiDevice, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), DeviceError> {
+        self.timed_transmit::<1, 0, DELAY>(spi, delay, 100)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, ()> for WriteConfigRequest {}
+
+impl Command for WriteConfigRequest {
+    fn opcode(&self) -> u8 {
+        WRITE_CONFIG_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for WriteConfigRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.config);
+
+        (1, data, provider)
+    }
+}
+
+// A command without a request or a response section
+struct ResetRequest {}
+
+impl ResetRequest {
+    pub fn send<I2C: I2c>(&self, i2c: &mut I2C) -> Result<(), DeviceError> {
+        self.transmit::<0, 0>(i2c, DEVICE_ADDRESS)
+    }
+}
+
+impl<I2C: I2c> I2cTransmit<I2C, ()> for ResetRequest {}
+
+impl Command for ResetRequest {
+    fn opcode(&self) -> u8 {
+        RESET_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for ResetRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        (0, [0u8; LEN], core::iter::empty::<u8>())
+    }
+}
+
+#[test]
+fn test_send_without_response() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x10),
+        SpiTransaction::write(0x2A),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    WriteConfigRequest { config: 0x2A }.send(&mut spi).unwrap();
+
+    spi.done();
+}
+
+#[test]
+fn test_timed_send_without_response() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x10),
+        SpiTransaction::write(0x2A),
+        SpiTransaction::transaction_end(),
+    ];
+    let delay_expectations = [DelayTransaction::delay_us(100)];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut delay = CheckedDelay::new(&delay_expectations);
+
+    WriteConfigRequest { config: 0x2A }
+        .timed_send(&mut spi, &mut delay)
+        .unwrap();
+
+    spi.done();
+    delay.done();
+}
+
+#[test]
+fn test_send_without_request_or_response_i2c() {
+    let i2c_expectations = [
+        I2cTransaction::transaction_start(DEVICE_ADDRESS),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x06]),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![]),
+        I2cTransaction::transaction_end(DEVICE_ADDRESS),
+    ];
+
+    let mut i2c = I2cMock::new(&i2c_expectations);
+
+    ResetRequest {}.send(&mut i2c).unwrap();
+
+    i2c.done();
+}
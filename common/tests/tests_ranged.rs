@@ -0,0 +1,82 @@
+use common::ranged::Ranged;
+use common::{
+    deserialize::Deserialize, request::RequestField, response::ResponseField, serialize::Serialize,
+    DeviceError,
+};
+
+// tr_size = { bits = "0[0..3]", type = "u8", min = 1, max = 15 }
+// ctun    = { bits = "1[]", type = "u8", min = 0, max = 63 }
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Tune {
+    tr_size: Ranged<u8, 1, 15>,
+    ctun: Ranged<u8, 0, 63>,
+}
+
+impl Serialize for Tune {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_field(self.tr_size.value(), 0, 3);
+        data[1] = self.ctun.value();
+
+        (2, data, provider)
+    }
+}
+
+impl Deserialize<Self> for Tune {
+    fn deserialize(buf: &[u8]) -> Result<Tune, DeviceError> {
+        let tr_size = Ranged::new(buf[0].deserialize_field(0, 3))?;
+        let ctun = Ranged::new(buf[1])?;
+
+        Ok(Self { tr_size, ctun })
+    }
+}
+
+#[test]
+fn test_ranged_new() {
+    assert_eq!(Ranged::<u8, 1, 15>::new(1).unwrap().value(), 1);
+    assert_eq!(Ranged::<u8, 1, 15>::new(15).unwrap().value(), 15);
+    assert!(matches!(
+        Ranged::<u8, 1, 15>::new(0),
+        Err(DeviceError::OutOfRange)
+    ));
+    assert!(matches!(
+        Ranged::<u8, 1, 15>::new(16),
+        Err(DeviceError::OutOfRange)
+    ));
+    assert_eq!(Ranged::<i8, -128, 63>::new(-100).unwrap().value(), -100);
+}
+
+#[test]
+fn test_ranged_default() {
+    assert_eq!(Ranged::<u8, 1, 15>::default().value(), 1);
+    assert_eq!(Ranged::<i16, -40, 85>::default().value(), -40);
+}
+
+#[test]
+fn test_ranged_se_de() {
+    let tune = Tune {
+        tr_size: Ranged::new(7).unwrap(),
+        ctun: Ranged::new(63).unwrap(),
+    };
+
+    let (size, data, _) = tune.serialize::<2>();
+    assert_eq!(size, 2);
+    assert_eq!(data, [7, 63]);
+
+    assert_eq!(Tune::deserialize(&data).unwrap(), tune);
+}
+
+#[test]
+fn test_ranged_deserialize_out_of_range() {
+    assert!(matches!(
+        Tune::deserialize(&[0, 10]),
+        Err(DeviceError::OutOfRange)
+    ));
+    assert!(matches!(
+        Tune::deserialize(&[5, 64]),
+        Err(DeviceError::OutOfRange)
+    ));
+}
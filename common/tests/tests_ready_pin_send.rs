@@ -0,0 +1,145 @@
+use common::ready_pin::{ActiveLevel, ReadyPin, ReadyPinWithTimeout};
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseWord,
+    serialize::Serialize,
+};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::InputPin;
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::digital::{
+    Mock as PinMock, State as PinState, Transaction as PinTransaction,
+};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const STATUS_REQUEST_OPCODE: u8 = 0x09;
+
+// flow_control = { type = "ready_pin", active = "low" }
+struct StatusRequest {
+    arg1: u8,
+}
+
+impl StatusRequest {
+    pub fn send<SPI: SpiDevice, READY: InputPin>(
+        &self,
+        spi: &mut SPI,
+        ready: &mut READY,
+    ) -> Result<StatusResponse, DeviceError> {
+        let response = self
+            .ready_pin_transmit::<1, 1, _>(spi, &mut ReadyPin::new(ready, ActiveLevel::Low))?;
+
+        Ok(response)
+    }
+
+    // flow_control = { type = "ready_pin", active = "low", timeout_us = 20 }
+    pub fn send_with_timeout<SPI: SpiDevice, READY: InputPin, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        ready: &mut READY,
+        delay: &mut DELAY,
+    ) -> Result<StatusResponse, DeviceError> {
+        let response = self.ready_pin_transmit::<1, 1, _>(
+            spi,
+            &mut ReadyPinWithTimeout::new(ready, ActiveLevel::Low, delay, 20),
+        )?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, StatusResponse> for StatusRequest {}
+
+impl Command for StatusRequest {
+    fn opcode(&self) -> u8 {
+        STATUS_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for StatusRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.arg1);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct StatusResponse {
+    status: u8,
+}
+
+impl Deserialize<Self> for StatusResponse {
+    fn deserialize(buf: &[u8]) -> Result<StatusResponse, DeviceError> {
+        let status = buf[0].deserialize_word();
+
+        Ok(Self { status })
+    }
+}
+
+fn request_expectations() -> [SpiTransaction<u8>; 4] {
+    [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x09),
+        SpiTransaction::write(0x08),
+        SpiTransaction::transaction_end(),
+    ]
+}
+
+#[test]
+fn test_ready_pin_request() {
+    let mut spi_expectations = request_expectations().to_vec();
+    spi_expectations.extend([
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read(0xAA),
+        SpiTransaction::transaction_end(),
+    ]);
+    let pin_expectations = [
+        PinTransaction::get(PinState::High),
+        PinTransaction::get(PinState::High),
+        PinTransaction::get(PinState::Low),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut ready = PinMock::new(&pin_expectations);
+
+    let response = StatusRequest { arg1: 8 }
+        .send(&mut spi, &mut ready)
+        .unwrap();
+
+    assert_eq!(response, StatusResponse { status: 0xAA });
+
+    spi.done();
+    ready.done();
+}
+
+#[test]
+fn test_ready_pin_timeout() {
+    let spi_expectations = request_expectations();
+    // Read at 0, 10 and 20 us before giving up
+    let pin_expectations = [
+        PinTransaction::get(PinState::High),
+        PinTransaction::get(PinState::High),
+        PinTransaction::get(PinState::High),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut ready = PinMock::new(&pin_expectations);
+
+    let result = StatusRequest { arg1: 8 }.send_with_timeout(
+        &mut spi,
+        &mut ready,
+        &mut NoopDelay::new(),
+    );
+
+    assert!(matches!(result, Err(DeviceError::Timeout)));
+
+    spi.done();
+    ready.done();
+}
@@ -0,0 +1,128 @@
+use common::register::{RegisterRead, RegisterWritten};
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestField, response::ResponseField,
+    serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+// A read/write register.
+// Bit structure is:
+// mode = {bits = "0[0..2]", type = "u8"}
+// gain = {bits = "0[4..7]", type = "u8"}
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct Config {
+    mode: u8,
+    gain: u8,
+}
+
+impl Config {
+    const ADDRESS: u8 = 0x05;
+
+    pub fn read<SPI: SpiDevice>(spi: &mut SPI) -> Result<Self, DeviceError> {
+        RegisterRead::new(Self::ADDRESS).transmit::<0, 1>(spi)
+    }
+
+    pub fn write<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<(), DeviceError> {
+        self.transmit::<1, 0>(spi)?;
+        Ok(())
+    }
+
+    pub fn modify<SPI: SpiDevice, F: FnOnce(&mut Self)>(
+        spi: &mut SPI,
+        f: F,
+    ) -> Result<(), DeviceError> {
+        let mut register = Self::read(spi)?;
+        f(&mut register);
+        register.write(spi)
+    }
+}
+
+impl Command for Config {
+    fn opcode(&self) -> u8 {
+        Self::ADDRESS
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, RegisterWritten> for Config {}
+
+impl Serialize for Config {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_field(self.mode, 0, 2);
+        data[0].serialize_field(self.gain, 4, 7);
+
+        (1, data, provider)
+    }
+}
+
+impl Deserialize<Self> for Config {
+    fn deserialize(buf: &[u8]) -> Result<Config, DeviceError> {
+        let mode = buf[0].deserialize_field(0, 2);
+        let gain = buf[0].deserialize_field(4, 7);
+
+        Ok(Self { mode, gain })
+    }
+}
+
+#[test]
+fn test_register_read() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x05),
+        SpiTransaction::write_vec(vec![]),
+        SpiTransaction::read(0b1010_0011),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let config = Config::read(&mut spi).unwrap();
+
+    assert_eq!(config, Config { mode: 3, gain: 10 });
+
+    spi.done();
+}
+
+#[test]
+fn test_register_write() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x05),
+        SpiTransaction::write(0b0110_0001),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    Config { mode: 1, gain: 6 }.write(&mut spi).unwrap();
+
+    spi.done();
+}
+
+#[test]
+fn test_register_modify() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x05),
+        SpiTransaction::write_vec(vec![]),
+        SpiTransaction::read(0b1010_0011),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x05),
+        SpiTransaction::write(0b1010_0101),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    Config::modify(&mut spi, |config| config.mode = 5).unwrap();
+
+    spi.done();
+}
@@ -0,0 +1,159 @@
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command,
+    deserialize::Deserialize,
+    request::{RequestArray, RequestWord},
+    response::{ResponseArray, ResponseWord},
+    serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const VALUES_REQUEST_OPCODE: u8 = 0x12;
+
+// n_values = { bits = "0[]" }
+// values   = { bits = "1[];(n_values)<=2" }
+// crc      = { bits = "(>values)[]" }
+struct ValuesRequest {
+    n_values: u8,
+    values: [u8; 2],
+    crc: u8,
+}
+
+impl ValuesRequest {
+    pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<ValuesResponse, DeviceError> {
+        self.transmit::<4, 4>(spi)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, ValuesResponse> for ValuesRequest {}
+
+impl Command for ValuesRequest {
+    fn opcode(&self) -> u8 {
+        VALUES_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for ValuesRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.n_values);
+        data[1..].serialize_repeating_words(self.values, self.n_values as usize);
+        data[1 + self.n_values as usize].serialize_word(self.crc);
+
+        (2 + self.n_values as usize, data, provider)
+    }
+
+    fn check_counts(&self) -> Result<(), DeviceError> {
+        if self.n_values as usize > 2 {
+            return Err(DeviceError::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+// n_values = { bits = "0[]" }
+// values   = { bits = "1[];(n_values)<=2" }
+// crc      = { bits = "(>values)[]" }
+#[derive(Debug, PartialEq)]
+struct ValuesResponse {
+    n_values: u8,
+    values: [u8; 2],
+    crc: u8,
+}
+
+impl Deserialize<Self> for ValuesResponse {
+    fn deserialize(buf: &[u8]) -> Result<ValuesResponse, DeviceError> {
+        let n_values = buf[0].deserialize_word();
+        if n_values as usize > 2 {
+            return Err(DeviceError::OutOfRange);
+        }
+        let values = buf[1..].deserialize_repeating_words(n_values as usize);
+        let crc = buf[1 + n_values as usize].deserialize_word();
+
+        Ok(Self {
+            n_values,
+            values,
+            crc,
+        })
+    }
+}
+
+#[test]
+fn test_count_within_limit() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x12),
+        SpiTransaction::write_vec(vec![2, 0x0A, 0x0B, 0xCC]),
+        SpiTransaction::read_vec(vec![1, 0x0A, 0xCC, 0x00]),
+        SpiTransaction::transaction_end(),
+    ];
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let request = ValuesRequest {
+        n_values: 2,
+        values: [0x0A, 0x0B],
+        crc: 0xCC,
+    };
+    let response = request.send(&mut spi).unwrap();
+
+    assert_eq!(
+        response,
+        ValuesResponse {
+            n_values: 1,
+            values: [0x0A, 0],
+            crc: 0xCC,
+        }
+    );
+
+    spi.done();
+}
+
+#[test]
+fn test_request_count_over_limit() {
+    // Nothing is sent
+    let mut spi = SpiMock::new(&[]);
+
+    let request = ValuesRequest {
+        n_values: 3,
+        values: [0x0A, 0x0B],
+        crc: 0xCC,
+    };
+
+    assert!(matches!(
+        request.send(&mut spi),
+        Err(DeviceError::OutOfRange)
+    ));
+
+    spi.done();
+}
+
+#[test]
+fn test_response_count_over_limit() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x12),
+        SpiTransaction::write_vec(vec![0, 0xCC]),
+        SpiTransaction::read_vec(vec![20, 0x0A, 0x0B, 0xCC]),
+        SpiTransaction::transaction_end(),
+    ];
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let request = ValuesRequest {
+        n_values: 0,
+        values: [0, 0],
+        crc: 0xCC,
+    };
+
+    assert!(matches!(
+        request.send(&mut spi),
+        Err(DeviceError::OutOfRange)
+    ));
+
+    spi.done();
+}
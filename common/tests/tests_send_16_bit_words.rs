@@ -0,0 +1,99 @@
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command,
+    deserialize::Deserialize,
+    request::{RequestField, RequestWord},
+    response::{ResponseBit, ResponseWord},
+    serialize::Serialize,
+};
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const WIDE_REQUEST_OPCODE: u8 = 0x21;
+
+// A request for a device with 16 bit words.
+// Bit structure is:
+// a_field = {bits = "0[0..11]", type = "u16"}
+// a_u32 = {bits = "1[]..2[]"}
+struct WideRequest {
+    a_field: u16,
+    a_u32: u32,
+}
+
+impl WideRequest {
+    pub fn send<SPI: SpiDevice<u16>>(&self, spi: &mut SPI) -> Result<WideResponse, DeviceError> {
+        let response = self.transmit::<3, 2>(spi)?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice<u16>> Transmit<SPI, WideResponse, u16> for WideRequest {}
+
+impl Command for WideRequest {
+    fn opcode(&self) -> u8 {
+        WIDE_REQUEST_OPCODE
+    }
+}
+
+impl Serialize<u16> for WideRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u16; LEN], impl Iterator<Item = u16>) {
+        let mut data = [0u16; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u16>();
+
+        data[0].serialize_field(self.a_field, 0, 11);
+        data[1..=2].serialize_word(self.a_u32);
+
+        (3, data, provider)
+    }
+}
+
+// Bit structure is:
+// ready = {bits = "0[15]"}
+// a_u16 = {bits = "1[]"}
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct WideResponse {
+    ready: bool,
+    a_u16: u16,
+}
+
+impl Deserialize<Self, u16> for WideResponse {
+    fn deserialize(buf: &[u16]) -> Result<WideResponse, DeviceError> {
+        let ready = buf[0].deserialize_bit(15);
+        let a_u16 = buf[1].deserialize_word();
+
+        Ok(Self { ready, a_u16 })
+    }
+}
+
+#[test]
+fn test_16_bit_word_request() {
+    let request = WideRequest {
+        a_field: 0xABC,
+        a_u32: 0x1234_5678,
+    };
+
+    let expected_response = WideResponse {
+        ready: true,
+        a_u16: 0xBEEF,
+    };
+
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x0021),
+        SpiTransaction::write_vec(vec![0x0ABC, 0x5678, 0x1234]),
+        SpiTransaction::read_vec(vec![0x8000, 0xBEEF]),
+        SpiTransaction::transaction_end(),
+    ];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+
+    let response = request.send(&mut spi).unwrap();
+
+    assert_eq!(response, expected_response);
+
+    spi.done();
+}
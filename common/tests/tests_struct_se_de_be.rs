@@ -0,0 +1,279 @@
+use common::deserialize::Deserialize;
+use common::error::DeviceError;
+use common::request::{RequestArray, RequestBit, RequestField, RequestSpanningField, RequestWord};
+use common::response::{
+    ResponseArray, ResponseBit, ResponseField, ResponseSpanningField, ResponseWord,
+};
+use common::serialize::Serialize;
+
+// An enum for testing
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TestField {
+    Disabled = 0,
+    Enabled = 1,
+    Tristate = 2,
+}
+
+impl TryFrom<u8> for TestField {
+    type Error = DeviceError;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Disabled),
+            1 => Ok(Self::Enabled),
+            2 => Ok(Self::Tristate),
+            _ => Err(DeviceError::EnumConversion),
+        }
+    }
+}
+
+// A struct for testing request/serialization on a big endian device.
+// Bit structure is:
+// a_bit =  {bits = "4"}
+// a_field =  {bits = "[5..6]"}
+// a_u16 =  {bits = "1[]..2[]"}
+// a_u8 =  {bits = "3[]"}
+// a_count =  {bits = "4[]"}
+// a_repeating_u16 =  {bits = "5[]..6[];(4[])<=6" }
+// a_u32 = {bits = "17[]..20[]"}
+//
+#[derive(PartialEq, Debug, Copy, Clone)]
+struct TestStruct {
+    a_bit: bool,
+    a_field: TestField,
+    a_u8: u8,
+    a_u16: u16,
+    a_count: u8,
+    a_repeating_u16: [u16; 6],
+    a_u32: u32,
+}
+
+// This is the code that is generated for a device with `endian = "be"`
+impl Serialize for TestStruct {
+    fn serialize<const N: usize>(&self) -> (usize, [u8; N], impl Iterator<Item = u8>) {
+        let mut data = [0u8; N];
+
+        data[0].serialize_bit(self.a_bit, 4);
+        data[0].serialize_field(self.a_field as u8, 5, 6);
+        data[1..=2].serialize_word_be(self.a_u16);
+        data[3].serialize_word(self.a_u8);
+        data[4].serialize_word(self.a_count);
+        data[5..].serialize_repeating_words_be(self.a_repeating_u16, self.a_count.into());
+        data[17..=20].serialize_word_be(self.a_u32);
+
+        (21, data, std::iter::empty::<u8>())
+    }
+}
+
+impl Deserialize<Self> for TestStruct {
+    fn deserialize(buf: &[u8]) -> Result<TestStruct, DeviceError> {
+        let a_bit = buf[0].deserialize_bit(4);
+        let a_field = buf[0].deserialize_field(5, 6).try_into()?;
+        let a_u16 = buf[1..=2].deserialize_word_be();
+        let a_u8 = buf[3].deserialize_word();
+        let a_count = buf[4].deserialize_word();
+        let a_repeating_u16 =
+            buf[5..5 + (a_count * 2) as usize].deserialize_repeating_words_be(a_count as usize);
+        let a_u32 = buf[17..=20].deserialize_word_be();
+
+        Ok(Self {
+            a_bit,
+            a_field,
+            a_u16,
+            a_u8,
+            a_count,
+            a_repeating_u16,
+            a_u32,
+        })
+    }
+}
+
+#[test]
+fn test_deserialize_repeating_word_be() {
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    struct TestStruct {
+        a_repeating_u8: [u8; 2],
+        a_repeating_u16: [u16; 4],
+    }
+
+    let expected_struct = TestStruct {
+        a_repeating_u8: [206, 86],
+        a_repeating_u16: [11111, 22222, 33333, 44444],
+    };
+
+    // Bit structure is:
+    // a_repeating_u8 = {bits = "0[];2"}
+    // a_repeating_u16: {bits = "2[]..3[];4"}
+    #[rustfmt::skip]
+    let data: [u8; 10] = [
+        // [206, 86],
+        0xCE,
+        0x56,
+
+        //[11111, 22222, 33333, 44444],
+        0x2B, 0x67,
+        0x56, 0xCE,
+        0x82, 0x35,
+        0xAD, 0x9C,
+        ];
+
+    let deserialized_test_struct = TestStruct {
+        a_repeating_u8: data[0..=1].deserialize_repeating_words_be(2),
+        a_repeating_u16: data[2..=9].deserialize_repeating_words_be(4),
+    };
+
+    assert_eq!(deserialized_test_struct, expected_struct);
+}
+
+#[test]
+fn test_serialize_struct_be() {
+    let test_request = TestStruct {
+        a_bit: true,
+        a_field: TestField::Enabled,
+        a_u8: 100,
+        a_u16: 22222,
+        a_count: 3,
+        a_repeating_u16: [44444, 33333, 22222, 0, 0, 0],
+        a_u32: 0x12345678,
+    };
+
+    #[rustfmt::skip]
+    let expected_data: [u8; 24] = [
+        // a_bit | a_field
+        0b0001_0000 | 0b0010_0000,
+        // a_u16
+        0x56, 0xCE,
+        // a_u8
+        0x64,
+        // a_count
+        0x03,
+        // a_repeating_u16
+        0xAD, 0x9C,
+        0x82, 0x35,
+        0x56, 0xCE,
+        0, 0, 0, 0, 0, 0,
+        // a_u32
+        0x12, 0x34, 0x56, 0x78,
+        0, 0, 0,
+    ];
+
+    let (count, data, _) = test_request.serialize();
+
+    assert_eq!(count, 21);
+    assert_eq!(data, expected_data);
+}
+
+#[test]
+fn test_deserialize_struct_be() {
+    #[rustfmt::skip]
+    let data: [u8; 21] = [
+        // a_bit | a_field
+        0b0001_0000 | 0b0010_0000,
+        // a_u16
+        0x56, 0xCE,
+        // a_u8
+        0x64,
+        //a_count
+        0x4,
+        // a_u16_repeating [11111, 22222, 33333, 44444],
+        0x2B, 0x67,
+        0x56, 0xCE,
+        0x82, 0x35,
+        0xAD, 0x9C,
+        0, 0, 0, 0,
+        // a_u32
+        0xFE, 0xDC, 0xBA, 0x98,
+    ];
+
+    let expected = TestStruct {
+        a_bit: true,
+        a_field: TestField::Enabled,
+        a_u8: 100,
+        a_u16: 22222,
+        a_count: 4,
+        a_repeating_u16: [11111, 22222, 33333, 44444, 0, 0],
+        a_u32: 0xFEDCBA98,
+    };
+
+    let test_struct = TestStruct::deserialize(&data).unwrap();
+
+    assert_eq!(test_struct, expected);
+}
+
+#[test]
+fn test_round_trip_be() {
+    let test_request = TestStruct {
+        a_bit: false,
+        a_field: TestField::Tristate,
+        a_u8: 7,
+        a_u16: 0xABCD,
+        a_count: 2,
+        a_repeating_u16: [0x0102, 0x0304, 0, 0, 0, 0],
+        a_u32: 0xDEADBEEF,
+    };
+
+    let (_, data, _) = test_request.serialize::<21>();
+
+    assert_eq!(data[1..=2], [0xAB, 0xCD]);
+    assert_eq!(data[5..=8], [0x01, 0x02, 0x03, 0x04]);
+
+    let test_response = TestStruct::deserialize(&data).unwrap();
+
+    assert_eq!(test_response, test_request);
+}
+
+#[test]
+fn test_spanning_field_round_trip_be() {
+    // Bit structure is:
+    // a_u12 = {bits = "0[0..3]..1[]"}
+    // a_flag = {bits = "0[7]"}
+    // an_i20 = {bits = "2[]..4[4..7]"}
+    // a_field = {bits = "4[0..3]"}
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    struct SpanningStruct {
+        a_u12: u16,
+        a_flag: bool,
+        an_i20: i32,
+        a_field: u8,
+    }
+
+    impl Serialize for SpanningStruct {
+        fn serialize<const N: usize>(&self) -> (usize, [u8; N], impl Iterator<Item = u8>) {
+            let mut data = [0u8; N];
+
+            data[0..=1].serialize_spanning_field_be(self.a_u12, 3, 0);
+            data[0].serialize_bit(self.a_flag, 7);
+            data[2..=4].serialize_spanning_field_be(self.an_i20, 7, 4);
+            data[4].serialize_field(self.a_field, 0, 3);
+
+            (5, data, std::iter::empty::<u8>())
+        }
+    }
+
+    impl Deserialize<Self> for SpanningStruct {
+        fn deserialize(buf: &[u8]) -> Result<SpanningStruct, DeviceError> {
+            Ok(Self {
+                a_u12: buf[0..=1].deserialize_spanning_field_be(3, 0),
+                a_flag: buf[0].deserialize_bit(7),
+                an_i20: buf[2..=4].deserialize_spanning_field_be(7, 4),
+                a_field: buf[4].deserialize_field(0, 3),
+            })
+        }
+    }
+
+    let test_request = SpanningStruct {
+        a_u12: 0xABC,
+        a_flag: true,
+        an_i20: -2,
+        a_field: 0x5,
+    };
+
+    let (count, data, _) = test_request.serialize::<5>();
+
+    // The most significant bits are sent first and the bits of each field are contiguous
+    assert_eq!(count, 5);
+    assert_eq!(data, [0x8A, 0xBC, 0xFF, 0xFF, 0xE5]);
+
+    let test_response = SpanningStruct::deserialize(&data).unwrap();
+
+    assert_eq!(test_response, test_request);
+}
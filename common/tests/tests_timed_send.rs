@@ -0,0 +1,132 @@
+use common::i2c_transmit::I2cTransmit;
+use common::transmit::Transmit;
+use common::DeviceError;
+use common::{
+    command::Command, deserialize::Deserialize, request::RequestWord, response::ResponseWord,
+    serialize::Serialize,
+};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
+
+use embedded_hal_mock::eh1::delay::{CheckedDelay, Transaction as DelayTransaction};
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+const DEVICE_ADDRESS: u8 = 0x48;
+const CONVERT_REQUEST_OPCODE: u8 = 0x20;
+
+// A conversion on an ADC that takes 500 us before the result can be read.
+// flow_control = { type = "timed", delay_us = 500 }
+struct ConvertRequest {
+    channel: u8,
+}
+
+impl ConvertRequest {
+    pub fn send<SPI: SpiDevice, DELAY: DelayNs>(
+        &self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<ConvertResponse, DeviceError> {
+        // Fully qualified as the request is sent over both buses in these tests
+        let response = Transmit::timed_transmit::<1, 2, DELAY>(self, spi, delay, 500)?;
+
+        Ok(response)
+    }
+
+    pub fn send_i2c<I2C: I2c, DELAY: DelayNs>(
+        &self,
+        i2c: &mut I2C,
+        delay: &mut DELAY,
+    ) -> Result<ConvertResponse, DeviceError> {
+        let response =
+            I2cTransmit::timed_transmit::<1, 2, DELAY>(self, i2c, DEVICE_ADDRESS, delay, 500)?;
+
+        Ok(response)
+    }
+}
+
+impl<SPI: SpiDevice> Transmit<SPI, ConvertResponse> for ConvertRequest {}
+
+impl<I2C: I2c> I2cTransmit<I2C, ConvertResponse> for ConvertRequest {}
+
+impl Command for ConvertRequest {
+    fn opcode(&self) -> u8 {
+        CONVERT_REQUEST_OPCODE
+    }
+}
+
+impl Serialize for ConvertRequest {
+    fn serialize<const LEN: usize>(&self) -> (usize, [u8; LEN], impl Iterator<Item = u8>) {
+        let mut data = [0u8; LEN];
+        #[allow(unused_variables)]
+        let provider = core::iter::empty::<u8>();
+
+        data[0].serialize_word(self.channel);
+
+        (1, data, provider)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ConvertResponse {
+    value: u16,
+}
+
+impl Deserialize<Self> for ConvertResponse {
+    fn deserialize(buf: &[u8]) -> Result<ConvertResponse, DeviceError> {
+        let value = buf[0..=1].deserialize_word();
+
+        Ok(Self { value })
+    }
+}
+
+#[test]
+fn test_timed_request() {
+    let spi_expectations = [
+        SpiTransaction::transaction_start(),
+        SpiTransaction::write(0x20),
+        SpiTransaction::write(0x03),
+        SpiTransaction::transaction_end(),
+        SpiTransaction::transaction_start(),
+        SpiTransaction::read_vec(vec![0x34, 0x12]),
+        SpiTransaction::transaction_end(),
+    ];
+    let delay_expectations = [DelayTransaction::delay_us(500)];
+
+    let mut spi = SpiMock::new(&spi_expectations);
+    let mut delay = CheckedDelay::new(&delay_expectations);
+
+    let response = ConvertRequest { channel: 3 }
+        .send(&mut spi, &mut delay)
+        .unwrap();
+
+    assert_eq!(response, ConvertResponse { value: 0x1234 });
+
+    spi.done();
+    delay.done();
+}
+
+#[test]
+fn test_timed_request_i2c() {
+    let i2c_expectations = [
+        I2cTransaction::transaction_start(DEVICE_ADDRESS),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x20]),
+        I2cTransaction::write(DEVICE_ADDRESS, vec![0x03]),
+        I2cTransaction::transaction_end(DEVICE_ADDRESS),
+        I2cTransaction::read(DEVICE_ADDRESS, vec![0x34, 0x12]),
+    ];
+    let delay_expectations = [DelayTransaction::delay_us(500)];
+
+    let mut i2c = I2cMock::new(&i2c_expectations);
+    let mut delay = CheckedDelay::new(&delay_expectations);
+
+    let response = ConvertRequest { channel: 3 }
+        .send_i2c(&mut i2c, &mut delay)
+        .unwrap();
+
+    assert_eq!(response, ConvertResponse { value: 0x1234 });
+
+    i2c.done();
+    delay.done();
+}
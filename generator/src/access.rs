@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
 pub enum Access {
     #[serde(alias = "r", alias = "R", alias = "read")]
     Read,
@@ -21,3 +21,13 @@ impl Default for Access {
         Self::ReadWrite
     }
 }
+
+impl Access {
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Access::Read | Access::ReadWrite)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+}
@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+use crate::error_reporting::Diagnostic;
+
+/// Checks every `bits` string in the TOML source with the bit-lang parser. This is
+/// done on the source rather than on the definition, as the fields are deserialized
+/// as an untagged enum, which loses both the bit-lang error and where it is. The span
+/// of each diagnostic is the character in the bit spec at which the error was found.
+///
+/// If the TOML source itself cannot be parsed, no diagnostics are returned and the
+/// error is left to be reported when deserializing the definition.
+pub fn check(toml_source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if let Ok(document) = ImDocument::parse(toml_source) {
+        check_table("", document.as_table(), toml_source, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn check_table(
+    path: &str,
+    table: &dyn TableLike,
+    toml_source: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, item) in table.iter() {
+        if let (Item::Value(Value::String(bits)), "bits") = (item, key) {
+            let (Err(err), Some(span)) = (bit_lang::parse(bits.value()), bits.span()) else {
+                continue;
+            };
+            let span = error_span(toml_source, span, err.position);
+            diagnostics.push(Diagnostic::new(format!("{path}: {err}"), Some(span)));
+        } else if let Some(table) = item.as_table_like() {
+            let path = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{path}.{key}")
+            };
+            check_table(&path, table, toml_source, diagnostics);
+        }
+    }
+}
+
+/// The span of the character at `position` in the bit spec string with the span
+/// `string_span`. Positions at the end of the bit spec point at the closing quote.
+fn error_span(toml_source: &str, string_span: Range<usize>, position: usize) -> Range<usize> {
+    let raw = &toml_source[string_span.clone()];
+    let quote_length = if raw.starts_with("\"\"\"") || raw.starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    let contents = &raw[quote_length..];
+    let offset = contents
+        .char_indices()
+        .nth(position)
+        .map_or(contents.len(), |(offset, _)| offset);
+
+    let start = string_span.start + quote_length + offset;
+    start..start + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_bit_specs() {
+        let toml_source = r#"
+[commands.TEST.request]
+a_field = { bits = "0[0..4]" }
+b_field = { bits = '1[];(a_field)<=4' }
+c_field = { bits = """2[]..3[]""" }
+"#;
+
+        assert!(check(toml_source).is_empty());
+    }
+
+    #[test]
+    fn test_bit_spec_errors() {
+        let toml_source = r#"
+[commands.TEST.request]
+a_field = { bits = "0[0..4" }
+b_field = { bits = '1[];(2[])10' }
+
+[struct.header]
+c_field = { bits = """2[]..;5""" }
+"#;
+
+        let diagnostics = check(toml_source);
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "commands.TEST.request.a_field: Error in bit specification at position 6: expected `]`",
+                "commands.TEST.request.b_field: Error in bit specification at position 9: expected a condition (`<` or `<=`)",
+                "struct.header.c_field: Error in bit specification at position 5: expected a word",
+            ]
+        );
+
+        // The spans point at the closing quote, the `1` of `10` and the `;`
+        let spanned: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| &toml_source[diagnostic.span.clone().unwrap()])
+            .collect();
+        assert_eq!(spanned, ["\"", "1", ";"]);
+        let a_field_start = toml_source.find("0[0..4").unwrap();
+        assert_eq!(
+            diagnostics[0].span,
+            Some(a_field_start + 6..a_field_start + 7)
+        );
+    }
+
+    #[test]
+    fn test_toml_syntax_errors_are_left_to_deserialization() {
+        assert!(check("[commands.TEST.request\na_field = { bits = \"0[\" }").is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+// Code generation that depends on the bus the device is connected to.
+
+use genco::prelude::*;
+
+use crate::definition::{Bus, Device};
+
+/// Generates the imports of the bus trait and of the delay trait used by timed flow control.
+pub fn generate_bus_imports(tokens: &mut Tokens<Rust>, device: &Device) {
+    match device.bus {
+        Bus::Spi => quote_in!(*tokens =>
+            #[cfg(not(feature = "async"))]
+            use embedded_hal::spi::SpiDevice;
+            #[cfg(feature = "async")]
+            use embedded_hal_async::spi::SpiDevice;
+        ),
+        Bus::I2c => quote_in!(*tokens =>
+            use embedded_hal::i2c::I2c;
+        ),
+    }
+}
+
+/// Generates the import of the delay trait for flow controls that need the caller to
+/// supply a delay.
+pub fn generate_delay_imports(tokens: &mut Tokens<Rust>, device: &Device) {
+    match device.bus {
+        Bus::Spi => quote_in!(*tokens =>
+            #[cfg(not(feature = "async"))]
+            use embedded_hal::delay::DelayNs;
+            #[cfg(feature = "async")]
+            use embedded_hal_async::delay::DelayNs;$['\r']
+        ),
+        Bus::I2c => quote_in!(*tokens =>
+            use embedded_hal::delay::DelayNs;$['\r']
+        ),
+    }
+}
+
+/// Generates the imports of the transmit traits used to send requests over the bus.
+pub fn generate_transmit_imports(tokens: &mut Tokens<Rust>, device: &Device) {
+    match device.bus {
+        Bus::Spi => quote_in!(*tokens =>
+            #[cfg(not(feature = "async"))]
+            use crate::transmit::Transmit;
+            #[cfg(feature = "async")]
+            use crate::transmit_async::AsyncTransmit;
+        ),
+        Bus::I2c => quote_in!(*tokens =>
+            use crate::i2c_transmit::I2cTransmit;
+            use crate::I2C_ADDRESS;
+        ),
+    }
+}
+
+/// Generates the implementations of the transmit traits for a request.
+pub fn generate_transmit_impls(
+    tokens: &mut Tokens<Rust>,
+    request_name: impl FormatInto<Rust> + Clone,
+    response_name: impl FormatInto<Rust> + Clone,
+    device: &Device,
+) {
+    let word_type = device.word_type();
+
+    match device.bus {
+        Bus::Spi => quote_in!(*tokens =>
+            #[cfg(not(feature = "async"))]
+            impl<SPI: SpiDevice<$(&word_type)>> Transmit<SPI, $(response_name.clone()), $(&word_type)> for $(request_name.clone()) {}
+            #[cfg(feature = "async")]
+            impl<SPI: SpiDevice<$(&word_type)>> AsyncTransmit<SPI, $response_name, $(&word_type)> for $request_name {}
+        ),
+        Bus::I2c => quote_in!(*tokens =>
+            impl<I2C: I2c> I2cTransmit<I2C, $response_name> for $request_name {}
+        ),
+    }
+}
+
+/// The parts of a generated function signature and body that depend on the bus.
+pub struct BusParameters {
+    /// The generic bus parameter, e.g. `SPI: SpiDevice<u8>`
+    pub generic: String,
+    /// The bus argument, e.g. `spi: &mut SPI`
+    pub argument: &'static str,
+    /// The name of the bus argument
+    pub name: &'static str,
+    /// The arguments passed on to the transmit functions
+    pub transmit_args: &'static str,
+}
+
+impl From<&Device> for BusParameters {
+    fn from(device: &Device) -> Self {
+        match device.bus {
+            Bus::Spi => BusParameters {
+                generic: format!("SPI: SpiDevice<{}>", device.word_type()),
+                argument: "spi: &mut SPI",
+                name: "spi",
+                transmit_args: "spi",
+            },
+            Bus::I2c => BusParameters {
+                generic: "I2C: I2c".to_string(),
+                argument: "i2c: &mut I2C",
+                name: "i2c",
+                transmit_args: "i2c, I2C_ADDRESS",
+            },
+        }
+    }
+}
+
+/// How the generated functions wait on the bus.
+#[derive(Clone, Copy)]
+pub enum SendMode {
+    Blocking,
+    Async,
+}
+
+impl SendMode {
+    /// SPI devices get both blocking and async functions, selected with the
+    /// `async` feature of the generated crate. I2C devices are blocking only, so their
+    /// functions are not feature gated.
+    pub fn for_bus(bus: Bus) -> Vec<SendMode> {
+        match bus {
+            Bus::Spi => vec![SendMode::Blocking, SendMode::Async],
+            Bus::I2c => vec![SendMode::Blocking],
+        }
+    }
+
+    pub fn cfg_attribute(&self, bus: Bus) -> Tokens<Rust> {
+        match (bus, self) {
+            (Bus::I2c, _) => quote!(),
+            (Bus::Spi, SendMode::Blocking) => quote!(#[cfg(not(feature = "async"))]),
+            (Bus::Spi, SendMode::Async) => quote!(#[cfg(feature = "async")]),
+        }
+    }
+
+    pub fn is_async(&self) -> bool {
+        matches!(self, SendMode::Async)
+    }
+
+    /// The bound on the ready pin of a ready pin send without a timeout. Async sends wait
+    /// on the pin so that other tasks can run until the device is ready.
+    pub fn ready_pin_bound(&self) -> &'static str {
+        match self {
+            SendMode::Blocking => "InputPin",
+            SendMode::Async => "InputPin + Wait",
+        }
+    }
+
+    pub fn await_suffix(&self) -> &'static str {
+        match self {
+            SendMode::Blocking => "",
+            SendMode::Async => ".await",
+        }
+    }
+}
@@ -4,6 +4,7 @@ use serde::Serialize;
 #[derive(Serialize)]
 struct CargoToml {
     package: Package,
+    features: Features,
     dependencies: Dependencies,
     #[serde(rename = "dev-dependencies")]
     dev_dependencies: DevDependencies,
@@ -16,10 +17,25 @@ struct Package {
     edition: String,
 }
 
+/// The `async` feature generates `async` send functions instead of blocking ones.
+/// The `defmt` and `serde` features derive `defmt::Format` and `serde::{Serialize,
+/// Deserialize}` for the generated types.
+#[derive(Serialize)]
+struct Features {
+    #[serde(rename = "async")]
+    async_send: Vec<String>,
+    defmt: Vec<String>,
+    serde: Vec<String>,
+}
+
 #[derive(Serialize)]
 struct Dependencies {
     #[serde(rename = "embedded-hal")]
     embedded_hal: Dependency,
+    #[serde(rename = "embedded-hal-async")]
+    embedded_hal_async: Dependency,
+    defmt: Dependency,
+    serde: Dependency,
 }
 
 #[derive(Serialize)]
@@ -33,7 +49,12 @@ struct DevDependencies {
 struct Dependency {
     version: semver::Version,
 
+    #[serde(rename = "default-features")]
+    default_features: Option<bool>,
+
     features: Option<Vec<String>>,
+
+    optional: Option<bool>,
 }
 
 pub fn generate(name: &str, version: &semver::Version) -> String {
@@ -45,15 +66,51 @@ pub fn generate(name: &str, version: &semver::Version) -> String {
 
     let embedded_hal_dependency = Dependency {
         version: semver::Version::parse("1.0.0").unwrap(),
+        default_features: None,
+        features: None,
+        optional: None,
+    };
+
+    let embedded_hal_async_dependency = Dependency {
+        version: semver::Version::parse("1.0.0").unwrap(),
+        default_features: None,
+        features: None,
+        optional: Some(true),
+    };
+
+    let defmt_dependency = Dependency {
+        version: semver::Version::parse("1.0.1").unwrap(),
+        default_features: None,
         features: None,
+        optional: Some(true),
+    };
+
+    // Without the default features serde does not need std
+    let serde_dependency = Dependency {
+        version: semver::Version::parse("1.0.197").unwrap(),
+        default_features: Some(false),
+        features: Some(vec!["derive".to_string()]),
+        optional: Some(true),
     };
 
     let embedded_hal_mock_dependency = Dependency {
         version: semver::Version::parse("0.11.1").unwrap(),
-        features: Some(vec!["eh1".to_string()]),
+        default_features: None,
+        features: Some(vec!["eh1".to_string(), "embedded-hal-async".to_string()]),
+        optional: None,
     };
+
+    let features = Features {
+        async_send: vec!["dep:embedded-hal-async".to_string()],
+        defmt: vec!["dep:defmt".to_string()],
+        serde: vec!["dep:serde".to_string()],
+    };
+
     let dependencies = Dependencies {
         embedded_hal: embedded_hal_dependency,
+        embedded_hal_async: embedded_hal_async_dependency,
+        defmt: defmt_dependency,
+        serde: serde_dependency,
     };
 
     let dev_dependencies = DevDependencies {
@@ -62,9 +119,39 @@ pub fn generate(name: &str, version: &semver::Version) -> String {
 
     let cargo_toml = CargoToml {
         package,
+        features,
         dependencies,
         dev_dependencies,
     };
 
     toml::to_string(&cargo_toml).expect("Unable to generate Cargo.toml")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optional_features() {
+        let cargo_toml: toml::Table =
+            toml::from_str(&generate("Test", &semver::Version::new(0, 1, 0))).unwrap();
+
+        let features = cargo_toml["features"].as_table().unwrap();
+        assert_eq!(
+            features["defmt"].as_array().unwrap()[0].as_str(),
+            Some("dep:defmt")
+        );
+        assert_eq!(
+            features["serde"].as_array().unwrap()[0].as_str(),
+            Some("dep:serde")
+        );
+
+        let serde = cargo_toml["dependencies"]["serde"].as_table().unwrap();
+        assert_eq!(serde["default-features"].as_bool(), Some(false));
+        assert_eq!(serde["optional"].as_bool(), Some(true));
+        assert_eq!(
+            cargo_toml["dependencies"]["defmt"]["optional"].as_bool(),
+            Some(true)
+        );
+    }
+}
@@ -7,10 +7,20 @@ use convert_case::{Case, Casing};
 //use convert_case::{Case, Casing};
 use genco::prelude::*;
 use serde::Deserialize;
+use toml::Spanned;
 
+use crate::bus::{
+    generate_bus_imports, generate_delay_imports, generate_transmit_impls,
+    generate_transmit_imports, BusParameters, SendMode,
+};
 use crate::common_structure::CommonStructure;
+use crate::condition::Condition;
+use crate::definition::{Bus, Device};
+use crate::derives::optional_derives;
 use crate::doc_comment::DocComment;
-use crate::flow_control::FlowControl;
+use crate::field::Field;
+use crate::flags::FlagSets;
+use crate::flow_control::{ActiveLevel, FlowControl, PollLimits};
 use crate::members::Members;
 use crate::naming::{CommandName, RequestStructName, ResponseStructName};
 use crate::output::output_file;
@@ -25,18 +35,32 @@ pub struct Command {
 
     description: Option<String>,
 
-    #[serde(default)]
-    flow_control: FlowControl,
+    #[serde(default = "direct_flow_control")]
+    flow_control: Spanned<FlowControl>,
+
+    // Conditions on the polled header that mean the device has reported an error. If
+    // not given, those of the header struct are used.
+    errors: Option<Spanned<Vec<Condition>>>,
 
+    // Either can be left out, for commands without arguments or without a response
+    #[serde(default)]
     request: Members,
+    #[serde(default)]
     response: Members,
 }
 
+// Commands without a flow control have no span in the definition
+fn direct_flow_control() -> Spanned<FlowControl> {
+    Spanned::new(0..0, FlowControl::Direct)
+}
+
 impl Command {
     pub fn generate_command(
         &self,
         command_name: &CommandName,
         common_structures: &HashMap<String, CommonStructure>,
+        flags: &FlagSets,
+        device: &Device,
         out_path: &Path,
     ) -> anyhow::Result<()> {
         println!("Generating command file for {command_name}");
@@ -52,6 +76,7 @@ impl Command {
         // let command_name = CommandName::from(command_name);
         let request_struct_name = RequestStructName::from(command_name);
         let response_struct_name = ResponseStructName::from(command_name);
+        let response_type = self.response_type(&response_struct_name);
 
         // let request_struct_name = format!("{}Request", name.to_case(Case::UpperCamel));
         // let response_struct_name = format!("{}Response", name.to_case(Case::UpperCamel));
@@ -75,18 +100,21 @@ impl Command {
 
             $(generated_doc_comment)$['\n']
 
-            use embedded_hal::spi::SpiDevice;
+            $(ref toks => generate_bus_imports(toks, device))
+            $(ref toks => self.generate_flow_control_imports(toks, device))
 
             use crate::command::Command;
             use crate::deserialize::Deserialize;
             use crate::error::DeviceError;
+            $(if self.members().any(|(_, members)| members.has_ranged()) => use crate::ranged::Ranged;)
 
-            use crate::request::{RequestArray, RequestBit, RequestField, RequestWord, RequestStruct};
-            use crate::response::{ResponseArray, ResponseBit, ResponseField, ResponseWord};
+            use crate::request::{RequestArray, RequestBit, RequestField, RequestSpanningField, RequestWord, RequestStruct};
+            use crate::response::{ResponseArray, ResponseBit, ResponseField, ResponseSpanningField, ResponseWord};
+            $(if self.members().any(|(_, members)| members.has_scaled()) => use crate::scaled;)
             use crate::serialize::Serialize;
             use crate::types::*;
 
-            use crate::transmit::Transmit;
+            $(ref toks => generate_transmit_imports(toks, device))
 
             $(for name in common_structures.keys() => use crate::$(name.to_lowercase())::$(name.to_case(Case::UpperCamel));)
 
@@ -94,29 +122,27 @@ impl Command {
 
 
             $['\n']
+            $(ref toks => self.generate_chunk_type(toks, device))
             #[derive(Debug, PartialEq)]$['\r']
+            $(optional_derives())
             pub struct $(request_struct_name.clone()) {$['\r']
-                $(ref toks => self.request.generate_members(toks))$['\r']
+                $(ref toks => self.request.generate_members(toks, device))$['\r']
             }
             $['\n']
             $(ref toks => self.request.generate_defaults(toks, request_struct_name.clone() ))$['\r']
             $['\n']
-            $(ref toks => self.request.generate_serializations(toks, &request_struct_name,  common_structures))$['\r']
+            $(ref toks => self.request.generate_serializations(toks, &request_struct_name, common_structures, device))$['\r']
+            $['\n']
+            $(ref toks => self.request.generate_scaled_accessors(toks, &request_struct_name, device))
 
 
             $['\n']
-            #[derive(Debug, PartialEq)]$['\r']
-            pub struct $(response_struct_name.clone()) {$['\r']
-                $(ref toks => self.response.generate_members(toks))$['\r']
-            }
-            $['\n']
-            $(ref toks => self.response.generate_deserializations(toks, response_struct_name.clone()))$['\r']
-            $['\n']
+            $(ref toks => self.generate_response(toks, &response_struct_name, device))
 
-            $(ref toks => self.generate_send(toks, &request_struct_name, &response_struct_name, common_structures))$['\r']
+            $(ref toks => self.generate_send(toks, &request_struct_name, &response_type, common_structures, flags, device))$['\r']
 
             //impl<SPI: SpiDevice> Transmit<SPI, $(command_name.to_case(Case::UpperCamel))Response> for $(command_name.to_case(Case::UpperCamel))Request {}
-            impl<SPI: SpiDevice> Transmit<SPI, $(response_struct_name)> for $(request_struct_name.clone()) {}
+            $(ref toks => generate_transmit_impls(toks, &request_struct_name, &response_type, device))
 
             impl Command for $request_struct_name  {
                 fn opcode(&self) -> u8 {
@@ -135,29 +161,130 @@ impl Command {
         Ok(())
     }
 
+    // The response struct. Commands without a response have none.
+    fn generate_response(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        response_name: &ResponseStructName,
+        device: &Device,
+    ) {
+        if self.response.is_empty() {
+            return;
+        }
+
+        quote_in!(*tokens =>
+            #[derive(Debug, PartialEq)]$['\r']
+            $(optional_derives())
+            pub struct $response_name {$['\r']
+                $(ref toks => self.response.generate_members(toks, device))$['\r']
+            }
+            $['\n']
+            $(ref toks => self.response.generate_deserializations(toks, response_name, device))$['\r']
+            $['\n']
+            $(ref toks => self.response.generate_scaled_accessors(toks, response_name, device))
+        );
+    }
+
+    // The type returned by the send functions, which is `()` for commands without a response
+    fn response_type(&self, response_name: &ResponseStructName) -> String {
+        if self.response.is_empty() {
+            "()".to_string()
+        } else {
+            response_name.to_string()
+        }
+    }
+
     //$(ref toks => self.generate_send(toks, &response_struct_name))$['\r']
     pub fn generate_send(
         &self,
         tokens: &mut Tokens<Rust>,
         request_name: &RequestStructName,
-        response_name: &ResponseStructName,
+        response_type: &str,
         common_structures: &HashMap<String, CommonStructure>,
+        flags: &FlagSets,
+        device: &Device,
     ) {
         // let cased_request_name = request_name.to_case(Case::UpperCamel);
         // let cased_response_name = response_name.to_case(Case::UpperCamel);
 
-        match &self.flow_control {
-            FlowControl::Direct => {
-                self.generate_direct_send(tokens, request_name, response_name, common_structures)
-            }
-            FlowControl::Polled { on, condition } => self.generate_polled_send(
+        match self.flow_control.get_ref() {
+            FlowControl::Direct => self.generate_direct_send(
+                tokens,
+                request_name,
+                response_type,
+                common_structures,
+                device,
+            ),
+            FlowControl::Polled {
+                on,
+                condition,
+                limits,
+            } => self.generate_polled_send(
                 tokens,
                 request_name,
-                response_name,
+                response_type,
                 common_structures,
+                flags,
+                device,
                 on,
                 condition,
+                limits,
             ),
+            FlowControl::Timed { delay_us } => self.generate_timed_send(
+                tokens,
+                request_name,
+                response_type,
+                common_structures,
+                device,
+                *delay_us,
+            ),
+            FlowControl::ReadyPin { active, timeout_us } => self.generate_ready_pin_send(
+                tokens,
+                request_name,
+                response_type,
+                common_structures,
+                device,
+                *active,
+                *timeout_us,
+            ),
+            FlowControl::MisoReady {
+                active,
+                max_retries,
+            } => self.generate_miso_ready_send(
+                tokens,
+                request_name,
+                response_type,
+                common_structures,
+                device,
+                *active,
+                *max_retries,
+            ),
+            FlowControl::Chunked {
+                on,
+                condition,
+                limits,
+                ..
+            } => {
+                self.generate_polled_send(
+                    tokens,
+                    request_name,
+                    response_type,
+                    common_structures,
+                    flags,
+                    device,
+                    on,
+                    condition,
+                    limits,
+                );
+                self.generate_chunked_send(
+                    tokens,
+                    request_name,
+                    response_type,
+                    common_structures,
+                    device,
+                    limits,
+                );
+            }
         };
 
         // quote_in!(*tokens =>
@@ -180,57 +307,412 @@ impl Command {
         &self,
         tokens: &mut Tokens<Rust>,
         request_name: &RequestStructName,
-        response_name: &ResponseStructName,
+        response_type: &str,
         common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
     ) {
-        quote_in!(*tokens =>
-            impl $(request_name) {
-                // This needs to be generated as we need to corrected specifiy the sizes
-                // of the request and response.
-                pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<$(response_name), DeviceError> {
-                    const REQUEST_BUF_LEN: usize = $(self.request.buffer_size(common_structures));
-                    const RESPONSE_BUF_LEN: usize = $(self.response.buffer_size(common_structures));
-
-                    let response = self.transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN>(spi)?;
-                    Ok(response)
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $(request_name) {
+                    // This needs to be generated as we need to corrected specifiy the sizes
+                    // of the request and response.
+                    pub $(if mode.is_async() => async) fn send<$(&bus.generic)>(&self, $(bus.argument)) -> Result<$response_type, DeviceError> {
+                        const REQUEST_BUF_LEN: usize = $(self.request.buffer_size(common_structures));
+                        const RESPONSE_BUF_LEN: usize = $(self.response.buffer_size(common_structures));
+
+                        self.transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN>($(bus.transmit_args))$(mode.await_suffix())
+                    }
                 }
-            }
-        )
+                $['\n']
+            )
+        }
+    }
+
+    // Generate the send function for a TIMED flow control. The caller supplies the delay.
+    fn generate_timed_send(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        request_name: &RequestStructName,
+        response_type: &str,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+        delay_us: u32,
+    ) {
+        let request_buf_size = self.request.buffer_size(common_structures);
+        let response_buf_size = self.response.buffer_size(common_structures);
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $request_name {
+                    pub $(if mode.is_async() => async) fn send<$(&bus.generic), DELAY: DelayNs>(&self, $(bus.argument), delay: &mut DELAY) -> Result<$response_type, DeviceError> {
+                        const REQUEST_BUF_LEN: usize = $request_buf_size;
+                        const RESPONSE_BUF_LEN: usize = $response_buf_size;
+                        const DELAY_US: u32 = $delay_us;
+
+                        self.timed_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, DELAY>($(bus.transmit_args), delay, DELAY_US)$(mode.await_suffix())
+                    }
+                }
+                $['\n']
+            )
+        }
+    }
+
+    // Generate the send function for a READY_PIN flow control. With a timeout the caller
+    // also supplies the delay used to time it. Without one, async sends wait on the pin.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_ready_pin_send(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        request_name: &RequestStructName,
+        response_type: &str,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+        active: ActiveLevel,
+        timeout_us: Option<u32>,
+    ) {
+        let request_buf_size = self.request.buffer_size(common_structures);
+        let response_buf_size = self.response.buffer_size(common_structures);
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $request_name {
+                    $(match timeout_us {
+                        None => {
+                            pub $(if mode.is_async() => async) fn send<$(&bus.generic), READY: $(mode.ready_pin_bound())>(&self, $(bus.argument), ready: &mut READY) -> Result<$response_type, DeviceError> {
+                                const REQUEST_BUF_LEN: usize = $request_buf_size;
+                                const RESPONSE_BUF_LEN: usize = $response_buf_size;
+
+                                let mut ready = ReadyPin::new(ready, $(active.variant()));
+                                self.ready_pin_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, _>($(bus.transmit_args), &mut ready)$(mode.await_suffix())
+                            }
+                        }
+                        Some(timeout_us) => {
+                            pub $(if mode.is_async() => async) fn send<$(&bus.generic), READY: InputPin, DELAY: DelayNs>(&self, $(bus.argument), ready: &mut READY, delay: &mut DELAY) -> Result<$response_type, DeviceError> {
+                                const REQUEST_BUF_LEN: usize = $request_buf_size;
+                                const RESPONSE_BUF_LEN: usize = $response_buf_size;
+                                const TIMEOUT_US: u32 = $timeout_us;
+
+                                let mut ready = ReadyPinWithTimeout::new(ready, $(active.variant()), delay, TIMEOUT_US);
+                                self.ready_pin_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, _>($(bus.transmit_args), &mut ready)$(mode.await_suffix())
+                            }
+                        }
+                    })
+                }
+                $['\n']
+            )
+        }
     }
 
-    // Generate the send function for a POLLED flow control.
+    // Generate the send function for a MISO_READY flow control.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_miso_ready_send(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        request_name: &RequestStructName,
+        response_type: &str,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+        active: ActiveLevel,
+        max_retries: u32,
+    ) {
+        let request_buf_size = self.request.buffer_size(common_structures);
+        let response_buf_size = self.response.buffer_size(common_structures);
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $request_name {
+                    pub $(if mode.is_async() => async) fn send<$(&bus.generic)>(&self, $(bus.argument)) -> Result<$response_type, DeviceError> {
+                        const REQUEST_BUF_LEN: usize = $request_buf_size;
+                        const RESPONSE_BUF_LEN: usize = $response_buf_size;
+                        const MAX_RETRIES: u32 = $max_retries;
+
+                        self.miso_ready_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN>($(bus.transmit_args), $(active.variant()), MAX_RETRIES)$(mode.await_suffix())
+                    }
+                }
+                $['\n']
+            )
+        }
+    }
+
+    // Generate the send function for a POLLED flow control. Any poll limits become
+    // constants of the send function and, with a timeout, the caller supplies the delay.
+    #[allow(clippy::too_many_arguments)]
     fn generate_polled_send(
         &self,
         tokens: &mut Tokens<Rust>,
         request_name: &RequestStructName,
-        response_name: &ResponseStructName,
+        response_type: &str,
         common_structures: &HashMap<String, CommonStructure>,
+        flags: &FlagSets,
+        device: &Device,
         on: &str,
-        condition: &str,
+        condition: &Condition,
+        limits: &PollLimits,
     ) {
-        let header_structure = common_structures.get(on).unwrap(); //TODO Error handling
+        let header_structure = common_structures
+            .get(on)
+            .expect("Polled header struct should have been validated");
         let header_structure_buf_size = header_structure.buffer_size();
         let cased_header_structure_name = on.to_case(Case::UpperCamel); // TOO this should be replaced with naming module functions
         let request_buf_size = self.request.buffer_size(common_structures);
         let response_buf_size = self.response.buffer_size(common_structures);
+        let bus = BusParameters::from(device);
+        let error_type = self.error_type(common_structures);
+        let error_check = self
+            .error_condition(common_structures)
+            .map(|error_condition| {
+                let header_field = self
+                    .header_field(on)
+                    .expect("The header field for the errors should have been validated");
+                (
+                    header_field,
+                    error_condition.to_expression(header_structure.members(), flags),
+                )
+            });
+        let map_error = if error_check.is_some() {
+            ".map_err(DeviceError::with_header)"
+        } else {
+            ""
+        };
 
-        quote_in!(*tokens =>
-            impl $request_name {
-            pub fn send<SPI: SpiDevice>(&self, spi: &mut SPI) -> Result<$response_name, DeviceError> {
-                let f = | h: $(cased_header_structure_name.clone())  | h.$condition;
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $request_name {
+                pub $(if mode.is_async() => async) fn send<$(&bus.generic)$(if limits.needs_delay() => , DELAY: DelayNs)>(&self, $(bus.argument)$(if limits.needs_delay() => , delay: &mut DELAY)) -> Result<$response_type, $(&error_type)> {
+                    let f = | h: $(cased_header_structure_name.clone())  | $(condition.to_expression(header_structure.members(), flags));
+
+                    const REQUEST_BUF_LEN: usize = $request_buf_size;
+                    const RESPONSE_BUF_LEN: usize = $response_buf_size;
+                    const STATUS_HEADER_LEN: usize = $header_structure_buf_size;
+                    $(match (limits.max_polls, limits.timeout_us) {
+                        (Some(max_polls), _) => {
+                            const MAX_POLLS: u32 = $max_polls;
+
+                            let response = self.limited_polled_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, $(cased_header_structure_name.clone()), STATUS_HEADER_LEN, _>($(bus.transmit_args), f, &mut MaxPolls::new(MAX_POLLS))$(mode.await_suffix())$map_error?;
+                        }
+                        (None, Some(timeout_us)) => {
+                            const POLL_INTERVAL_US: u32 = $(limits.poll_interval_us());
+                            const TIMEOUT_US: u32 = $timeout_us;
+
+                            let response = self.limited_polled_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, $(cased_header_structure_name.clone()), STATUS_HEADER_LEN, _>($(bus.transmit_args), f, &mut PollTimeout::new(delay, POLL_INTERVAL_US, TIMEOUT_US))$(mode.await_suffix())$map_error?;
+                        }
+                        (None, None) => {
+                            $['\n']
+                            let response = self.polled_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, $(cased_header_structure_name.clone()), STATUS_HEADER_LEN>($(bus.transmit_args), f)$(mode.await_suffix())$map_error?;
+                        }
+                    })
+                    $(if let Some((header_field, error_expression)) = &error_check {
+                        $['\n']
+                        let h = response.$(*header_field);
+                        if $error_expression {
+                            return Err(DeviceError::Device { header: h });
+                        }
+                    })
+
+                    Ok(response)
+                }
+            }
+            $['\n']
+            )
+        }
+    }
+
+    // Generate the function that sends data in chunks for a CHUNKED flow control. Each
+    // chunk is sent with the polled send function.
+    fn generate_chunked_send(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        request_name: &RequestStructName,
+        response_type: &str,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+        limits: &PollLimits,
+    ) {
+        let (Some((field_name, _)), Some(chunk_type)) = (self.chunk_field(), self.chunk_type())
+        else {
+            unreachable!("The chunk field of {request_name} should have been validated");
+        };
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $request_name {
+                    $(DocComment::from_string("Sends all of `data` by repeating the command with one chunk of the data at a time.\nAs the response of the last command is returned, the command is sent once with an\nempty chunk if `data` is empty.").as_string())
+                    pub $(if mode.is_async() => async) fn send_chunked<$(&bus.generic)$(if limits.needs_delay() => , DELAY: DelayNs)>(&mut self, $(bus.argument)$(if limits.needs_delay() => , delay: &mut DELAY), data: impl IntoIterator<Item = $(device.word_type())>) -> Result<$response_type, $(self.error_type(common_structures))> {
+                        let mut data = data.into_iter().peekable();
+                        loop {
+                            self.$field_name = $(&chunk_type)::fill(&mut data);
+                            let response = self.send($(bus.name)$(if limits.needs_delay() => , delay))$(mode.await_suffix())?;
+                            if data.peek().is_none() {
+                                return Ok(response);
+                            }
+                        }
+                    }
+                }
+                $['\n']
+            )
+        }
+    }
 
-                const REQUEST_BUF_LEN: usize = $request_buf_size;
-                const RESPONSE_BUF_LEN: usize = $response_buf_size;
-                const STATUS_HEADER_LEN: usize = $header_structure_buf_size;
+    // The imports of the runtime types and traits that the flow control uses
+    fn generate_flow_control_imports(&self, tokens: &mut Tokens<Rust>, device: &Device) {
+        let flow_control = self.flow_control.get_ref();
 
-                let response = self.polled_transmit::<REQUEST_BUF_LEN, RESPONSE_BUF_LEN, $cased_header_structure_name, STATUS_HEADER_LEN>(spi, f)?;
+        if flow_control.needs_delay() {
+            generate_delay_imports(tokens, device);
+        }
 
-                Ok(response)
+        match flow_control {
+            FlowControl::Polled { limits, .. } | FlowControl::Chunked { limits, .. } => {
+                if let FlowControl::Chunked { .. } = flow_control {
+                    quote_in!(*tokens => use crate::chunk::Chunk;$['\r']);
+                }
+                if limits.max_polls.is_some() {
+                    quote_in!(*tokens => use crate::poll::MaxPolls;$['\r']);
+                }
+                if limits.timeout_us.is_some() {
+                    quote_in!(*tokens => use crate::poll::PollTimeout;$['\r']);
+                }
+            }
+            FlowControl::ReadyPin { timeout_us, .. } => {
+                let ready_pin = match timeout_us {
+                    None => "ReadyPin",
+                    Some(_) => "ReadyPinWithTimeout",
+                };
+                quote_in!(*tokens =>
+                    use crate::ready_pin::{ActiveLevel, $ready_pin};
+                    use embedded_hal::digital::InputPin;$['\r']
+                );
+                // Async sends without a timeout wait on the pin instead of reading it
+                if timeout_us.is_none() && device.bus == Bus::Spi {
+                    quote_in!(*tokens =>
+                        #[cfg(feature = "async")]
+                        use embedded_hal_async::digital::Wait;$['\r']
+                    );
+                }
             }
-        })
+            FlowControl::MisoReady { .. } => {
+                quote_in!(*tokens => use crate::ready_pin::ActiveLevel;$['\r']);
+            }
+            FlowControl::Direct | FlowControl::Timed { .. } => (),
+        }
+    }
+
+    // The chunk type for the command, if the command has a chunked flow control
+    fn generate_chunk_type(&self, tokens: &mut Tokens<Rust>, device: &Device) {
+        if let (FlowControl::Chunked { chunk_size, .. }, Some(chunk_type)) =
+            (self.flow_control.get_ref(), self.chunk_type())
+        {
+            quote_in!(*tokens =>
+                $(DocComment::from_string("The chunks of data that are sent with each command").as_string())
+                pub type $chunk_type = Chunk<$(device.word_type()), $(*chunk_size)>;
+                $['\n']
+            );
+        }
+    }
+
+    /// The request field that holds the chunks of data for a chunked flow control.
+    pub fn chunk_field(&self) -> Option<(&str, &Field)> {
+        self.request
+            .iter()
+            .find(|(_, field)| field.provider().is_some())
+            .map(|(name, field)| (name.as_str(), field))
+    }
+
+    // The name of the type of the chunks, i.e. the provider type of the chunk field
+    fn chunk_type(&self) -> Option<String> {
+        self.chunk_field()
+            .and_then(|(_, field)| field.provider())
+            .map(|provider| provider.to_case(Case::UpperCamel))
+    }
+
+    /// The members of the request and the response, together with their TOML key.
+    pub fn members(&self) -> impl Iterator<Item = (&'static str, &Members)> {
+        [("request", &self.request), ("response", &self.response)].into_iter()
+    }
+
+    pub fn has_response(&self) -> bool {
+        !self.response.is_empty()
+    }
+
+    pub fn flow_control(&self) -> &Spanned<FlowControl> {
+        &self.flow_control
+    }
+
+    /// The error conditions given for the command itself
+    pub fn errors(&self) -> Option<&Spanned<Vec<Condition>>> {
+        self.errors.as_ref()
+    }
+
+    /// The error conditions of the command. If the command does not give its own, these
+    /// are the errors of the struct that it polls on.
+    pub fn error_conditions<'a>(
+        &'a self,
+        common_structures: &'a HashMap<String, CommonStructure>,
+    ) -> Option<&'a Spanned<Vec<Condition>>> {
+        match self.flow_control.get_ref() {
+            _ if self.errors.is_some() => self.errors.as_ref(),
+            FlowControl::Polled { on, .. } | FlowControl::Chunked { on, .. } => {
+                common_structures.get(on)?.errors()
+            }
+            _ => None,
+        }
+    }
+
+    // Any of the error conditions
+    fn error_condition(
+        &self,
+        common_structures: &HashMap<String, CommonStructure>,
+    ) -> Option<Condition> {
+        self.error_conditions(common_structures)?
+            .get_ref()
+            .iter()
+            .cloned()
+            .reduce(|left, right| Condition::Or(Box::new(left), Box::new(right)))
+    }
+
+    // The error type of the send functions. With errors this carries the polled header.
+    fn error_type(&self, common_structures: &HashMap<String, CommonStructure>) -> String {
+        match (
+            self.error_condition(common_structures),
+            self.flow_control.get_ref(),
+        ) {
+            (Some(_), FlowControl::Polled { on, .. } | FlowControl::Chunked { on, .. }) => {
+                format!("DeviceError<{}>", on.to_case(Case::UpperCamel))
+            }
+            _ => "DeviceError".to_string(),
+        }
+    }
+
+    /// The first response field that holds the header struct `on`
+    pub fn header_field(&self, on: &str) -> Option<&str> {
+        let mut fields = self.response.to_vec();
+        fields.sort_by_key(|(_, field)| *field);
+        fields
+            .into_iter()
+            .find(|(_, field)| {
+                matches!(field, Field::Structure { common_structure_name, .. } if common_structure_name == on)
+            })
+            .map(|(name, _)| name.as_str())
     }
 
     pub fn providers(&self) -> impl Iterator<Item = String> {
+        // The chunk type of a chunked flow control is generated in the command file
+        if matches!(self.flow_control.get_ref(), FlowControl::Chunked { .. }) {
+            return Vec::new().into_iter();
+        }
+
         let providers: Vec<String> = self
             .request
             .fields()
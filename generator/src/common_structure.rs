@@ -1,43 +1,108 @@
-use serde::Deserialize;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
+use std::fmt;
 
 use genco::prelude::*;
+use toml::Spanned;
 
+use crate::condition::Condition;
+use crate::definition::Device;
+use crate::derives::optional_derives;
 use crate::members::Members;
 use crate::naming::CommonStructureName;
 
-#[derive(Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-pub struct CommonStructure(Members);
+#[derive(Debug)]
+pub struct CommonStructure {
+    members: Members,
+
+    // Conditions on the struct that mean the device has reported an error, for the
+    // commands that poll on it
+    errors: Option<Spanned<Vec<Condition>>>,
+}
+
+// A struct is a table of its fields, in which `errors` is taken as its error conditions
+impl<'de> Deserialize<'de> for CommonStructure {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CommonStructureVisitor;
+
+        impl<'de> Visitor<'de> for CommonStructureVisitor {
+            type Value = CommonStructure;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a table of fields")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields = HashMap::new();
+                let mut errors = None;
+                while let Some(name) = map.next_key::<Spanned<String>>()? {
+                    if name.get_ref() == "errors" {
+                        errors = Some(map.next_value()?);
+                    } else {
+                        fields.insert(name, map.next_value()?);
+                    }
+                }
+
+                Ok(CommonStructure {
+                    members: Members::from(fields),
+                    errors,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(CommonStructureVisitor)
+    }
+}
 
 impl CommonStructure {
+    #[cfg(test)]
+    pub fn new(members: Members) -> CommonStructure {
+        CommonStructure {
+            members,
+            errors: None,
+        }
+    }
+
     pub fn generate(
         &self,
         tokens: &mut Tokens<Rust>,
         struct_name: &CommonStructureName,
         common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
     ) {
         //let struct_name = name.to_case(Case::UpperCamel);
         quote_in!(*tokens =>
             #[derive(Debug, PartialEq, Copy, Clone, Default)]
+            $(optional_derives())
             pub struct $(struct_name.clone()) {
-                $(for (name, field) in self.0.iter() => $(ref toks {field.generate_struct_member(toks, name)}) )
+                $(for (name, field) in self.members.iter() => $(ref toks {field.generate_struct_member(toks, name, device)}) )
             }
 
-            $(ref toks => self.0.generate_serializations(toks, struct_name.clone(), common_structures))$['\r']
+            $(ref toks => self.members.generate_serializations(toks, struct_name.clone(), common_structures, device))$['\r']
+
+            $(ref toks => self.members.generate_deserializations(toks, struct_name.clone(), device))$['\r']
 
-            $(ref toks => self.0.generate_deserializations(toks, struct_name))$['\r']
+            $(ref toks => self.members.generate_scaled_accessors(toks, struct_name, device))
         );
     }
 
-    /// Determine how many bytes this structure would need.
+    pub fn members(&self) -> &Members {
+        &self.members
+    }
+
+    pub fn errors(&self) -> Option<&Spanned<Vec<Condition>>> {
+        self.errors.as_ref()
+    }
+
+    /// Determine how many words this structure would need.
     /// Note: Common Structures cannot contain variable fields.
     // In the future this should
     // return a Vec of tuples - `Vec<(usize, Option<String>)>` - each containing the
     // fixed size of the repeating elements and an optional String with the symbolic name
     // of the mutiplieing field
     pub fn size(&self) -> usize {
-        let size = self.0.size();
+        let size = self.members.size();
         assert!(
             size.1.is_empty(),
             "Fatal Error: Common structures should not contain varaible fields."
@@ -49,7 +114,7 @@ impl CommonStructure {
     /// Common structures cannot contain other common structures
     pub fn buffer_size(&self) -> usize {
         let empty_common_structures = HashMap::<String, CommonStructure>::new();
-        self.0.buffer_size(&empty_common_structures)
+        self.members.buffer_size(&empty_common_structures)
     }
 }
 
@@ -65,16 +130,31 @@ mod tests {
             bit_spec: parse("0[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field_b = Field::BitField {
             bit_spec: parse("1[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field_c = Field::BitField {
             bit_spec: parse("2[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let mut members = Members::new();
@@ -83,7 +163,7 @@ mod tests {
         members.add("b", field_b);
         members.add("c", field_c);
 
-        let common_structure = CommonStructure(members);
+        let common_structure = CommonStructure::new(members);
 
         let buf_size = common_structure.buffer_size();
 
@@ -96,12 +176,22 @@ mod tests {
             bit_spec: parse("1[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let field_c = Field::BitField {
             bit_spec: parse("3[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let mut members = Members::new();
@@ -109,7 +199,7 @@ mod tests {
         members.add("a", field_a);
         members.add("c", field_c);
 
-        let common_structure = CommonStructure(members);
+        let common_structure = CommonStructure::new(members);
 
         let buf_size = common_structure.buffer_size();
 
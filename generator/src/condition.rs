@@ -0,0 +1,627 @@
+//! The condition of a polled flow control. Conditions are boolean expressions over the
+//! fields of the header struct that is polled, for instance:
+//!
+//! ```text
+//! cts
+//! cts && !err_cmd
+//! pup_state == app_running || (stcint && count != 0)
+//! ```
+//!
+//! A field on its own has to be a single bit. A flag of a header field that holds a set of
+//! flags can also be used on its own, by the name of the flag. Other fields are compared with `==` or `!=`
+//! against either an integer or, for enumerated fields, the name of a variant. `!`
+//! binds tighter than `&&`, which binds tighter than `||`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use bit_lang::{BitRange, Repeat};
+use convert_case::{Case, Casing};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{
+        alpha1, alphanumeric1, char, hex_digit1, multispace0, u64 as u64_parser,
+    },
+    combinator::{all_consuming, map, map_res, opt, recognize, value},
+    multi::{fold_many0, many0},
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::definition::Device;
+use crate::field::{Field, TargetType};
+use crate::flags::FlagSets;
+use crate::members::Members;
+use crate::Enumeration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Field(String),
+    Compare {
+        field: String,
+        equal: bool,
+        value: Value,
+    },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+/// The value a field is compared with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(u64),
+    Variant(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(integer) => write!(f, "{integer}"),
+            Value::Variant(variant) => write!(f, "{variant}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+
+        parse(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Parse a condition, e.g. `cts && !err_cmd`
+pub fn parse(condition: &str) -> Result<Condition, String> {
+    all_consuming(delimited(multispace0, disjunction, multispace0))(condition)
+        .map(|(_, condition)| condition)
+        .map_err(|_| format!("Error in condition `{condition}`"))
+}
+
+// Parse functions follow ...
+
+fn padded<'a, O>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    delimited(multispace0, parser, multispace0)
+}
+
+fn identifier(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0(alt((alphanumeric1, tag("_")))),
+    ))(input)
+}
+
+fn integer(input: &str) -> IResult<&str, u64> {
+    alt((
+        map_res(preceded(tag("0x"), hex_digit1), |hex| {
+            u64::from_str_radix(hex, 16)
+        }),
+        u64_parser,
+    ))(input)
+}
+
+fn comparison_value(input: &str) -> IResult<&str, Value> {
+    alt((
+        map(integer, Value::Integer),
+        map(identifier, |variant| Value::Variant(variant.to_string())),
+    ))(input)
+}
+
+// comparison = identifier [("==" | "!=") (integer | identifier)]
+fn comparison(input: &str) -> IResult<&str, Condition> {
+    let (remaining, (field, comparison)) = tuple((
+        identifier,
+        opt(pair(
+            padded(alt((value(true, tag("==")), value(false, tag("!="))))),
+            comparison_value,
+        )),
+    ))(input)?;
+
+    let field = field.to_string();
+    let condition = match comparison {
+        Some((equal, value)) => Condition::Compare {
+            field,
+            equal,
+            value,
+        },
+        None => Condition::Field(field),
+    };
+
+    Ok((remaining, condition))
+}
+
+// term = "!" term | "(" disjunction ")" | comparison
+fn term(input: &str) -> IResult<&str, Condition> {
+    padded(alt((
+        map(preceded(char('!'), term), |term| {
+            Condition::Not(Box::new(term))
+        }),
+        delimited(char('('), disjunction, char(')')),
+        comparison,
+    )))(input)
+}
+
+// conjunction = term {"&&" term}
+fn conjunction(input: &str) -> IResult<&str, Condition> {
+    let (remaining, first) = term(input)?;
+
+    fold_many0(
+        preceded(tag("&&"), term),
+        move || first.clone(),
+        |left, right| Condition::And(Box::new(left), Box::new(right)),
+    )(remaining)
+}
+
+// disjunction = conjunction {"||" conjunction}
+fn disjunction(input: &str) -> IResult<&str, Condition> {
+    let (remaining, first) = conjunction(input)?;
+
+    fold_many0(
+        preceded(tag("||"), conjunction),
+        move || first.clone(),
+        |left, right| Condition::Or(Box::new(left), Box::new(right)),
+    )(remaining)
+}
+
+/// How a header field can be used in a condition
+enum FieldKind<'a> {
+    Bool,
+    Integer,
+    Enumeration(&'a str),
+}
+
+fn field_kind<'a>(
+    name: &str,
+    on: &str,
+    header: &'a Members,
+    enumerations: &HashMap<String, Enumeration>,
+    flags: &FlagSets,
+) -> Result<FieldKind<'a>, String> {
+    let not_usable = |reason: &str| format!("condition field {name} {reason}");
+
+    match header.get(name) {
+        None => match flag_fields(name, header, flags).as_slice() {
+            [] => Err(format!(
+                "condition field {name} is not a field of struct {on}"
+            )),
+            [_] => Ok(FieldKind::Bool),
+            fields => Err(not_usable(&format!(
+                "is a flag of more than one field: {}",
+                fields
+                    .iter()
+                    .map(|(field_name, _)| *field_name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        },
+        Some(Field::Structure { .. }) => Err(not_usable("is a struct")),
+        Some(Field::BitField {
+            bit_spec,
+            target_type,
+            ..
+        }) => {
+            if bit_spec.repeat != Repeat::None {
+                return Err(not_usable("is repeated"));
+            }
+            match target_type {
+                Some(TargetType::TypeName(type_name)) if enumerations.contains_key(type_name) => {
+                    Ok(FieldKind::Enumeration(type_name))
+                }
+                Some(TargetType::TypeName(type_name)) if flags.contains_key(type_name.as_str()) => {
+                    Err(not_usable(&format!(
+                        "holds the flags {type_name}, so one of the flags should be used"
+                    )))
+                }
+                Some(TargetType::TypeName(type_name)) => Err(not_usable(&format!(
+                    "has the type {type_name}, which is not an enum"
+                ))),
+                Some(_) => Ok(FieldKind::Integer),
+                None if bit_spec.end.is_none()
+                    && matches!(bit_spec.start.bit_range, BitRange::Single(_)) =>
+                {
+                    Ok(FieldKind::Bool)
+                }
+                None => Ok(FieldKind::Integer),
+            }
+        }
+    }
+}
+
+// The header fields that hold a set of flags with the flag `name`, together with the
+// name of the set
+fn flag_fields<'a>(name: &str, header: &'a Members, flags: &FlagSets) -> Vec<(&'a str, &'a str)> {
+    header
+        .sorted()
+        .into_iter()
+        .filter_map(|(field_name, field)| match field {
+            Field::BitField {
+                target_type: Some(TargetType::TypeName(type_name)),
+                ..
+            } if flags
+                .get(type_name.as_str())
+                .is_some_and(|flag_set| flag_set.contains(name)) =>
+            {
+                Some((field_name.as_str(), type_name.as_str()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl Condition {
+    /// Type check the condition against the fields of the `on` header struct. Integers
+    /// compared with a field have to be values that the field can hold. Returns the
+    /// problems found.
+    pub fn check(
+        &self,
+        on: &str,
+        header: &Members,
+        enumerations: &HashMap<String, Enumeration>,
+        flags: &FlagSets,
+        device: &Device,
+    ) -> Vec<String> {
+        match self {
+            Condition::Field(name) => match field_kind(name, on, header, enumerations, flags) {
+                Ok(FieldKind::Bool) => vec![],
+                Ok(_) => vec![format!(
+                    "condition field {name} is not a single bit, so needs to be compared with == or !="
+                )],
+                Err(message) => vec![message],
+            },
+            Condition::Compare { field, value, .. } => {
+                match (field_kind(field, on, header, enumerations, flags), value) {
+                    (Err(message), _) => vec![message],
+                    (Ok(FieldKind::Bool), _) => vec![format!(
+                        "condition field {field} is a single bit and cannot be compared with {value}"
+                    )],
+                    (Ok(FieldKind::Integer), Value::Integer(integer)) => {
+                        // Ranged fields only hold the values of their range
+                        let limits = header.get(field).and_then(|header_field| {
+                            header_field
+                                .range()
+                                .map(|(min, max)| (min as i128, max as i128))
+                                .or_else(|| header_field.value_limits(device))
+                        });
+                        match limits {
+                            Some((lowest, highest))
+                                if !(lowest..=highest).contains(&(*integer as i128)) =>
+                            {
+                                vec![format!(
+                                    "condition field {field} holds {lowest} to {highest}, so cannot be compared with {value}"
+                                )]
+                            }
+                            _ => vec![],
+                        }
+                    }
+                    (Ok(FieldKind::Integer), Value::Variant(_)) => vec![format!(
+                        "condition field {field} is an integer and cannot be compared with {value}"
+                    )],
+                    (Ok(FieldKind::Enumeration(enum_name)), Value::Variant(variant))
+                        if enumerations[enum_name].0.contains_key(variant) =>
+                    {
+                        vec![]
+                    }
+                    (Ok(FieldKind::Enumeration(enum_name)), _) => vec![format!(
+                        "condition field {field} is a {enum_name}, which has no variant {value}"
+                    )],
+                }
+            }
+            Condition::Not(condition) => condition.check(on, header, enumerations, flags, device),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let mut errors = left.check(on, header, enumerations, flags, device);
+                errors.extend(right.check(on, header, enumerations, flags, device));
+                errors
+            }
+        }
+    }
+
+    /// The Rust expression for the condition, where the header is `h`. The condition
+    /// should have been checked.
+    pub fn to_expression(&self, header: &Members, flags: &FlagSets) -> String {
+        match self {
+            Condition::Field(name) if header.get(name).is_none() => {
+                let (field_name, flags_name) = flag_fields(name, header, flags)
+                    .first()
+                    .copied()
+                    .expect("Condition flags should have been validated");
+                format!(
+                    "h.{field_name}.contains({}::{})",
+                    flags_name.to_case(Case::UpperCamel),
+                    name.to_case(Case::UpperSnake)
+                )
+            }
+            Condition::Field(name) => format!("h.{name}"),
+            Condition::Compare {
+                field: name,
+                equal,
+                value,
+            } => {
+                let operator = if *equal { "==" } else { "!=" };
+                match (header.get(name), value) {
+                    (Some(Field::BitField { target_type, .. }), Value::Variant(variant)) => {
+                        let enum_name = target_type
+                            .as_ref()
+                            .expect("Enumerated condition fields should have been validated");
+                        format!(
+                            "h.{name} {operator} {enum_name}::{}",
+                            variant.to_case(Case::UpperCamel)
+                        )
+                    }
+                    (Some(field), Value::Integer(integer)) if field.range().is_some() => {
+                        format!("h.{name}.value() {operator} {integer}")
+                    }
+                    _ => format!("h.{name} {operator} {value}"),
+                }
+            }
+            Condition::Not(condition) => match **condition {
+                Condition::Field(_) | Condition::Not(_) => {
+                    format!("!{}", condition.to_expression(header, flags))
+                }
+                _ => format!("!({})", condition.to_expression(header, flags)),
+            },
+            Condition::And(left, right) => {
+                let operand = |condition: &Condition| match condition {
+                    Condition::Or(..) => format!("({})", condition.to_expression(header, flags)),
+                    _ => condition.to_expression(header, flags),
+                };
+                format!("{} && {}", operand(left), operand(right))
+            }
+            Condition::Or(left, right) => format!(
+                "{} || {}",
+                left.to_expression(header, flags),
+                right.to_expression(header, flags)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::Endian;
+
+    fn field(name: &str) -> Box<Condition> {
+        Box::new(Condition::Field(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("cts").unwrap(), *field("cts"));
+        assert_eq!(
+            parse(" cts && !err_cmd ").unwrap(),
+            Condition::And(field("cts"), Box::new(Condition::Not(field("err_cmd"))))
+        );
+        assert_eq!(
+            parse("pup_state == app_running").unwrap(),
+            Condition::Compare {
+                field: "pup_state".to_string(),
+                equal: true,
+                value: Value::Variant("app_running".to_string()),
+            }
+        );
+        assert_eq!(
+            parse("count!=0x1F").unwrap(),
+            Condition::Compare {
+                field: "count".to_string(),
+                equal: false,
+                value: Value::Integer(0x1F),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        assert_eq!(
+            parse("a || b && c").unwrap(),
+            Condition::Or(field("a"), Box::new(Condition::And(field("b"), field("c"))))
+        );
+        assert_eq!(
+            parse("(a || b) && !(c)").unwrap(),
+            Condition::And(
+                Box::new(Condition::Or(field("a"), field("b"))),
+                Box::new(Condition::Not(field("c")))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for condition in [
+            "",
+            "cts &&",
+            "cts & err",
+            "(cts",
+            "h.cts",
+            "cts == ",
+            "1cts",
+        ] {
+            assert!(parse(condition).is_err(), "{condition} should not parse");
+        }
+    }
+
+    fn le_device() -> Device {
+        Device::new("test", 8, Endian::Little)
+    }
+
+    fn header() -> (Members, HashMap<String, Enumeration>) {
+        let mut members = Members::new();
+        members.add("cts", Field::new_bitfield("0[7]", None).unwrap());
+        members.add("err_cmd", Field::new_bitfield("0[6]", None).unwrap());
+        members.add(
+            "pup_state",
+            Field::new_bitfield("1[6..7]", Some("power_up_state")).unwrap(),
+        );
+        members.add("count", Field::new_bitfield("2[]", None).unwrap());
+
+        let enumerations = HashMap::from([(
+            "power_up_state".to_string(),
+            Enumeration(HashMap::from([
+                ("reset".to_string(), 0),
+                ("app_running".to_string(), 3),
+            ])),
+        )]);
+
+        (members, enumerations)
+    }
+
+    #[test]
+    fn test_check() {
+        let (members, enumerations) = header();
+        let check = |condition: &str| {
+            parse(condition).unwrap().check(
+                "header",
+                &members,
+                &enumerations,
+                &FlagSets::new(),
+                &le_device(),
+            )
+        };
+
+        assert!(check("cts && !err_cmd").is_empty());
+        assert!(check("pup_state == app_running || count != 0").is_empty());
+
+        assert_eq!(
+            check("cts && ctx"),
+            vec!["condition field ctx is not a field of struct header"]
+        );
+        assert_eq!(
+            check("count"),
+            vec![
+                "condition field count is not a single bit, so needs to be compared with == or !="
+            ]
+        );
+        assert_eq!(
+            check("cts == 1 || count == reset"),
+            vec![
+                "condition field cts is a single bit and cannot be compared with 1",
+                "condition field count is an integer and cannot be compared with reset"
+            ]
+        );
+        assert_eq!(
+            check("pup_state != app_runing"),
+            vec!["condition field pup_state is a power_up_state, which has no variant app_runing"]
+        );
+    }
+
+    #[test]
+    fn test_check_integer_values() {
+        let members: Members = toml::from_str(
+            r#"
+            st = { bits = "0[0..5]" }
+            count = { bits = "1[]" }
+            level = { bits = "2[]", min = 1, max = 10 }
+            "#,
+        )
+        .unwrap();
+        let check = |condition: &str| {
+            parse(condition).unwrap().check(
+                "header",
+                &members,
+                &HashMap::new(),
+                &FlagSets::new(),
+                &le_device(),
+            )
+        };
+
+        assert!(check("st == 63 && count != 255 && level == 10").is_empty());
+        assert_eq!(
+            check("st == 300 || count == 256"),
+            vec![
+                "condition field st holds 0 to 63, so cannot be compared with 300",
+                "condition field count holds 0 to 255, so cannot be compared with 256"
+            ]
+        );
+        assert_eq!(
+            check("level != 0"),
+            vec!["condition field level holds 1 to 10, so cannot be compared with 0"]
+        );
+    }
+
+    #[test]
+    fn test_to_expression() {
+        let (members, _) = header();
+        let expression = |condition: &str| {
+            parse(condition)
+                .unwrap()
+                .to_expression(&members, &FlagSets::new())
+        };
+
+        assert_eq!(expression("cts"), "h.cts");
+        assert_eq!(expression("cts && !err_cmd"), "h.cts && !h.err_cmd");
+        assert_eq!(
+            expression("!(cts || err_cmd) && count == 2"),
+            "!(h.cts || h.err_cmd) && h.count == 2"
+        );
+        assert_eq!(
+            expression("(cts || err_cmd) && pup_state != app_running"),
+            "(h.cts || h.err_cmd) && h.pup_state != PowerUpState::AppRunning"
+        );
+    }
+
+    #[test]
+    fn test_flag_conditions() {
+        let mut members = Members::new();
+        members.add(
+            "status",
+            Field::new_bitfield("0[]", Some("status")).unwrap(),
+        );
+        members.add("count", Field::new_bitfield("1[]", None).unwrap());
+        let flags: FlagSets = toml::from_str(
+            r#"
+            status = { cts = 7, err_cmd = 6 }
+            "#,
+        )
+        .unwrap();
+        let check = |condition: &str| {
+            parse(condition).unwrap().check(
+                "header",
+                &members,
+                &HashMap::new(),
+                &flags,
+                &le_device(),
+            )
+        };
+        let expression =
+            |condition: &str| parse(condition).unwrap().to_expression(&members, &flags);
+
+        assert!(check("cts && !err_cmd").is_empty());
+        assert_eq!(
+            expression("cts && !err_cmd"),
+            "h.status.contains(Status::CTS) && !h.status.contains(Status::ERR_CMD)"
+        );
+        assert_eq!(
+            expression("err_cmd || count == 2"),
+            "h.status.contains(Status::ERR_CMD) || h.count == 2"
+        );
+
+        assert_eq!(
+            check("status || stcint"),
+            vec![
+                "condition field status holds the flags status, so one of the flags should be used",
+                "condition field stcint is not a field of struct header"
+            ]
+        );
+        assert_eq!(
+            check("cts == 1"),
+            vec!["condition field cts is a single bit and cannot be compared with 1"]
+        );
+
+        members.add(
+            "other_status",
+            Field::new_bitfield("2[]", Some("status")).unwrap(),
+        );
+        assert_eq!(
+            parse("cts")
+                .unwrap()
+                .check("header", &members, &HashMap::new(), &flags, &le_device()),
+            vec!["condition field cts is a flag of more than one field: status, other_status"]
+        );
+    }
+}
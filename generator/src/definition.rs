@@ -1,8 +1,9 @@
 use serde::Deserialize;
 
-use std::{collections::HashMap, fs, path::Path};
+use std::{collections::HashMap, fs, ops::Range, path::Path};
 
 use anyhow::Context;
+use bit_lang::BitSpec;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
@@ -10,60 +11,110 @@ use std::path::PathBuf;
 use convert_case::{Case, Casing};
 use crossterm::style::Stylize;
 use genco::prelude::*;
+use toml::Spanned;
 
 use crate::cargo_gen;
 use crate::command::Command;
 use crate::common_structure::CommonStructure;
+use crate::derives::optional_derives;
 use crate::doc_comment::DocComment;
-use crate::naming::{CommandName, CommonStructureName, RequestStructName};
+use crate::flags::{FlagSets, Flags};
+use crate::naming::{CommandName, CommonStructureName, RegisterName, RequestStructName};
 use crate::output::output_file;
 use crate::providers::Providers;
+use crate::register::Register;
 use crate::Enumeration;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
-use std::error::Error;
-use std::fmt;
+#[derive(Deserialize, Debug)]
+#[serde(from = "DefinitionTable")]
+pub struct Definition {
+    pub(crate) version: semver::Version,
+    pub(crate) device: Device,
 
-#[derive(Debug)]
-struct UnsupportedFeatureError(String);
+    pub(crate) commands: HashMap<String, Command>,
 
-impl fmt::Display for UnsupportedFeatureError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
+    pub(crate) registers: HashMap<String, Register>,
 
-impl Error for UnsupportedFeatureError {
-    fn description(&self) -> &str {
-        &self.0
-    }
+    //pub(crate) common_structures: Option<HashMap<String, CommonStructure>>,  See line 86
+    pub(crate) common_structures: HashMap<String, CommonStructure>,
+
+    pub(crate) enumerations: Option<HashMap<String, Enumeration>>,
+
+    pub(crate) flags: FlagSets,
+
+    // The spans of the names of the structs, commands and registers, keyed by their
+    // section and name, e.g. `commands.READ_ID`, to report problems with the names
+    name_spans: HashMap<String, Range<usize>>,
 }
 
-#[derive(Deserialize, Debug)]
+// The definition as it is given in the toml file
+#[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct Definition {
-    pub(crate) version: semver::Version,
-    pub(crate) device: Device,
+struct DefinitionTable {
+    version: semver::Version,
+    device: Device,
 
     // Note: Using the alias "commands" (in plural) seems to be a reserved name
     // in toml, at least when using vscode.
-    #[serde[alias = "command"]]
-    //pub(crate) registers: HashMap<String, Register>,
-    pub(crate) commands: HashMap<String, Command>,
+    #[serde(alias = "command", default)]
+    commands: HashMap<Spanned<String>, Command>,
+
+    #[serde(alias = "register", default)]
+    registers: HashMap<Spanned<String>, Register>,
 
     // Note: Using default here rather than Option as the default - an empty hash map -
     // makes the logic easier.
     #[serde(rename = "struct", default)]
-    //pub(crate) common_structures: Option<HashMap<String, CommonStructure>>,  See line 86
-    pub(crate) common_structures: HashMap<String, CommonStructure>,
+    common_structures: HashMap<Spanned<String>, CommonStructure>,
 
     #[serde(rename = "enum")]
-    pub(crate) enumerations: Option<HashMap<String, Enumeration>>,
+    enumerations: Option<HashMap<String, Enumeration>>,
+
+    #[serde(default)]
+    flags: FlagSets,
 }
 
-#[derive(Deserialize, Debug)]
+impl From<DefinitionTable> for Definition {
+    fn from(table: DefinitionTable) -> Self {
+        let mut name_spans = HashMap::new();
+        let mut unspanned = |section: &str, name: Spanned<String>| {
+            name_spans.insert(format!("{section}.{}", name.get_ref()), name.span());
+            name.into_inner()
+        };
+
+        let common_structures = table
+            .common_structures
+            .into_iter()
+            .map(|(name, structure)| (unspanned("struct", name), structure))
+            .collect();
+        let commands = table
+            .commands
+            .into_iter()
+            .map(|(name, command)| (unspanned("commands", name), command))
+            .collect();
+        let registers = table
+            .registers
+            .into_iter()
+            .map(|(name, register)| (unspanned("registers", name), register))
+            .collect();
+
+        Definition {
+            version: table.version,
+            device: table.device,
+            commands,
+            registers,
+            common_structures,
+            enumerations: table.enumerations,
+            flags: table.flags,
+            name_spans,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub enum Endian {
     #[serde(alias = "le")]
@@ -76,8 +127,19 @@ pub enum Endian {
     Big,
 }
 
-#[derive(Deserialize, Debug)]
+/// The bus used to communicate with the device
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
+pub enum Bus {
+    #[default]
+    #[serde(alias = "spi")]
+    Spi,
+    #[serde(alias = "i2c")]
+    I2c,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(from = "DeviceTable")]
 pub struct Device {
     pub(crate) name: String,
 
@@ -86,40 +148,141 @@ pub struct Device {
 
     /// Endian of words used transmitted/received by the devive
     pub(crate) endian: Endian,
+
+    /// The bus the device is connected to. SPI if not specified.
+    pub(crate) bus: Bus,
+
+    /// The 7 bit address of an I2C device
+    pub(crate) address: Option<u8>,
+
+    // The spans of the values of the keys given in the definition, to report limitations
+    value_spans: HashMap<&'static str, Range<usize>>,
+}
+
+// The device as it is given in the definition
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceTable {
+    name: String,
+    word_size: Spanned<u8>,
+    endian: Endian,
+    bus: Option<Spanned<Bus>>,
+    address: Option<Spanned<u8>>,
+}
+
+impl From<DeviceTable> for Device {
+    fn from(table: DeviceTable) -> Self {
+        let mut value_spans = HashMap::from([("word_size", table.word_size.span())]);
+        if let Some(bus) = &table.bus {
+            value_spans.insert("bus", bus.span());
+        }
+        if let Some(address) = &table.address {
+            value_spans.insert("address", address.span());
+        }
+
+        Device {
+            name: table.name,
+            word_size: table.word_size.into_inner(),
+            endian: table.endian,
+            bus: table.bus.map(Spanned::into_inner).unwrap_or_default(),
+            address: table.address.map(Spanned::into_inner),
+            value_spans,
+        }
+    }
+}
+
+impl Endian {
+    /// The suffix appended to the name of the runtime (de)serialization methods for
+    /// words that span more than one byte, e.g. `serialize_word_be`.
+    pub fn method_suffix(&self) -> &'static str {
+        match self {
+            Endian::Little => "",
+            Endian::Big => "_be",
+        }
+    }
 }
 
 impl Device {
-    pub fn check_limitations(&self) -> anyhow::Result<()> {
-        let mut feature_errors: Vec<&str> = Vec::new();
+    #[cfg(test)]
+    pub fn new(name: &str, word_size: u8, endian: Endian) -> Device {
+        Device {
+            name: name.to_string(),
+            word_size,
+            endian,
+            bus: Bus::Spi,
+            address: None,
+            value_spans: HashMap::new(),
+        }
+    }
 
-        if self.word_size != 8 {
-            feature_errors.push("word_size should be 8");
-        };
+    /// The Rust type that holds one device word.
+    pub fn word_type(&self) -> String {
+        format!("u{}", self.word_size)
+    }
+
+    /// The bits in the first and last word of a word range, for the endian of the
+    /// device. See `BitSpec::word_range_bits` and `BitSpec::word_range_bits_be`.
+    pub fn word_range_bits(&self, bit_spec: &BitSpec) -> Option<(u16, u16)> {
+        match self.endian {
+            Endian::Little => bit_spec.word_range_bits(self.word_size as usize),
+            Endian::Big => bit_spec.word_range_bits_be(self.word_size as usize),
+        }
+    }
 
+    /// The number of bits in one element of a word range, for the endian of the device.
+    pub fn word_range_bit_count(&self, bit_spec: &BitSpec) -> Option<usize> {
         match self.endian {
-            Endian::Little => (),
-            Endian::Big => feature_errors.push("only little endian format is supported"),
+            Endian::Little => bit_spec.word_range_bit_count(self.word_size as usize),
+            Endian::Big => bit_spec.word_range_bit_count_be(self.word_size as usize),
         }
+    }
+
+    /// The type suggested for the value of a bit spec, for the endian of the device.
+    pub fn suggested_type(&self, bit_spec: &BitSpec) -> String {
+        match self.endian {
+            Endian::Little => bit_spec.suggested_type(self.word_size as usize),
+            Endian::Big => bit_spec.suggested_type_be(self.word_size as usize),
+        }
+    }
+
+    /// The unsupported features used by the device, together with the span of the value
+    /// they concern if it is given.
+    pub fn limitations(&self) -> Vec<(&'static str, Option<Range<usize>>)> {
+        let mut feature_errors: Vec<(&str, &str)> = Vec::new();
 
-        let mut msg = String::from("Error: Unsupported features specified: ");
-        for (index, error_msg) in feature_errors.iter().enumerate() {
-            if index < feature_errors.len() - 1 {
-                msg.push_str(error_msg);
-                msg.push_str(", ");
-            } else {
-                msg.push_str(error_msg);
+        if ![8, 16, 32].contains(&self.word_size) {
+            feature_errors.push(("word_size should be 8, 16 or 32", "word_size"));
+        };
+
+        match (self.bus, self.address) {
+            (Bus::Spi, Some(_)) => {
+                feature_errors.push(("address is only used with i2c devices", "address"))
             }
+            (Bus::I2c, None) => feature_errors.push(("i2c devices need an address", "bus")),
+            (Bus::I2c, Some(address)) if address > 0x7F => {
+                feature_errors.push(("i2c address should be a 7 bit address", "address"))
+            }
+            _ => (),
         }
 
-        if feature_errors.is_empty() {
-            Ok(())
-        } else {
-            Err(UnsupportedFeatureError(msg).into())
+        if self.bus == Bus::I2c && self.word_size != 8 {
+            feature_errors.push(("i2c devices should have a word_size of 8", "word_size"));
         }
+
+        feature_errors
+            .into_iter()
+            .map(|(message, key)| (message, self.value_spans.get(key).cloned()))
+            .collect()
     }
 }
 
 impl Definition {
+    /// The span of the name of a struct, command or register in the definition, e.g. of
+    /// `READ_ID` for the section `commands` and the name `READ_ID`.
+    pub fn name_span(&self, section: &str, name: &str) -> Option<Range<usize>> {
+        self.name_spans.get(&format!("{section}.{name}")).cloned()
+    }
+
     pub fn generate_code(
         &self,
         out_path: &Path,
@@ -133,8 +296,6 @@ impl Definition {
             out_path.as_os_str().to_str().unwrap()
         );
 
-        self.device.check_limitations()?;
-
         let source_path_buf = self.generate_package_structure(out_path, project_name)?;
         let source_path = &source_path_buf.as_path();
 
@@ -144,11 +305,13 @@ impl Definition {
 
         self.generate_commands(source_path, &self.common_structures)?;
 
+        self.generate_registers(source_path, &self.common_structures)?;
+
         self.generate_types_file(source_path)?;
 
         let providers = Providers::from_definition(self);
         if gen_providers {
-            providers.generate(source_path)?;
+            providers.generate(source_path, &self.device)?;
         }
 
         self.generate_lib(source_path, &providers, tests_path.is_some())?;
@@ -188,6 +351,29 @@ impl Definition {
         Ok(source_path_buf)
     }
 
+    /// The modules of the generated crate that are not generated from the definition. The
+    /// modules of the structs, commands and registers cannot have these names.
+    pub(crate) const RUNTIME_MODULES: [&'static str; 18] = [
+        "error",
+        "types",
+        "deserialize",
+        "serialize",
+        "request",
+        "response",
+        "bits",
+        "chunk",
+        "command",
+        "transmit",
+        "i2c_transmit",
+        "poll",
+        "ranged",
+        "scaled",
+        "serde_array",
+        "ready_pin",
+        "register",
+        "transmit_async",
+    ];
+
     fn generate_lib(
         &self,
         out_path: &Path,
@@ -209,10 +395,16 @@ impl Definition {
            pub use crate::error::DeviceError;
 
 
-           $(for name in self.common_structures.keys() join(;$['\r'])=>  pub mod $(name.to_lowercase()) );
+           $(for name in self.common_structures.keys() => pub mod $(name.to_lowercase());$['\r'])
 
             // $(if self.enumerations.is_some() => $['\n']pub mod types;  )  TODO
-           $(for name in self.commands.keys() join(;$['\r'])=>  pub mod $(name.to_lowercase()) );
+           $(for name in self.commands.keys() => pub mod $(name.to_lowercase());$['\r'])
+           $(for name in self.registers.keys() => pub mod $(name.to_lowercase());$['\r'])
+
+           $(if let Some(address) = self.device.address {
+               $(DocComment::from_string("I2C address of the device").as_string())
+               pub const I2C_ADDRESS: u8 = $(format!("0x{:02X}", address));
+           })
 
            pub mod error;
            pub mod types;
@@ -221,8 +413,19 @@ impl Definition {
            pub mod request;
            pub mod response;
            pub mod bits;
+           pub mod chunk;
            pub mod command;
            pub mod transmit;
+           pub mod i2c_transmit;
+           pub mod poll;
+           pub mod ranged;
+           pub mod scaled;
+           #[cfg(feature = "serde")]
+           pub mod serde_array;
+           pub mod ready_pin;
+           pub mod register;
+           #[cfg(feature = "async")]
+           pub mod transmit_async;
 
            $(if providers.len() > 0 {
             $(DocComment::from_string("Providers").as_string())
@@ -239,8 +442,10 @@ impl Definition {
                 request
           }
 
-          $(DocComment::from_string("Entry points").as_string())
-          $(for name in self.commands.keys() join($['\n'])=> $(ref toks {self.generate_entry_point(toks, name)}) )
+          $(if !self.commands.is_empty() {
+            $(DocComment::from_string("Entry points").as_string())
+            $(for name in self.commands.keys() join($['\n'])=> $(ref toks {self.generate_entry_point(toks, name)}) )
+          })
 
 
             //     $(if tests => $['\n']  #[cfg(test)] $['\n'] mod tests;)
@@ -277,6 +482,25 @@ impl Definition {
             command.generate_command(
                 &CommandName::from(command_name.to_string()),
                 common_structures,
+                &self.flags,
+                &self.device,
+                out_path,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn generate_registers(
+        &self,
+        out_path: &Path,
+        common_structures: &HashMap<String, CommonStructure>,
+    ) -> anyhow::Result<()> {
+        for (register_name, register) in &self.registers {
+            register.generate_register(
+                &RegisterName::from(register_name.to_string()),
+                common_structures,
+                &self.device,
                 out_path,
             )?;
         }
@@ -285,7 +509,7 @@ impl Definition {
     }
 
     /// Generates a file `types.rs` that contains any type defined in the device definition,
-    /// especially the enumerations.
+    /// especially the enumerations and flags.
     /// Note: the type are generated for the whole devive rather than for individual registers as:
     /// * Imports are simpler.
     /// * They can be reused for different registers.
@@ -319,14 +543,15 @@ impl Definition {
 
                 quote_in!(tokens =>
                     #[derive(PartialEq, Debug, Copy, Clone, Default)]
+                    $(optional_derives())
                     pub enum $enum_identifier {
                         $(ref toks {self.generate_enum_items(toks, enumeration.1)})
                     }
                     $['\n']
 
-                    impl TryFrom<u8> for $enum_identifier {
+                    impl TryFrom<$(self.device.word_type())> for $enum_identifier {
                         type Error = DeviceError;
-                        fn try_from(value: u8) -> Result<Self, Self::Error> {
+                        fn try_from(value: $(self.device.word_type())) -> Result<Self, Self::Error> {
                             match value {
                                 $(ref toks {self.generate_enum_mappings(toks, enumeration.1)})
                                 _ => Err(DeviceError::EnumConversion),
@@ -334,10 +559,27 @@ impl Definition {
                         }
                     }
                     $['\n']
+
+                    impl From<$enum_identifier> for $(self.device.word_type()) {
+                        fn from(value: $enum_identifier) -> Self {
+                            value as Self
+                        }
+                    }
+                    $['\n']
                 );
             }
         };
 
+        let mut flag_sets: Vec<(&String, &Flags)> = self
+            .flags
+            .iter()
+            .map(|(name, flags)| (name.get_ref(), flags))
+            .collect();
+        flag_sets.sort_by_key(|(name, _)| *name);
+        for (name, flags) in flag_sets {
+            flags.generate(&mut tokens, name, &self.device);
+        }
+
         output_file(file, tokens)?;
 
         Ok(())
@@ -398,14 +640,18 @@ impl Definition {
                 use crate::deserialize::Deserialize;
                 use crate::error::DeviceError;
                 #[allow(unused_imports)]
-                use crate::request::{RequestArray, RequestBit, RequestField, RequestWord};
+                use crate::ranged::Ranged;
+                #[allow(unused_imports)]
+                use crate::request::{RequestArray, RequestBit, RequestField, RequestSpanningField, RequestWord};
                 #[allow(unused_imports)]
-                use crate::response::{ResponseArray, ResponseBit, ResponseField, ResponseWord};
+                use crate::response::{ResponseArray, ResponseBit, ResponseField, ResponseSpanningField, ResponseWord};
+                #[allow(unused_imports)]
+                use crate::scaled;
                 use crate::serialize::Serialize;
 
                 use crate::types::*;
 
-                $(ref toks {structure.generate(toks, &common_structure_name, common_structures)})
+                $(ref toks {structure.generate(toks, &common_structure_name, common_structures, &self.device)})
 
                 //$(ref toks {structure.generate_serializations(toks, name)})
 
@@ -427,6 +673,7 @@ impl Definition {
         // initialise the hashmap in this awkward way.
         let common_resources = HashMap::from([
             ("bits.rs", include_str!("../../common/src/bits.rs")),
+            ("chunk.rs", include_str!("../../common/src/chunk.rs")),
             (
                 "deserialize.rs",
                 include_str!("../../common/src/deserialize.rs"),
@@ -439,7 +686,27 @@ impl Definition {
                 include_str!("../../common/src/serialize.rs"),
             ),
             ("transmit.rs", include_str!("../../common/src/transmit.rs")),
+            (
+                "transmit_async.rs",
+                include_str!("../../common/src/transmit_async.rs"),
+            ),
+            (
+                "i2c_transmit.rs",
+                include_str!("../../common/src/i2c_transmit.rs"),
+            ),
+            ("poll.rs", include_str!("../../common/src/poll.rs")),
+            ("ranged.rs", include_str!("../../common/src/ranged.rs")),
+            ("scaled.rs", include_str!("../../common/src/scaled.rs")),
+            (
+                "serde_array.rs",
+                include_str!("../../common/src/serde_array.rs"),
+            ),
+            (
+                "ready_pin.rs",
+                include_str!("../../common/src/ready_pin.rs"),
+            ),
             ("command.rs", include_str!("../../common/src/command.rs")),
+            ("register.rs", include_str!("../../common/src/register.rs")),
         ]);
 
         for (file_name, code_resource) in &common_resources {
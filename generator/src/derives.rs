@@ -0,0 +1,10 @@
+use genco::prelude::*;
+
+/// The derives that the `defmt` and `serde` features of the generated crate add to every
+/// generated type.
+pub fn optional_derives() -> Tokens<Rust> {
+    quote!(
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]$['\r']
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]$['\r']
+    )
+}
@@ -33,7 +33,6 @@ impl DocComment {
     }
 
     /// Gets a iterator over all the doc comment lines.
-    #[allow(dead_code)]
     pub fn lines(&self) -> std::slice::Iter<'_, std::string::String> {
         self.0.iter()
     }
@@ -59,29 +59,54 @@ pub struct ErrorLocation {
     end_column: usize,
 }
 
+/// An error in a definition, together with the span of the TOML source that caused it
+/// (if known).
+#[derive(PartialEq, Clone, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String, span: Option<Range<usize>>) -> Diagnostic {
+        Diagnostic { message, span }
+    }
+}
+
 pub fn error_report(contents: &str, message: &str, span_range: Option<Range<usize>>) {
+    println!("{}", render(contents, message, span_range));
+}
+
+/// Render an error message with the line of the TOML source the span is in and a
+/// caret under the spanned part of the line.
+fn render(contents: &str, message: &str, span_range: Option<Range<usize>>) -> String {
+    let heading = format!("error: {message}");
+
+    // TOML spans are 0 based, content spans are 1 based.
     let span = match span_range {
         Some(range) => Span {
-            start: range.start,
-            end: range.end,
+            start: range.start + 1,
+            end: range.end + 1,
         },
-        None => {
-            println!("{}", message);
-            return;
-        }
+        None => return heading,
     };
 
     let content_ranges = ContentSpans::from(contents);
     let location = content_ranges.location(span);
-    match location {
-        Some(loc) => println!(
-            "{} at line: {}, columns: {}..{}",
-            message, loc.line, loc.start_column, loc.end_column
-        ),
-        None => println!("{}", message),
-    }
+    let Some(loc) = location else {
+        return heading;
+    };
+    let source_line = contents.lines().nth(loc.line - 1).unwrap_or_default();
+
+    let line_number = loc.line.to_string();
+    let margin = " ".repeat(line_number.len());
+    let indent = " ".repeat(loc.start_column - 1);
+    let caret = "^".repeat((loc.end_column - loc.start_column).max(1));
 
-    //println!("{} {:?}", message.red(), span);
+    format!(
+        "{heading}\n{margin}--> line: {}, columns: {}..{}\n{margin} |\n{line_number} | {source_line}\n{margin} | {indent}{caret}",
+        loc.line, loc.start_column, loc.end_column
+    )
 }
 
 #[cfg(test)]
@@ -150,6 +175,26 @@ mod tests {
         assert!(spans.location(search_range).is_none());
     }
 
+    #[test]
+    fn test_render() {
+        let content = "[commands.TEST.request]\na_field = { bits = \"0[0..4]\" }\nb_field = { bits = \"0[4..7]\" }";
+
+        // The span of the key `b_field`
+        let rendered = render(content, "b_field overlaps a_field", Some(55..62));
+
+        assert_eq!(
+            rendered,
+            "error: b_field overlaps a_field\n --> line: 3, columns: 1..8\n  |\n3 | b_field = { bits = \"0[4..7]\" }\n  | ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let rendered = render("[device]", "word_size should be 8, 16 or 32", None);
+
+        assert_eq!(rendered, "error: word_size should be 8, 16 or 32");
+    }
+
     #[test]
     fn test_sub_range() {
         let span = Span { start: 4, end: 9 };
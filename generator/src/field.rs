@@ -5,11 +5,12 @@ use genco::prelude::*;
 use serde::{de::Error, Deserialize, Deserializer};
 
 use crate::common_structure::CommonStructure;
+use crate::definition::Device;
 use crate::doc_comment::DocComment;
 use crate::members::Members;
 use bit_lang::{bit_spec::WordRange, BitRange, BitSpec, Repeat, Word};
 
-#[derive(Deserialize, Debug, Eq)]
+#[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 #[serde(untagged)]
 pub enum Field {
@@ -32,6 +33,20 @@ pub enum Field {
         target_type: Option<TargetType>,
 
         description: Option<String>,
+
+        // The lowest and highest values the field can hold
+        #[serde(default)]
+        min: Option<i64>,
+        #[serde(default)]
+        max: Option<i64>,
+
+        // The physical value of the field is raw * scale + offset, measured in unit
+        #[serde(default)]
+        scale: Option<f64>,
+        #[serde(default)]
+        offset: Option<f64>,
+        #[serde(default)]
+        unit: Option<String>,
     },
 }
 
@@ -73,6 +88,8 @@ impl PartialEq for Field {
     }
 }
 
+impl Eq for Field {}
+
 // TODO merge this with BitSpecType in  BitSpec.
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TargetType {
@@ -155,7 +172,7 @@ where
 }
 
 impl Field {
-    pub fn generate_struct_member(&self, tokens: &mut Tokens<Rust>, name: &str) {
+    pub fn generate_struct_member(&self, tokens: &mut Tokens<Rust>, name: &str, device: &Device) {
         match self {
             Field::Structure {
                 common_structure_name,
@@ -180,6 +197,7 @@ impl Field {
                 target_type,
                 description,
                 bit_spec,
+                ..
             } => {
                 if description.is_some() {
                     let comments = DocComment::from_string(description.as_deref().unwrap());
@@ -191,7 +209,7 @@ impl Field {
 
                 let type_string = match target_type {
                     Some(t) => t.clone().to_string(),
-                    None => bit_spec.suggested_word_type(),
+                    None => device.suggested_type(bit_spec),
                 };
 
                 let type_string = match bit_spec.repeat {
@@ -201,11 +219,28 @@ impl Field {
                     }
                     Repeat::Variable { limit: _ } => match target_type {
                         Some(target_type) => format!("{}", target_type),
-                        None => panic!("Variable repeat for {name} with no provider struct (in the type attribute) specified!"),
+                        None => {
+                            unreachable!("The provider type of {name} should have been validated")
+                        }
+                    },
+                    Repeat::None => match self.range() {
+                        Some((min, max)) => format!("Ranged<{type_string}, {min}, {max}>"),
+                        None => type_string,
                     },
-                    Repeat::None => type_string,
                 };
 
+                // serde only implements its traits for arrays of up to 32 elements
+                let array_length = match bit_spec.repeat {
+                    Repeat::Fixed { number } => Some(number),
+                    Repeat::Dependent { limit, .. } => Some(limit),
+                    _ => None,
+                };
+                if array_length.is_some_and(|length| length > 32) {
+                    quote_in!(*tokens =>
+                        #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]$['\r']
+                    );
+                }
+
                 // Field name
                 quote_in!(*tokens =>
                     pub $(name): $(type_string),$['\r']
@@ -220,11 +255,12 @@ impl Field {
         name: &str,
         members: &Members,
         common_structures: &CommonStructures,
+        device: &Device,
     ) {
         let field_serialize_code = match self {
             Field::BitField { bit_spec: _, .. } => {
                 //self.generate_word_field_serialization(name, bit_spec, members)
-                self.generate_word_field_serialization(name, members)
+                self.generate_word_field_serialization(name, members, device)
             }
 
             Field::Structure {
@@ -247,13 +283,27 @@ impl Field {
         name: &str,
         //symbol_table: &HashMap<BitSpec, String>,
         members: &Members,
+        device: &Device,
     ) {
         let field_deserialize_code = match self {
             Field::BitField {
                 bit_spec,
                 target_type,
                 ..
-            } => self.generate_word_field_deserialization(name, bit_spec, target_type, members),
+            } => {
+                let deserialization = self.generate_word_field_deserialization(
+                    name,
+                    bit_spec,
+                    target_type,
+                    members,
+                    device,
+                );
+                // Values read from the device are checked against the range as well
+                match self.range() {
+                    Some(_) => format!("Ranged::new({deserialization})?"),
+                    None => deserialization,
+                }
+            }
 
             Field::Structure {
                 common_structure_name,
@@ -273,9 +323,13 @@ impl Field {
         name: &str,
         //bit_spec: &BitSpec,
         members: &Members,
+        device: &Device,
     ) -> String {
         // match bit_spec {
         let bit_spec = self.bit_spec();
+        let suffix = self.endian_suffix(device);
+        let source = self.serialization_source(name);
+        let offset = members.offset(bit_spec, "self.");
         match bit_spec {
             BitSpec {
                 start:
@@ -285,7 +339,11 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
-            } => format!("data[{index}].serialize_bit(self.{name}, {bit_position})"),
+                ..
+            } => format!(
+                "data[{}].serialize_bit(self.{name}, {bit_position})",
+                offset.index(*index)
+            ),
             BitSpec {
                 start:
                     Word {
@@ -294,8 +352,16 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
+                ..
             } => {
-                format!("data[{index}].serialize_field(self.{name} as u8, {start_bit}, {end_bit})")
+                let word_type = device.word_type();
+                let index = offset.index(*index);
+                let value = if self.has_type_name() {
+                    format!("{word_type}::from({source})")
+                } else {
+                    format!("{source} as {word_type}")
+                };
+                format!("data[{index}].serialize_field({value}, {start_bit}, {end_bit})")
             }
             BitSpec {
                 start:
@@ -305,7 +371,22 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
-            } => format!("data[{index}].serialize_word(self.{name})"),
+                ..
+            } if self.has_type_name() => format!(
+                "data[{}].serialize_word({}::from({source}))",
+                offset.index(*index),
+                device.word_type()
+            ),
+            BitSpec {
+                start:
+                    Word {
+                        index,
+                        bit_range: BitRange::WholeWord,
+                    },
+                end: None,
+                repeat: Repeat::None,
+                ..
+            } => format!("data[{}].serialize_word({source})", offset.index(*index)),
             BitSpec {
                 start:
                     Word {
@@ -318,7 +399,31 @@ impl Field {
                         bit_range: BitRange::WholeWord,
                     }),
                 repeat: Repeat::None,
-            } => format!("data[{start_index}..={end_index}].serialize_word(self.{name})"),
+                ..
+            } => format!(
+                "data[{}..={}].serialize_word{suffix}({source})",
+                offset.index(*start_index),
+                offset.index(*end_index)
+            ),
+            BitSpec {
+                start: Word {
+                    index: start_index, ..
+                },
+                end: Some(Word {
+                    index: end_index, ..
+                }),
+                repeat: Repeat::None,
+                ..
+            } if bit_spec.spans_partial_words() => {
+                let Some((start_bit, end_bit)) = device.word_range_bits(bit_spec) else {
+                    unreachable!("A word range spanning partial words should have its bits")
+                };
+                format!(
+                    "data[{}..={}].serialize_spanning_field{suffix}({source}, {start_bit}, {end_bit})",
+                    offset.index(*start_index),
+                    offset.index(*end_index)
+                )
+            }
 
             BitSpec {
                 start:
@@ -333,7 +438,8 @@ impl Field {
                 let WordRange::Fixed(start_index, end_index) = bit_spec.word_range() else {
                     panic!("Repeating bit specification should have been a fixed repeat")
                 };
-                format!("data[{start_index}..={end_index}].serialize_repeating_words(self.{name}, {number})")
+                let (start_index, end_index) = (offset.index(start_index), offset.index(end_index));
+                format!("data[{start_index}..={end_index}].serialize_repeating_words{suffix}(self.{name}, {number})")
                 //format!("data[{start_index}..].serialize_repeating_words(self.{name}, {limit})")
             }
             BitSpec {
@@ -343,36 +449,13 @@ impl Field {
                         bit_range: BitRange::WholeWord,
                     },
 
-                repeat:
-                    Repeat::Dependent {
-                        bit_spec: repeat_bit_spec, // word: repeat_word,
-                        ..
-                    },
+                repeat: Repeat::Dependent { count, .. },
                 ..
-            } => {
-                // The parser assumes that the repeat word is a simple word (i.e. no range, no repeats) -
-                // for example, a byte.
-                // This excludes repeat words that are, for instance, a u16 that using two words.
-                // What follows is a workaround, but ultimately the parser
-                //  should recognise full bit specs for variable repeat words.
-                // let repeat_bit_spec = BitSpec {
-                //     start: repeat_word.clone(),
-                //     end: None,
-                //     repeat: Repeat::None,
-                // };
-                // let count_symbol_name = symbol_table.get(&repeat_bit_spec);
-                if let Some((count_symbol_name, _)) = members.find_field_by_bitspec(repeat_bit_spec)
-                {
-                    format!(
-                        "data[{start_index}..].serialize_repeating_words(self.{}, self.{} as usize)",
-                        name,
-                        count_symbol_name
-                    )
-                } else {
-                    // TODO This is a fatal error
-                    format!("Cannot find bit spec {bit_spec}")
-                }
-            }
+            } => format!(
+                "data[{}..].serialize_repeating_words{suffix}(self.{name}, self.{} as usize)",
+                offset.index(*start_index),
+                members.count_name(name, count)
+            ),
             BitSpec {
                 repeat: Repeat::Variable { .. },
                 ..
@@ -386,8 +469,8 @@ impl Field {
                         bit_range: BitRange::Literal(literal),
                     },
                 ..
-            } => format!("data[{start_index}] = {literal}"),
-            _ => format!("todo!(\"{name}\")"),
+            } => format!("data[{}] = {literal}", offset.index(*start_index)),
+            _ => unreachable!("The bit spec of {name} should have been validated"),
         }
     }
 
@@ -398,7 +481,10 @@ impl Field {
         target_type: &Option<TargetType>,
         //symbol_table: &HashMap<BitSpec, String>,
         members: &Members,
+        device: &Device,
     ) -> String {
+        let suffix = self.endian_suffix(device);
+        let offset = members.offset(bit_spec, "");
         match bit_spec {
             BitSpec {
                 start:
@@ -408,8 +494,12 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
+                ..
                 //} => format!("data[{index}].serialize_bit(self.{name}, {bit_position});"),
-            } => format!("buf[{index}].deserialize_bit({bit_position})"),
+            } => format!(
+                "buf[{}].deserialize_bit({bit_position})",
+                offset.index(*index)
+            ),
             BitSpec {
                 start:
                     Word {
@@ -418,11 +508,17 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
+                ..
             } => {
+                // Fields are deserialized into the word type so only need converting
+                // if another type has been specified.
                 let conversion = match target_type {
-                    Some(TargetType::U8) => "",
-                    _ => ".try_into()?",
+                    None => String::new(),
+                    Some(t) if t.to_string() == device.word_type() => String::new(),
+                    Some(TargetType::TypeName(_)) => ".try_into()?".to_string(),
+                    Some(t) => format!(" as {t}"),
                 };
+                let index = offset.index(*index);
                 format!("buf[{index}].deserialize_field({start_bit}, {end_bit}){conversion}")
             }
             BitSpec {
@@ -433,7 +529,18 @@ impl Field {
                     },
                 end: None,
                 repeat: Repeat::None,
-            } => format!("buf[{index}].deserialize_word()"),
+                ..
+            } if self.has_type_name() => format!("buf[{}].try_into()?", offset.index(*index)),
+            BitSpec {
+                start:
+                    Word {
+                        index,
+                        bit_range: BitRange::WholeWord,
+                    },
+                end: None,
+                repeat: Repeat::None,
+                ..
+            } => format!("buf[{}].deserialize_word()", offset.index(*index)),
             BitSpec {
                 start:
                     Word {
@@ -446,7 +553,33 @@ impl Field {
                         bit_range: BitRange::WholeWord,
                     }),
                 repeat: Repeat::None,
-            } => format!("buf[{start_index}..={end_index}].deserialize_word()"),
+                ..
+            } => format!(
+                "buf[{}..={}].deserialize_word{suffix}()",
+                offset.index(*start_index),
+                offset.index(*end_index)
+            ),
+            BitSpec {
+                start:
+                    Word {
+                        index: start_index, ..
+                    },
+                end:
+                    Some(Word {
+                        index: end_index, ..
+                    }),
+                repeat: Repeat::None,
+                ..
+            } if bit_spec.spans_partial_words() => {
+                let Some((start_bit, end_bit)) = device.word_range_bits(bit_spec) else {
+                    unreachable!("A word range spanning partial words should have its bits")
+                };
+                format!(
+                    "buf[{}..={}].deserialize_spanning_field{suffix}({start_bit}, {end_bit})",
+                    offset.index(*start_index),
+                    offset.index(*end_index)
+                )
+            }
 
             BitSpec {
                 start:
@@ -458,7 +591,8 @@ impl Field {
                 repeat: Repeat::Fixed { number },
                 ..
             } => {
-                format!("buf[{start_index}..].deserialize_repeating_words({number})")
+                let start_index = offset.index(*start_index);
+                format!("buf[{start_index}..].deserialize_repeating_words{suffix}({number})")
             }
             BitSpec {
                 start:
@@ -467,37 +601,13 @@ impl Field {
                         bit_range: BitRange::WholeWord,
                     },
 
-                repeat:
-                    Repeat::Dependent {
-                        bit_spec: repeat_bit_spec,
-                        //word: repeat_word,
-                        ..
-                    },
+                repeat: Repeat::Dependent { count, .. },
                 ..
-            } => {
-                // The parser assumes that the repeat word is a simple word (i.e. no range, no repeats) -
-                // for example, a byte.
-                // This excludes repeat words that are, for instance, a u16 that using two words.
-                // In most cases this is enough, however the symbol table maps full bit_specs to symbols as
-                // it needs to cover all symbols. What follows is a workaround, but ultimately the parser
-                //  should recognise full bit specs for variable repeat words.
-                // TODO do we need the symbol_table?
-
-                if let Some((count_symbol_name, _)) =
-                    // members.find_field_by_bitspec(&BitSpec::from_word(repeat_word))
-                    members.find_field_by_bitspec(repeat_bit_spec)
-                {
-                    format!(
-                        //"self[{start_index}..].deserialize_repeating_words(self[{}].deserialize_word() as usize)",
-                        "buf[{start_index}..].deserialize_repeating_words({} as usize)",
-                        //repeat_word.index
-                        count_symbol_name
-                    )
-                } else {
-                    println!("Cannot find bit_spec {}", bit_spec);
-                    todo!("Proper error handling");
-                }
-            }
+            } => format!(
+                "buf[{}..].deserialize_repeating_words{suffix}({} as usize)",
+                offset.index(*start_index),
+                members.count_name(name, count)
+            ),
             BitSpec {
                 start:
                     Word {
@@ -506,7 +616,7 @@ impl Field {
                     },
                 ..
             } => literal.to_string(), //format!("{literal}"),
-            _ => format!("todo!(\"{name}\")"),
+            _ => unreachable!("The bit spec of {name} should have been validated"),
         }
     }
 
@@ -522,8 +632,9 @@ impl Field {
 
         let common_structure = common_structures
             .get(common_structure_name)
-            //.expect(format!("Fatal Error: Common structure {common_structure_name} is not found! Check that the names of common structures are the same.").as_str());
-            .unwrap_or_else(|| panic!("Fatal Error: Common structure {common_structure_name} is not found! Check that the names of common structures are the same."));
+            .unwrap_or_else(|| {
+                unreachable!("Common structure {common_structure_name} should have been validated")
+            });
         let size = common_structure.size();
 
         // Position of the common structure
@@ -621,13 +732,164 @@ impl Field {
         // }
     }
 
-    fn bit_spec(&self) -> &BitSpec {
+    pub fn bit_spec(&self) -> &BitSpec {
         match self {
             Field::Structure { bit_spec, .. } => bit_spec,
             Field::BitField { bit_spec, .. } => bit_spec,
         }
     }
 
+    /// The suffix for the (de)serialization method names. Only fields whose
+    /// elements span several words have a byte order that depends on the device.
+    fn endian_suffix(&self, device: &Device) -> &'static str {
+        if self.bit_spec().end.is_some() {
+            device.endian.method_suffix()
+        } else {
+            ""
+        }
+    }
+
+    /// The lowest and highest values of a ranged field.
+    pub fn range(&self) -> Option<(i64, i64)> {
+        match self {
+            Field::BitField {
+                min: Some(min),
+                max: Some(max),
+                ..
+            } => Some((*min, *max)),
+            _ => None,
+        }
+    }
+
+    /// The lowest and highest values that the bits of an integer field can hold, or
+    /// None if the field is not a single integer.
+    pub fn value_limits(&self, device: &Device) -> Option<(i128, i128)> {
+        let Field::BitField {
+            bit_spec,
+            target_type,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        if bit_spec.repeat != Repeat::None {
+            return None;
+        }
+
+        let bits = match (&bit_spec.start.bit_range, &bit_spec.end) {
+            (BitRange::Range(start, end), None) => (end - start + 1) as u32,
+            (BitRange::WholeWord, None) => device.word_size as u32,
+            (_, Some(_)) => device.word_range_bit_count(bit_spec)? as u32,
+            _ => return None,
+        };
+        if bits >= 128 {
+            return None;
+        }
+
+        let signed = match target_type {
+            None => false,
+            Some(TargetType::I8 | TargetType::I16 | TargetType::I32 | TargetType::I64) => true,
+            Some(TargetType::U8 | TargetType::U16 | TargetType::U32 | TargetType::U64) => false,
+            Some(_) => return None,
+        };
+
+        if signed {
+            Some((-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1i128 << bits) - 1))
+        }
+    }
+
+    /// The scale, offset and unit of a field that holds a physical value.
+    pub fn scaling(&self) -> Option<(f64, f64, &str)> {
+        match self {
+            Field::BitField {
+                scale,
+                offset,
+                unit: Some(unit),
+                ..
+            } => Some((scale.unwrap_or(1.0), offset.unwrap_or(0.0), unit)),
+            _ => None,
+        }
+    }
+
+    /// Generates the methods that set and get a scaled field in its unit, e.g.
+    /// `set_capacitance_pf()` and `capacitance_pf()`. The raw value is limited to
+    /// the range of the field, or else to what its bits can hold.
+    pub fn generate_scaled_accessors(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        name: &str,
+        device: &Device,
+    ) {
+        let (
+            Field::BitField {
+                bit_spec,
+                target_type,
+                ..
+            },
+            Some((scale, offset, unit)),
+        ) = (self, self.scaling())
+        else {
+            return;
+        };
+        let Some((min, max)) = self
+            .range()
+            .map(|(min, max)| (min as i128, max as i128))
+            .or_else(|| self.value_limits(device))
+        else {
+            unreachable!("The scaling of {name} should have been validated")
+        };
+
+        let raw_type = match target_type {
+            Some(t) => t.to_string(),
+            None => device.suggested_type(bit_spec),
+        };
+        let raw_value = match self.range() {
+            Some(_) => format!("Ranged::new(raw as {raw_type})?"),
+            None => format!("raw as {raw_type}"),
+        };
+        let accessor_name = format!("{name}_{}", unit.to_lowercase());
+        let set_comment = DocComment::from_string(&format!(
+            "Sets `{name}` to a value in {unit}, rounded to the nearest step of {scale:?} {unit}.\nReturns `DeviceError::OutOfRange` if the value cannot be held by the field."
+        ));
+        let get_comment = DocComment::from_string(&format!("The value of `{name}` in {unit}."));
+
+        quote_in!(*tokens =>
+            $(for line in set_comment.lines() => $line$['\r'])
+            pub fn set_$(&accessor_name)(&mut self, value: f32) -> Result<(), DeviceError> {
+                let raw = scaled::to_raw(value, $(format!("{scale:?}")), $(format!("{offset:?}")), $min, $max)?;
+                self.$name = $raw_value;
+                Ok(())
+            }
+            $['\n']
+            $(get_comment.as_string())
+            pub fn $(&accessor_name)(&self) -> f32 {
+                scaled::from_raw($(self.serialization_source(name)) as i128, $(format!("{scale:?}")), $(format!("{offset:?}")))
+            }
+            $['\n']
+        );
+    }
+
+    // The expression for the value of the field that is serialized
+    fn serialization_source(&self, name: &str) -> String {
+        match self.range() {
+            Some(_) => format!("self.{name}.value()"),
+            None => format!("self.{name}"),
+        }
+    }
+
+    // Enumerations and flags are converted to and from the word type
+    fn has_type_name(&self) -> bool {
+        matches!(
+            self,
+            Field::BitField {
+                target_type: Some(TargetType::TypeName(_)),
+                ..
+            }
+        )
+    }
+
     /// If the field has a provider type than return the name of it.
     pub fn provider(&self) -> Option<&str> {
         match self {
@@ -648,6 +910,7 @@ impl Field {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::definition::Endian;
     use bit_lang::parse;
     #[test]
     fn test_order() {
@@ -655,12 +918,22 @@ mod tests {
             bit_spec: parse("1[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let upper = Field::BitField {
             bit_spec: parse("2[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         assert!(upper > lower);
@@ -669,12 +942,22 @@ mod tests {
             bit_spec: parse("1[]").unwrap(),
             target_type: Some(TargetType::I16),
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let upper = Field::BitField {
             bit_spec: parse("2[]").unwrap(),
             target_type: Some(TargetType::U16),
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         assert!(upper > lower);
@@ -683,12 +966,22 @@ mod tests {
             bit_spec: parse("1[]").unwrap(),
             target_type: Some(TargetType::U16),
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let upper = Field::BitField {
             bit_spec: parse("1[]").unwrap(),
             target_type: Some(TargetType::I16),
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         assert!(upper == lower);
@@ -697,12 +990,22 @@ mod tests {
             bit_spec: parse("1[]").unwrap(),
             target_type: Some(TargetType::U16),
             description: Some("ZZZZ".to_string()),
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let upper = Field::BitField {
             bit_spec: parse("1[]").unwrap(),
             target_type: Some(TargetType::I16),
             description: Some("AAAA".to_string()),
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         assert!(upper == lower);
@@ -713,7 +1016,7 @@ mod tests {
         let field = Field::new_bitfield("4", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_bool");
+        field.generate_struct_member(&mut tokens, "a_bool", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_bool: bool,");
     }
@@ -723,7 +1026,7 @@ mod tests {
         let field = Field::new_bitfield("5[1..4]", Some("AnEnum")).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_bool");
+        field.generate_struct_member(&mut tokens, "a_bool", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_bool: AnEnum,");
     }
@@ -733,7 +1036,7 @@ mod tests {
         let field = Field::new_bitfield("3[]", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_u8");
+        field.generate_struct_member(&mut tokens, "a_u8", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_u8: u8,");
     }
@@ -743,7 +1046,7 @@ mod tests {
         let field = Field::new_bitfield("3[]..4[]", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_u16");
+        field.generate_struct_member(&mut tokens, "a_u16", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_u16: u16,");
     }
@@ -752,7 +1055,7 @@ mod tests {
         let field = Field::new_bitfield("3[]..6[]", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_u32");
+        field.generate_struct_member(&mut tokens, "a_u32", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_u32: u32,");
     }
@@ -762,7 +1065,7 @@ mod tests {
         let field = Field::new_bitfield("5[];10", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_repeat");
+        field.generate_struct_member(&mut tokens, "a_repeat", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_repeat: [u8; 10],");
     }
@@ -772,17 +1075,30 @@ mod tests {
         let field = Field::new_bitfield("5[]..6[];12", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_repeat");
+        field.generate_struct_member(&mut tokens, "a_repeat", &le_device());
 
         assert_eq!(tokens.to_string().unwrap(), "pub a_repeat: [u16; 12],");
     }
 
+    #[test]
+    fn test_generate_struct_member_long_array() {
+        let field = Field::new_bitfield("5[]..6[];(1[])<=48", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+
+        field.generate_struct_member(&mut tokens, "a_long_array", &le_device());
+
+        assert_eq!(
+            tokens.to_string().unwrap(),
+            "#[cfg_attr(feature = \"serde\", serde(with = \"crate::serde_array\"))]\npub a_long_array: [u16; 48],"
+        );
+    }
+
     #[test]
     fn test_generate_struct_member_dependent_repeat_u16() {
         let field = Field::new_bitfield("5[]..6[];(1[])<12", None).unwrap();
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_dependent_repeat");
+        field.generate_struct_member(&mut tokens, "a_dependent_repeat", &le_device());
 
         assert_eq!(
             tokens.to_string().unwrap(),
@@ -796,7 +1112,7 @@ mod tests {
 
         let mut tokens = rust::Tokens::new();
 
-        field.generate_struct_member(&mut tokens, "a_variable_repeat");
+        field.generate_struct_member(&mut tokens, "a_variable_repeat", &le_device());
 
         assert_eq!(
             tokens.to_string().unwrap(),
@@ -809,7 +1125,7 @@ mod tests {
         let field = Field::new_bitfield("5[7]", None).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_bit", &fields);
+        let s = field.generate_word_field_serialization("a_bit", &fields, &le_device());
 
         assert_eq!(s, "data[5].serialize_bit(self.a_bit, 7)");
     }
@@ -819,9 +1135,9 @@ mod tests {
         let field = Field::new_bitfield("5[1..4]", Some("AnEnum")).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_field", &fields);
+        let s = field.generate_word_field_serialization("a_field", &fields, &le_device());
 
-        assert_eq!(s, "data[5].serialize_field(self.a_field as u8, 1, 4)");
+        assert_eq!(s, "data[5].serialize_field(u8::from(self.a_field), 1, 4)");
     }
 
     #[test]
@@ -829,7 +1145,7 @@ mod tests {
         let field = Field::new_bitfield("5[]", None).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_u8", &fields);
+        let s = field.generate_word_field_serialization("a_u8", &fields, &le_device());
 
         assert_eq!(s, "data[5].serialize_word(self.a_u8)");
     }
@@ -839,7 +1155,7 @@ mod tests {
         let field = Field::new_bitfield("5[]..6[]", None).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_u16", &fields);
+        let s = field.generate_word_field_serialization("a_u16", &fields, &le_device());
 
         assert_eq!(s, "data[5..=6].serialize_word(self.a_u16)");
     }
@@ -849,7 +1165,7 @@ mod tests {
         let field = Field::new_bitfield("5[];10", None).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_repeat_u8", &fields);
+        let s = field.generate_word_field_serialization("a_repeat_u8", &fields, &le_device());
 
         assert_eq!(
             s,
@@ -862,7 +1178,7 @@ mod tests {
         let field = Field::new_bitfield("5[]..8[];10", None).unwrap();
 
         let fields = Members::new();
-        let s = field.generate_word_field_serialization("a_repeat_u32", &fields);
+        let s = field.generate_word_field_serialization("a_repeat_u32", &fields, &le_device());
 
         assert_eq!(
             s,
@@ -877,7 +1193,7 @@ mod tests {
         let mut fields = Members::new();
         let dependent_field = Field::new_bitfield("2[]", None).unwrap();
         fields.add("count", dependent_field);
-        let s = field.generate_word_field_serialization("a_repeat_u32", &fields);
+        let s = field.generate_word_field_serialization("a_repeat_u32", &fields, &le_device());
 
         assert_eq!(
             s,
@@ -891,10 +1207,244 @@ mod tests {
 
         let fields = Members::new();
 
-        let s = field.generate_word_field_serialization("a_repeat_u32", &fields);
+        let s = field.generate_word_field_serialization("a_repeat_u32", &fields, &le_device());
 
         assert_eq!(s, "let provider = self.a_repeat_u32");
     }
+    #[test]
+    fn test_generate_word_big_endian_serialization() {
+        let fields = Members::new();
+
+        let field = Field::new_bitfield("5[]", None).unwrap();
+        let s = field.generate_word_field_serialization("a_u8", &fields, &be_device());
+        assert_eq!(s, "data[5].serialize_word(self.a_u8)");
+
+        let field = Field::new_bitfield("5[]..6[]", None).unwrap();
+        let s = field.generate_word_field_serialization("a_u16", &fields, &be_device());
+        assert_eq!(s, "data[5..=6].serialize_word_be(self.a_u16)");
+
+        let field = Field::new_bitfield("5[]..8[];10", None).unwrap();
+        let s = field.generate_word_field_serialization("a_repeat_u32", &fields, &be_device());
+        assert_eq!(
+            s,
+            "data[5..=44].serialize_repeating_words_be(self.a_repeat_u32, 10)"
+        );
+    }
+
+    #[test]
+    fn test_generate_word_big_endian_deserialization() {
+        let mut fields = Members::new();
+        let dependent_field = Field::new_bitfield("2[]", None).unwrap();
+        fields.add("count", dependent_field);
+
+        let field = Field::new_bitfield("5[]..6[]", None).unwrap();
+        let s = field.generate_word_field_deserialization(
+            "a_u16",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &be_device(),
+        );
+        assert_eq!(s, "buf[5..=6].deserialize_word_be()");
+
+        let field = Field::new_bitfield("3[]..4[];(2[])<=10", None).unwrap();
+        let s = field.generate_word_field_deserialization(
+            "a_repeat_u16",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &be_device(),
+        );
+        assert_eq!(s, "buf[3..].deserialize_repeating_words_be(count as usize)");
+
+        let field = Field::new_bitfield("3[];(2[])<=10", None).unwrap();
+        let s = field.generate_word_field_deserialization(
+            "a_repeat_u8",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &be_device(),
+        );
+        assert_eq!(s, "buf[3..].deserialize_repeating_words(count as usize)");
+    }
+
+    #[test]
+    fn test_generate_spanning_fields() {
+        let fields = Members::new();
+
+        let field = Field::new_bitfield("0[4]..1[7]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_struct_member(&mut tokens, "a_u12", &le_device());
+        assert_eq!(tokens.to_string().unwrap(), "pub a_u12: u16,");
+
+        let s = field.generate_word_field_serialization("a_u12", &fields, &le_device());
+        assert_eq!(s, "data[0..=1].serialize_spanning_field(self.a_u12, 4, 7)");
+        let s = field.generate_word_field_deserialization(
+            "a_u12",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &le_device(),
+        );
+        assert_eq!(s, "buf[0..=1].deserialize_spanning_field(4, 7)");
+
+        let field = Field::new_bitfield("2[]..4[4..7]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_struct_member(&mut tokens, "a_u20", &be_device());
+        assert_eq!(tokens.to_string().unwrap(), "pub a_u20: u32,");
+
+        let s = field.generate_word_field_serialization("a_u20", &fields, &be_device());
+        assert_eq!(
+            s,
+            "data[2..=4].serialize_spanning_field_be(self.a_u20, 7, 4)"
+        );
+        let s = field.generate_word_field_deserialization(
+            "a_u20",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &be_device(),
+        );
+        assert_eq!(s, "buf[2..=4].deserialize_spanning_field_be(7, 4)");
+
+        // The most significant bits of a big endian field are in the first word from bit 0
+        let field = Field::new_bitfield("0[0..3]..1[]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_struct_member(&mut tokens, "a_u12", &be_device());
+        assert_eq!(tokens.to_string().unwrap(), "pub a_u12: u16,");
+
+        let s = field.generate_word_field_serialization("a_u12", &fields, &be_device());
+        assert_eq!(
+            s,
+            "data[0..=1].serialize_spanning_field_be(self.a_u12, 3, 0)"
+        );
+
+        let device = Device::new("test", 16, Endian::Little);
+        let field = Field::new_bitfield("1[12]..2[]", None).unwrap();
+        let s = field.generate_word_field_serialization("a_u20", &fields, &device);
+        assert_eq!(
+            s,
+            "data[1..=2].serialize_spanning_field(self.a_u20, 12, 15)"
+        );
+    }
+
+    #[test]
+    fn test_generate_16_bit_word_fields() {
+        let device = Device::new("test", 16, Endian::Little);
+        let fields = Members::new();
+
+        let field = Field::new_bitfield("1[0..11]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_struct_member(&mut tokens, "a_field", &device);
+        assert_eq!(tokens.to_string().unwrap(), "pub a_field: u16,");
+
+        let s = field.generate_word_field_serialization("a_field", &fields, &device);
+        assert_eq!(s, "data[1].serialize_field(self.a_field as u16, 0, 11)");
+
+        let s = field.generate_word_field_deserialization(
+            "a_field",
+            field.bit_spec(),
+            &None,
+            &fields,
+            &device,
+        );
+        assert_eq!(s, "buf[1].deserialize_field(0, 11)");
+
+        let field = Field::new_bitfield("1[0..7]", Some("u8")).unwrap();
+        let s = field.generate_word_field_deserialization(
+            "a_u8",
+            field.bit_spec(),
+            &Some(TargetType::U8),
+            &fields,
+            &device,
+        );
+        assert_eq!(s, "buf[1].deserialize_field(0, 7) as u8");
+
+        let field = Field::new_bitfield("2[]..3[]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_struct_member(&mut tokens, "a_u32", &device);
+        assert_eq!(tokens.to_string().unwrap(), "pub a_u32: u32,");
+    }
+
+    #[test]
+    fn test_generate_scaled_accessors() {
+        let mut field = Field::new_bitfield("3[]..4[]", None).unwrap();
+        if let Field::BitField {
+            min,
+            max,
+            scale,
+            unit,
+            ..
+        } = &mut field
+        {
+            (*min, *max) = (Some(0), Some(128));
+            *scale = Some(0.25);
+            *unit = Some("pF".to_string());
+        }
+        let mut tokens = rust::Tokens::new();
+        field.generate_scaled_accessors(&mut tokens, "antenna_capacitance", &le_device());
+
+        let code = tokens.to_string().unwrap();
+        assert!(code.contains(
+            "pub fn set_antenna_capacitance_pf(&mut self, value: f32) -> Result<(), DeviceError> {"
+        ));
+        assert!(code.contains("let raw = scaled::to_raw(value, 0.25, 0.0, 0, 128)?;"));
+        assert!(code.contains("self.antenna_capacitance = Ranged::new(raw as u16)?;"));
+        assert!(code.contains("pub fn antenna_capacitance_pf(&self) -> f32 {"));
+        assert!(
+            code.contains("scaled::from_raw(self.antenna_capacitance.value() as i128, 0.25, 0.0)")
+        );
+
+        // Without a range the raw value is limited by the bits of the field
+        let mut field = Field::new_bitfield("1[4]..2[]", Some("i16")).unwrap();
+        if let Field::BitField {
+            scale,
+            offset,
+            unit,
+            ..
+        } = &mut field
+        {
+            *scale = Some(0.5);
+            *offset = Some(-40.0);
+            *unit = Some("degC".to_string());
+        }
+        let mut tokens = rust::Tokens::new();
+        field.generate_scaled_accessors(&mut tokens, "temp", &le_device());
+
+        let code = tokens.to_string().unwrap();
+        assert!(code.contains("let raw = scaled::to_raw(value, 0.5, -40.0, -2048, 2047)?;"));
+        assert!(code.contains("self.temp = raw as i16;"));
+        assert!(code.contains("pub fn temp_degc(&self) -> f32 {"));
+
+        // Fields without a unit have no accessors
+        let field = Field::new_bitfield("3[]", None).unwrap();
+        let mut tokens = rust::Tokens::new();
+        field.generate_scaled_accessors(&mut tokens, "a_u8", &le_device());
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_generate_type_name_fields() {
+        let fields = Members::new();
+
+        let field = Field::new_bitfield("2[]", Some("status")).unwrap();
+        let s = field.generate_word_field_serialization("status", &fields, &le_device());
+        assert_eq!(s, "data[2].serialize_word(u8::from(self.status))");
+        let s = field.generate_word_field_deserialization(
+            "status",
+            field.bit_spec(),
+            &Some(TargetType::TypeName("status".to_string())),
+            &fields,
+            &le_device(),
+        );
+        assert_eq!(s, "buf[2].try_into()?");
+
+        let device = Device::new("test", 16, Endian::Little);
+        let field = Field::new_bitfield("1[4..11]", Some("status")).unwrap();
+        let s = field.generate_word_field_serialization("status", &fields, &device);
+        assert_eq!(s, "data[1].serialize_field(u16::from(self.status), 4, 11)");
+    }
+
     #[test]
     fn test_provider() {
         let field = Field::new_bitfield("3[];<=10", Some("a_provider")).unwrap();
@@ -923,8 +1473,19 @@ mod tests {
     }
 
     // Test utilities
+    fn le_device() -> Device {
+        Device::new("test", 8, Endian::Little)
+    }
+
+    fn be_device() -> Device {
+        Device::new("test", 8, Endian::Big)
+    }
+
     impl Field {
-        fn new_bitfield(bit_spec: &str, target_type: Option<&str>) -> Result<Field, String> {
+        pub(crate) fn new_bitfield(
+            bit_spec: &str,
+            target_type: Option<&str>,
+        ) -> Result<Field, String> {
             // TODO proper error handling here. This requires that the error handling in the
             // generator is completely overhauled.
             let bit_spec = parse(bit_spec).map_err(|e| e.to_string())?;
@@ -938,6 +1499,11 @@ mod tests {
                 bit_spec,
                 target_type,
                 description: None,
+                min: None,
+                max: None,
+                scale: None,
+                offset: None,
+                unit: None,
             })
         }
     }
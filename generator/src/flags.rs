@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use convert_case::{Case, Casing};
+use genco::prelude::*;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::definition::Device;
+use crate::derives::optional_derives;
+use crate::doc_comment::DocComment;
+
+/// The flag sets of a definition, by their name.
+pub type FlagSets = HashMap<Spanned<String>, Flags>;
+
+/// A set of single bit flags, given by the name and the bit position of each flag
+/// in the field that holds the set.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Flags(HashMap<Spanned<String>, u8>);
+
+impl Flags {
+    #[cfg(test)]
+    pub fn new(flags: &[(&str, u8)]) -> Flags {
+        Flags(
+            flags
+                .iter()
+                .map(|(name, position)| (Spanned::new(0..0, name.to_string()), *position))
+                .collect(),
+        )
+    }
+
+    /// The flags sorted by their bit position, so that the generated code is always
+    /// the same.
+    pub fn sorted(&self) -> Vec<(&String, u8)> {
+        let mut flags: Vec<(&String, u8)> = self
+            .0
+            .iter()
+            .map(|(name, position)| (name.get_ref(), *position))
+            .collect();
+        flags.sort_by(|(name_a, position_a), (name_b, position_b)| {
+            position_a.cmp(position_b).then(name_a.cmp(name_b))
+        });
+        flags
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, flag: &str) -> bool {
+        self.0.contains_key(flag)
+    }
+
+    /// The span of the TOML key of a flag in the definition.
+    pub fn span(&self, flag: &str) -> Option<Range<usize>> {
+        self.0.get_key_value(flag).map(|(key, _)| key.span())
+    }
+
+    /// The highest bit position used by a flag.
+    pub fn highest_position(&self) -> Option<u8> {
+        self.0.values().max().copied()
+    }
+
+    /// Generates a type that holds the set of flags in a device word, with
+    /// a constant for each flag, set operations and a `Debug` that lists the flags
+    /// that are set.
+    pub fn generate(&self, tokens: &mut Tokens<Rust>, name: &str, device: &Device) {
+        let type_name = name.to_case(Case::UpperCamel);
+        let word_type = device.word_type();
+        let flags: Vec<(String, u8)> = self
+            .sorted()
+            .into_iter()
+            .map(|(flag, position)| (flag.to_case(Case::UpperSnake), position))
+            .collect();
+        let all = flags
+            .iter()
+            .fold(0u64, |all, (_, position)| all | 1 << position);
+
+        let doc_comment = DocComment::from_string(&format!(
+            "Set of the {name} flags. Bits that are not one of the flags are kept, so that\nthey are written back unchanged."
+        ));
+
+        quote_in!(*tokens =>
+            $(doc_comment.as_string())
+            #[derive(PartialEq, Eq, Hash, Copy, Clone, Default)]
+            $(optional_derives())
+            pub struct $(&type_name)($(&word_type));
+            $['\n']
+            impl $(&type_name) {
+                $(for (flag, position) in &flags => pub const $flag: Self = Self(1 << $(*position));$['\r'])
+                $['\n']
+                const NAMED: [(&'static str, Self); $(flags.len())] = [
+                    $(for (flag, _) in &flags => ($(quoted(flag)), Self::$flag),$['\r'])
+                ];
+                $['\n']
+                $(DocComment::from_string("The set without any flags.").as_string())
+                pub const fn empty() -> Self {
+                    Self(0)
+                }
+                $['\n']
+                $(DocComment::from_string("The set of all the flags.").as_string())
+                pub const fn all() -> Self {
+                    Self($(format!("0x{all:X}")))
+                }
+                $['\n']
+                pub const fn bits(&self) -> $(&word_type) {
+                    self.0
+                }
+                $['\n']
+                $(DocComment::from_string("Keeps all the bits, including any that are not one of the flags.").as_string())
+                pub const fn from_bits_retain(bits: $(&word_type)) -> Self {
+                    Self(bits)
+                }
+                $['\n']
+                pub const fn is_empty(&self) -> bool {
+                    self.0 == 0
+                }
+                $['\n']
+                $(DocComment::from_string("Whether all the flags of `other` are set.").as_string())
+                pub const fn contains(&self, other: Self) -> bool {
+                    self.0 & other.0 == other.0
+                }
+                $['\n']
+                $(DocComment::from_string("Whether any of the flags of `other` are set.").as_string())
+                pub const fn intersects(&self, other: Self) -> bool {
+                    self.0 & other.0 != 0
+                }
+                $['\n']
+                pub fn insert(&mut self, other: Self) {
+                    self.0 |= other.0;
+                }
+                $['\n']
+                pub fn remove(&mut self, other: Self) {
+                    self.0 &= !other.0;
+                }
+                $['\n']
+                pub fn toggle(&mut self, other: Self) {
+                    self.0 ^= other.0;
+                }
+                $['\n']
+                $(DocComment::from_string("Inserts the flags of `other` if `value` is true, otherwise removes them.").as_string())
+                pub fn set(&mut self, other: Self, value: bool) {
+                    if value {
+                        self.insert(other);
+                    } else {
+                        self.remove(other);
+                    }
+                }
+            }
+            $['\n']
+            impl core::ops::BitOr for $(&type_name) {
+                type Output = Self;
+                fn bitor(self, other: Self) -> Self {
+                    Self(self.0 | other.0)
+                }
+            }
+            $['\n']
+            impl core::ops::BitOrAssign for $(&type_name) {
+                fn bitor_assign(&mut self, other: Self) {
+                    self.0 |= other.0;
+                }
+            }
+            $['\n']
+            impl core::ops::BitAnd for $(&type_name) {
+                type Output = Self;
+                fn bitand(self, other: Self) -> Self {
+                    Self(self.0 & other.0)
+                }
+            }
+            $['\n']
+            impl core::ops::BitAndAssign for $(&type_name) {
+                fn bitand_assign(&mut self, other: Self) {
+                    self.0 &= other.0;
+                }
+            }
+            $['\n']
+            impl core::ops::BitXor for $(&type_name) {
+                type Output = Self;
+                fn bitxor(self, other: Self) -> Self {
+                    Self(self.0 ^ other.0)
+                }
+            }
+            $['\n']
+            impl core::ops::BitXorAssign for $(&type_name) {
+                fn bitxor_assign(&mut self, other: Self) {
+                    self.0 ^= other.0;
+                }
+            }
+            $['\n']
+            impl core::ops::Sub for $(&type_name) {
+                type Output = Self;
+                fn sub(self, other: Self) -> Self {
+                    Self(self.0 & !other.0)
+                }
+            }
+            $['\n']
+            impl core::ops::SubAssign for $(&type_name) {
+                fn sub_assign(&mut self, other: Self) {
+                    self.0 &= !other.0;
+                }
+            }
+            $['\n']
+            $(DocComment::from_string("The complement within the flags, so without any other bits.").as_string())
+            impl core::ops::Not for $(&type_name) {
+                type Output = Self;
+                fn not(self) -> Self {
+                    Self(!self.0 & Self::all().0)
+                }
+            }
+            $['\n']
+            $(DocComment::from_string("Lists the flags that are set, e.g. `Status(CTS | ERR_CMD)`, followed by any other\nbits in hex.").as_string())
+            impl core::fmt::Debug for $(&type_name) {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    write!(f, $(quoted(format!("{type_name}("))))?;
+                    let mut separator = "";
+                    for (name, flag) in Self::NAMED {
+                        if self.contains(flag) {
+                            write!(f, "{separator}{name}")?;
+                            separator = " | ";
+                        }
+                    }
+                    let other_bits = self.0 & !Self::all().0;
+                    if other_bits != 0 || self.is_empty() {
+                        write!(f, "{separator}{other_bits:#x}")?;
+                    }
+                    write!(f, ")")
+                }
+            }
+            $['\n']
+            impl From<$(&type_name)> for $(&word_type) {
+                fn from(flags: $(&type_name)) -> Self {
+                    flags.0
+                }
+            }
+            $['\n']
+            $(DocComment::from_string("Never fails, as bits that are not one of the flags are kept.").as_string())
+            impl TryFrom<$(&word_type)> for $(&type_name) {
+                type Error = DeviceError;
+                fn try_from(bits: $(&word_type)) -> Result<Self, Self::Error> {
+                    Ok(Self(bits))
+                }
+            }
+            $['\n']
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::Endian;
+
+    #[test]
+    fn test_sorted() {
+        let flags = Flags::new(&[("cts", 7), ("err_cmd", 6), ("stcint", 0)]);
+
+        let sorted: Vec<(&str, u8)> = flags
+            .sorted()
+            .into_iter()
+            .map(|(name, position)| (name.as_str(), position))
+            .collect();
+
+        assert_eq!(sorted, [("stcint", 0), ("err_cmd", 6), ("cts", 7)]);
+        assert_eq!(flags.highest_position(), Some(7));
+    }
+
+    #[test]
+    fn test_generate() {
+        let flags = Flags::new(&[("cts", 7), ("err_cmd", 6), ("stcint", 0)]);
+        let mut tokens = rust::Tokens::new();
+
+        flags.generate(
+            &mut tokens,
+            "status",
+            &Device::new("test", 8, Endian::Little),
+        );
+
+        let code = tokens.to_string().unwrap();
+        assert!(code.contains("pub struct Status(u8);"));
+        assert!(code.contains("pub const STCINT: Self = Self(1 << 0);"));
+        assert!(code.contains("pub const ERR_CMD: Self = Self(1 << 6);"));
+        assert!(code.contains("pub const CTS: Self = Self(1 << 7);"));
+        assert!(code.contains("(\"CTS\", Self::CTS),"));
+        assert!(code.contains("Self(0xC1)"));
+        assert!(code.contains("write!(f, \"Status(\")?;"));
+        assert!(code.contains("impl From<Status> for u8 {"));
+        assert!(code.contains("impl TryFrom<u8> for Status {"));
+    }
+}
@@ -1,21 +1,203 @@
 use serde::Deserialize;
 
+use crate::condition::Condition;
+
 /// Flow control patterns
 #[derive(Deserialize, Debug, Default)]
-#[serde(tag = "type")]
+#[serde(from = "FlowControlTable")]
 pub enum FlowControl {
     /// Request data is send to the peripheral and then the response data is directly read in afterwards.
     #[default]
-    #[serde(alias = "direct")]
     Direct,
 
     /// After the request data is sent, a small and fixed amount of data (a header) is read and inspected.
-    /// If a condition is met (e.g. a bit is set) then the rest of the data is read. If the condition is
-    /// not met then another read of the header is performed until the condition is met.
+    /// If a condition over the header fields is met (e.g. `cts && !err_cmd`) then the rest of the data is read. If the condition is
+    /// not met then another read of the header is performed until the condition is met, or
+    /// until the `limits` are reached.
+    Polled {
+        on: String,
+        condition: Condition,
+        limits: PollLimits,
+    },
+
+    /// Request data is sent to the peripheral and then, after a delay of `delay_us` microseconds,
+    /// the response data is read in.
+    Timed { delay_us: u32 },
+
+    /// A large amount of data is sent by repeating the command with up to `chunk_size` words
+    /// of the data each time. Between the chunks the header is polled as for `Polled`.
+    Chunked {
+        chunk_size: usize,
+        on: String,
+        condition: Condition,
+        limits: PollLimits,
+    },
+
+    /// Request data is sent to the peripheral and the response data is read in once an extra
+    /// ready pin is `active`. Without a `timeout_us` the pin is waited on indefinitely.
+    ReadyPin {
+        active: ActiveLevel,
+        timeout_us: Option<u32>,
+    },
+
+    /// Request data is sent to the peripheral and then single words are read until the device
+    /// holds MISO at the `active` level, which signals that the response data can be read in.
+    /// After the first word, at most `max_retries` more words are read.
+    MisoReady {
+        active: ActiveLevel,
+        max_retries: u32,
+    },
+}
+
+impl FlowControl {
+    /// True if the generated `send` function takes a `DelayNs` from the caller
+    pub fn needs_delay(&self) -> bool {
+        match self {
+            FlowControl::Timed { .. } => true,
+            FlowControl::ReadyPin { timeout_us, .. } => timeout_us.is_some(),
+            FlowControl::Polled { limits, .. } | FlowControl::Chunked { limits, .. } => {
+                limits.needs_delay()
+            }
+            FlowControl::Direct | FlowControl::MisoReady { .. } => false,
+        }
+    }
+}
+
+/// The flow control as it is written in the definition. The poll limits are listed with the
+/// other keys of the variants, as unknown keys cannot be rejected with `#[serde(flatten)]`.
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum FlowControlTable {
+    #[serde(alias = "direct")]
+    Direct,
     #[serde(alias = "polled")]
-    Polled { on: String, condition: String },
-    // The following flow control patterns are not currently supported
-    // - Timer. Request data is send to the peripheral and then, after a delay, the response data is directly read in afterwards.
-    // - ReadyPin. An extra ready pin signals that data can be read in.
-    // - MisoPin. The MISO pin is used to signal that data is now available
+    Polled {
+        on: String,
+        condition: Condition,
+        max_polls: Option<u32>,
+        poll_interval_us: Option<u32>,
+        timeout_us: Option<u32>,
+    },
+    #[serde(alias = "timed")]
+    Timed { delay_us: u32 },
+    #[serde(alias = "chunked")]
+    Chunked {
+        chunk_size: usize,
+        on: String,
+        condition: Condition,
+        max_polls: Option<u32>,
+        poll_interval_us: Option<u32>,
+        timeout_us: Option<u32>,
+    },
+    #[serde(alias = "ready_pin")]
+    ReadyPin {
+        active: ActiveLevel,
+        timeout_us: Option<u32>,
+    },
+    #[serde(alias = "miso_ready")]
+    MisoReady {
+        #[serde(default)]
+        active: ActiveLevel,
+        #[serde(default = "default_max_retries")]
+        max_retries: u32,
+    },
+}
+
+impl From<FlowControlTable> for FlowControl {
+    fn from(table: FlowControlTable) -> Self {
+        match table {
+            FlowControlTable::Direct => FlowControl::Direct,
+            FlowControlTable::Polled {
+                on,
+                condition,
+                max_polls,
+                poll_interval_us,
+                timeout_us,
+            } => FlowControl::Polled {
+                on,
+                condition,
+                limits: PollLimits {
+                    max_polls,
+                    poll_interval_us,
+                    timeout_us,
+                },
+            },
+            FlowControlTable::Timed { delay_us } => FlowControl::Timed { delay_us },
+            FlowControlTable::Chunked {
+                chunk_size,
+                on,
+                condition,
+                max_polls,
+                poll_interval_us,
+                timeout_us,
+            } => FlowControl::Chunked {
+                chunk_size,
+                on,
+                condition,
+                limits: PollLimits {
+                    max_polls,
+                    poll_interval_us,
+                    timeout_us,
+                },
+            },
+            FlowControlTable::ReadyPin { active, timeout_us } => {
+                FlowControl::ReadyPin { active, timeout_us }
+            }
+            FlowControlTable::MisoReady {
+                active,
+                max_retries,
+            } => FlowControl::MisoReady {
+                active,
+                max_retries,
+            },
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    1000
+}
+
+/// The poll interval used when a timeout is given without one
+const DEFAULT_POLL_INTERVAL_US: u32 = 100;
+
+/// How long a polled header is read before giving up. Either the header is read at most
+/// `max_polls` times or, with a `timeout_us`, the header is read every `poll_interval_us`
+/// microseconds until the timeout. Without either the header is polled indefinitely.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PollLimits {
+    pub max_polls: Option<u32>,
+    pub poll_interval_us: Option<u32>,
+    pub timeout_us: Option<u32>,
+}
+
+impl PollLimits {
+    pub fn poll_interval_us(&self) -> u32 {
+        self.poll_interval_us.unwrap_or(DEFAULT_POLL_INTERVAL_US)
+    }
+
+    /// A timeout needs the caller to supply a delay
+    pub fn needs_delay(&self) -> bool {
+        self.timeout_us.is_some()
+    }
+}
+
+/// The level of a pin when it is active
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum ActiveLevel {
+    #[serde(alias = "high")]
+    High,
+    #[default]
+    #[serde(alias = "low")]
+    Low,
+}
+
+impl ActiveLevel {
+    /// The name of the level in the generated code
+    pub fn variant(&self) -> &'static str {
+        match self {
+            ActiveLevel::High => "ActiveLevel::High",
+            ActiveLevel::Low => "ActiveLevel::Low",
+        }
+    }
 }
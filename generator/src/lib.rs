@@ -6,21 +6,31 @@ use std::path::{Path, PathBuf};
 use crossterm::style::Stylize;
 use serde::Deserialize;
 
-use crate::{definition::Definition, error_reporting::error_report};
+use crate::{
+    definition::Definition,
+    error_reporting::{error_report, Diagnostic},
+};
 
 mod access;
+mod bit_specs;
+mod bus;
 mod cargo_gen;
 mod command;
 mod common_structure;
+mod condition;
 mod definition;
+mod derives;
 mod doc_comment;
 mod error_reporting;
 mod field;
+mod flags;
 mod flow_control;
 mod members;
 mod naming;
 mod output;
 mod providers;
+mod register;
+mod validation;
 
 // TODO move this to another file as for the other serde structs.
 #[derive(Deserialize, Debug)]
@@ -35,10 +45,18 @@ pub fn generate(
     tests_path: &Option<PathBuf>,
     toml_specification: &str,
 ) {
+    if report(toml_specification, &bit_specs::check(toml_specification)) {
+        return;
+    }
+
     let parse_result: Result<Definition, toml::de::Error> = toml::from_str(toml_specification);
 
     match parse_result {
         Ok(definition) => {
+            if report(toml_specification, &validation::validate(&definition)) {
+                return;
+            }
+
             definition
                 .generate_code(out_path, project_name, gen_providers, tests_path)
                 .expect("Unable to generate driver code");
@@ -52,3 +70,25 @@ pub fn generate(
         }
     };
 }
+
+// Reports the diagnostics with the TOML source they refer to. Returns true if there
+// were any, in which case no code should be generated.
+fn report(toml_specification: &str, diagnostics: &[Diagnostic]) -> bool {
+    if diagnostics.is_empty() {
+        return false;
+    }
+
+    for diagnostic in diagnostics {
+        error_report(
+            toml_specification,
+            &diagnostic.message,
+            diagnostic.span.clone(),
+        );
+    }
+    println!(
+        "{}",
+        format!("Found {} errors, no code generated", diagnostics.len()).red()
+    );
+
+    true
+}
@@ -1,16 +1,25 @@
 use bit_lang::bit_spec::WordRange;
-use bit_lang::BitSpec;
+use bit_lang::{BitSpec, Repeat, RepeatCount};
 use genco::prelude::*;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::ops::Range;
+use toml::Spanned;
 
 use crate::common_structure::CommonStructure;
+use crate::definition::Device;
 use crate::field::Field;
 use crate::naming::RequestStructName;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 #[serde(deny_unknown_fields)]
-pub struct Members(HashMap<String, Field>);
+pub struct Members(HashMap<Spanned<String>, Field>);
+
+impl From<HashMap<Spanned<String>, Field>> for Members {
+    fn from(fields: HashMap<Spanned<String>, Field>) -> Self {
+        Members(fields)
+    }
+}
 
 type CommonStructures = HashMap<String, CommonStructure>;
 type FieldName = String;
@@ -24,25 +33,19 @@ impl Members {
 
     #[cfg(test)]
     pub fn add(&mut self, name: &str, field: Field) {
-        self.0.insert(name.to_string(), field);
+        self.0.insert(Spanned::new(0..0, name.to_string()), field);
     }
 
-    pub fn generate_members(&self, tokens: &mut Tokens<Rust>) {
-        let mut sorted_members = self.to_vec();
-
-        // Sort by fields, not by the name
-        sorted_members.sort_by(|(_, field_a), (_, field_b)| field_a.cmp(field_b));
+    pub fn generate_members(&self, tokens: &mut Tokens<Rust>, device: &Device) {
+        let sorted_members = self.sorted();
 
         for (name, field) in sorted_members {
-            field.generate_struct_member(tokens, name);
+            field.generate_struct_member(tokens, name, device);
         }
     }
 
     pub fn generate_defaults(&self, tokens: &mut Tokens<Rust>, request_name: RequestStructName) {
-        let mut sorted_members = self.to_vec();
-
-        // Sort by fields, not by the name
-        sorted_members.sort_by(|(_, field_a), (_, field_b)| field_a.cmp(field_b));
+        let sorted_members = self.sorted();
 
         quote_in!(*tokens =>
             #[allow(clippy::derivable_impls)]
@@ -63,34 +66,59 @@ impl Members {
         // request_struct_name: &RequestStructName,
         struct_name: impl FormatInto<Rust> + Clone,
         common_structures: &CommonStructures,
+        device: &Device,
     ) {
-        let mut sorted_members: Vec<_> = self.to_vec();
+        let sorted_members = self.sorted();
 
-        // Sort by fields, not by the name. This should give an order that is closer to what woudl be in a
-        // device data sheet. It is also essential for an integration testing as code generation should
-        // always give the same results.
-        sorted_members.sort_by(|(_, field_a), (_, field_b)| field_a.cmp(field_b));
-
-        // Generate the expresssion required to calculate the actual nmber of bytes serialized
+        // Generate the expresssion required to calculate the actual nmber of words serialized
         let serialization_size_expression = self.generate_serialization_size_expression();
 
+        let word_type = device.word_type();
+
+        let count_limits = self.count_limits();
+
         quote_in!(*tokens =>
-            impl Serialize for $(struct_name) {
-                fn serialize<const N: usize>(&self) -> (usize, [u8; N], impl Iterator<Item=u8>) {
-                  let mut data = [0u8; N];
+            impl Serialize<$(&word_type)> for $(struct_name) {
+                fn serialize<const N: usize>(&self) -> (usize, [$(&word_type); N], impl Iterator<Item=$(&word_type)>) {
+                  #[allow(unused_mut)]
+                  let mut data: [$(&word_type); N] = [0; N];
                   #[allow(unused_variables)]
-                  let provider = core::iter::empty::<u8>();
+                  let provider = core::iter::empty::<$(&word_type)>();
 
-                  $(for (name, field) in sorted_members => $(ref toks {field.generate_field_serialization(toks,  name,  self, common_structures)}) )
+                  $(for (name, field) in sorted_members => $(ref toks {field.generate_field_serialization(toks, name, self, common_structures, device)}) )
 
                   ($(serialization_size_expression), data, provider)
                 }
+                $(if !count_limits.is_empty() {
 
+                    fn check_counts(&self) -> Result<(), DeviceError> {
+                        $(for (count_name, limit) in &count_limits => $(Self::generate_count_check(&format!("self.{count_name}"), *limit))$['\r'])
+                        Ok(())
+                    }
+                })
 
             }
         );
     }
 
+    /// The names of the fields holding the number of repeats of dependent repeats, together
+    /// with the limits of those repeats.
+    fn count_limits(&self) -> Vec<(&str, usize)> {
+        self.sorted()
+            .into_iter()
+            .filter_map(|(name, field)| match &field.bit_spec().repeat {
+                Repeat::Dependent { count, limit } => Some((self.count_name(name, count), *limit)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Returns an error if a count is over the limit of its repeat, as the words
+    // following it would otherwise be outside the buffer.
+    fn generate_count_check(count: &str, limit: usize) -> String {
+        format!("if {count} as usize > {limit} {{ return Err(DeviceError::OutOfRange); }}")
+    }
+
     /// Generate the expresssion required to calculate the actual number of bytes serialized
     fn generate_serialization_size_expression(&self) -> String {
         let (fixed_size, variable_sizes) = self.size();
@@ -100,7 +128,17 @@ impl Members {
             .map(|s| format!(" + ({} * self.{} as usize)", s.0, s.1))
             .fold(String::new(), |acc, s| acc + s.as_str());
 
-        format!("{fixed_size}{variable_size_expression}")
+        let size_expression = format!("{fixed_size}{variable_size_expression}");
+
+        // Fields that follow others end at a position only known at run time
+        self.sorted()
+            .into_iter()
+            .map(|(_, field)| field.bit_spec())
+            .filter(|bit_spec| bit_spec.follows.is_some() && bit_spec.size().is_some())
+            .map(|bit_spec| self.end_offset(bit_spec, "self.").index(0))
+            .fold(size_expression, |acc, end| {
+                format!("core::cmp::max({acc}, {end})")
+            })
     }
 
     pub fn generate_deserializations(
@@ -108,18 +146,26 @@ impl Members {
         tokens: &mut Tokens<Rust>,
         // response_struct_name: &ResponseStructName,
         struct_name: impl FormatInto<Rust> + Clone,
+        device: &Device,
     ) {
-        let mut sorted_members: Vec<_> = self.to_vec();
-
-        // Sort by fields, not by the name
-        sorted_members.sort_by(|(_, field_a), (_, field_b)| field_a.cmp(field_b));
+        let sorted_members = self.sorted();
+        let count_limits = self.count_limits();
+
+        // The counts are checked as soon as they are read, before any later position depends on them
+        let count_checks = |name: &str| {
+            count_limits
+                .iter()
+                .filter(|(count_name, _)| *count_name == name)
+                .map(|(count_name, limit)| Self::generate_count_check(count_name, *limit))
+                .collect::<Vec<_>>()
+        };
 
         quote_in!(*tokens=>
-           impl Deserialize<Self> for $(struct_name.clone()) {
+           impl Deserialize<Self, $(device.word_type())> for $(struct_name.clone()) {
 
-               fn deserialize(buf: &[u8]) -> Result<$(struct_name), DeviceError> { $['\r']
+               fn deserialize(buf: &[$(device.word_type())]) -> Result<$(struct_name), DeviceError> { $['\r']
 
-                    $(for (name, field) in &sorted_members => let $(*name) = $(ref toks {field.generate_field_deserialization(toks,  name, self)}) ) $['\r']
+                    $(for (name, field) in &sorted_members => let $(*name) = $(ref toks {field.generate_field_deserialization(toks, name, self, device)}) $(for check in count_checks(name) => $check$['\r']) ) $['\r']
 
                     Ok(Self {$['\r']
                         $(for (name, _) in &sorted_members => $(*name),$['\r'])
@@ -132,9 +178,33 @@ impl Members {
         );
     }
 
+    /// Generates the accessors that set and get scaled fields in their units. Nothing
+    /// is generated if none of the fields are scaled.
+    pub fn generate_scaled_accessors(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        struct_name: impl FormatInto<Rust>,
+        device: &Device,
+    ) {
+        let scaled_members: Vec<(&String, &Field)> = self
+            .sorted()
+            .into_iter()
+            .filter(|(_, field)| field.scaling().is_some())
+            .collect();
+        if scaled_members.is_empty() {
+            return;
+        }
+
+        quote_in!(*tokens =>
+            impl $(struct_name) {
+                $(for (name, field) in scaled_members => $(ref toks {field.generate_scaled_accessors(toks, name, device)}))
+            }
+        );
+    }
+
     /// Calculates the max size in bytes of a set of members. This is required
     /// so that the buffers for the structures can be sized to cater for
-    /// largest size.
+    /// largest size. Members without any fields need no buffer.
     ///
     pub fn buffer_size(&self, common_structures: &HashMap<String, CommonStructure>) -> usize {
         let mut buffer_size = 0;
@@ -144,7 +214,9 @@ impl Members {
                     bit_spec: bit_range,
                     ..
                 } => {
-                    let max_pos = bit_range.start.index + bit_range.max_size() - 1;
+                    let max_pos =
+                        self.max_offset(bit_range) + bit_range.start.index + bit_range.max_size()
+                            - 1;
                     if max_pos >= buffer_size {
                         buffer_size = max_pos + 1;
                     }
@@ -166,8 +238,39 @@ impl Members {
         buffer_size
     }
 
+    /// The members in the order they are (de)serialized. This is the order of their bit specs,
+    /// except that fields following others come after the fields they follow.
+    pub fn sorted(&self) -> Vec<(&String, &Field)> {
+        let mut sorted_members = self.to_vec();
+
+        // Sort by fields, not by the name. This should give an order that is closer to what would be in a
+        // device data sheet. It is also essential for an integration testing as code generation should
+        // always give the same results.
+        sorted_members.sort_by(|(_, field_a), (_, field_b)| {
+            self.follows_depth(field_a.bit_spec())
+                .cmp(&self.follows_depth(field_b.bit_spec()))
+                .then(field_a.cmp(field_b))
+        });
+
+        sorted_members
+    }
+
+    /// True if any of the fields is restricted to a range
+    pub fn has_ranged(&self) -> bool {
+        self.fields().any(|field| field.range().is_some())
+    }
+
+    /// True if any of the fields is scaled
+    pub fn has_scaled(&self) -> bool {
+        self.fields().any(|field| field.scaling().is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn to_vec(&self) -> Vec<(&String, &Field)> {
-        let v = self.0.iter().collect();
+        let v = self.iter().collect();
         v
     }
 
@@ -176,7 +279,16 @@ impl Members {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Field)> {
-        self.0.iter()
+        self.0.iter().map(|(name, field)| (name.get_ref(), field))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.0.get(name)
+    }
+
+    /// The span of the TOML key of a member in the definition.
+    pub fn span(&self, name: &str) -> Option<Range<usize>> {
+        self.0.get_key_value(name).map(|(key, _)| key.span())
     }
 
     pub fn size(&self) -> MembersSize {
@@ -184,6 +296,7 @@ impl Members {
             .iter()
             .filter_map(|f| match f.1 {
                 Field::Structure { .. } => None,
+                Field::BitField { bit_spec, .. } if bit_spec.follows.is_some() => None,
                 Field::BitField { bit_spec, .. } => Some((f.0, bit_spec)),
             })
             .filter_map(|f| {
@@ -200,6 +313,7 @@ impl Members {
             .iter()
             .filter_map(|f| match f.1 {
                 Field::Structure { .. } => None,
+                Field::BitField { bit_spec, .. } if bit_spec.follows.is_some() => None,
                 Field::BitField { bit_spec, .. } => bit_spec.size(),
             })
             .filter_map(|s| match s.1 {
@@ -207,7 +321,7 @@ impl Members {
                 None => None,
             })
             .map(|s| (s.0, s.1))
-            .map(|s| (s.0, self.find_field_by_bitspec(&s.1).unwrap()))
+            .map(|s| (s.0, self.find_count_field(&s.1).unwrap()))
             .map(|s| (s.0, s.1 .0.to_string()))
             //.map(|s| format!(" + ({} * self.{} as usize)", s.0, s.1))
             .collect();
@@ -223,13 +337,126 @@ impl Members {
                 Field::BitField { bit_spec, .. } => bit_spec,
                 Field::Structure { bit_spec, .. } => bit_spec,
             };
-            if field_bit_spec == bit_spec {
+            if field_bit_spec.same_bits(bit_spec) {
                 found_field = Some((name.as_str(), field));
                 break;
             }
         }
         found_field
     }
+
+    /// Finds the field that a symbol, as used to follow a field or to count repeats,
+    /// refers to. The symbol is either the label of the bit spec of the field or, if
+    /// no bit spec has that label, the name of the field.
+    pub fn find_field_by_symbol(&self, symbol: &str) -> Option<(&str, &Field)> {
+        self.iter()
+            .find(|(_, field)| field.bit_spec().label.as_deref() == Some(symbol))
+            .map(|(name, field)| (name.as_str(), field))
+            .or_else(|| {
+                self.0
+                    .get_key_value(symbol)
+                    .map(|(name, field)| (name.get_ref().as_str(), field))
+            })
+    }
+
+    /// Finds the field holding the number of repeats of a dependent repeat.
+    pub fn find_count_field(&self, count: &RepeatCount) -> Option<(&str, &Field)> {
+        match count {
+            RepeatCount::BitSpec(bit_spec) => self.find_field_by_bitspec(bit_spec),
+            RepeatCount::Symbol(symbol) => self.find_field_by_symbol(symbol),
+        }
+    }
+
+    /// The name of the field holding the number of repeats of the field `name`.
+    pub fn count_name(&self, name: &str, count: &RepeatCount) -> &str {
+        let (count_name, _) = self.find_count_field(count).unwrap_or_else(|| {
+            unreachable!("The repeat count of {name} should have been validated")
+        });
+        count_name
+    }
+
+    /// The position of the first word of a bit spec that follows another field, with
+    /// the repeat counts read from `source` (e.g. `self.` when serializing).
+    pub fn offset(&self, bit_spec: &BitSpec, source: &str) -> Offset {
+        match &bit_spec.follows {
+            Some(symbol) => {
+                let (_, followed) = self
+                    .find_field_by_symbol(symbol)
+                    .unwrap_or_else(|| unreachable!("{symbol} should have been validated"));
+                self.end_offset(followed.bit_spec(), source)
+            }
+            None => Offset::default(),
+        }
+    }
+
+    /// The position of the word after the last word of a bit spec.
+    fn end_offset(&self, bit_spec: &BitSpec, source: &str) -> Offset {
+        let mut offset = self.offset(bit_spec, source);
+        offset.words += bit_spec.start.index;
+        match bit_spec.size() {
+            Some((words, None)) => offset.words += words,
+            Some((span, Some(count))) => {
+                let count_name = self.count_name(&bit_spec.to_string(), &count);
+                offset.counts.push(match span {
+                    1 => format!("{source}{count_name} as usize"),
+                    _ => format!("{span} * {source}{count_name} as usize"),
+                });
+            }
+            None => unreachable!("Fields with a variable repeat cannot be followed"),
+        }
+        offset
+    }
+
+    // The largest offset a bit spec can have, i.e. when all repeats it follows are at their limit
+    fn max_offset(&self, bit_spec: &BitSpec) -> usize {
+        self.followed_bit_specs(bit_spec)
+            .iter()
+            .map(|followed| followed.start.index + followed.max_size())
+            .sum()
+    }
+
+    // How many fields are followed before reaching a field with an absolute position
+    fn follows_depth(&self, bit_spec: &BitSpec) -> usize {
+        self.followed_bit_specs(bit_spec).len()
+    }
+
+    /// The bit specs of the fields that a bit spec follows, starting with the one it
+    /// directly follows. Stops at an unknown symbol or when a bit spec is repeated, so
+    /// that fields following each other in a cycle can be reported.
+    pub fn followed_bit_specs(&self, bit_spec: &BitSpec) -> Vec<&BitSpec> {
+        let mut followed: Vec<&BitSpec> = Vec::new();
+        let mut symbol = bit_spec.follows.as_ref();
+        while let Some((_, field)) = symbol.and_then(|symbol| self.find_field_by_symbol(symbol)) {
+            let followed_bit_spec = field.bit_spec();
+            let repeated = followed.iter().any(|b| std::ptr::eq(*b, followed_bit_spec));
+            followed.push(followed_bit_spec);
+            if repeated {
+                break;
+            }
+            symbol = followed_bit_spec.follows.as_ref();
+        }
+        followed
+    }
+}
+
+/// The position of a word, made up of a number of words known when generating
+/// and the words taken by dependent repeats, which are only known at run time.
+#[derive(Debug, Default, PartialEq)]
+pub struct Offset {
+    words: usize,
+    counts: Vec<String>,
+}
+
+impl Offset {
+    /// The expression for the index of the word `index` words on from the offset.
+    pub fn index(&self, index: usize) -> String {
+        let words = self.words + index;
+        match (words, self.counts.is_empty()) {
+            (_, true) => words.to_string(),
+            (0, false) => self.counts.join(" + "),
+            (_, false) => format!("{words} + {}", self.counts.join(" + ")),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -237,6 +464,7 @@ mod tests {
 
     use super::*;
 
+    use crate::definition::Endian;
     use bit_lang::parse;
     #[test]
     fn test_find_field_by_bitspec() {
@@ -248,31 +476,49 @@ mod tests {
             bit_spec: bit_spec0,
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field1 = Field::BitField {
             bit_spec: bit_spec1.clone(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field2 = Field::BitField {
             bit_spec: bit_spec2,
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
-        let map = HashMap::from([
-            ("a_u8".to_string(), field0),
-            ("a_u16".to_string(), field1),
-            ("a_repeating_u16".to_string(), field2),
-        ]);
+        let mut members = Members::new();
 
-        let members = Members(map);
+        members.add("a_u8", field0);
+        members.add("a_u16", field1);
+        members.add("a_repeating_u16", field2);
 
         let expected_bit_spec = parse("2[]..3[]").unwrap();
         let expected_field = Field::BitField {
             bit_spec: expected_bit_spec,
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         assert_eq!(
             members.find_field_by_bitspec(&bit_spec1),
@@ -286,16 +532,31 @@ mod tests {
             bit_spec: parse("0[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field_b = Field::BitField {
             bit_spec: parse("1[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
         let field_c = Field::BitField {
             bit_spec: parse("2[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let mut members = Members::new();
@@ -311,18 +572,37 @@ mod tests {
         assert_eq!(3, buf_size);
     }
 
+    #[test]
+    fn test_buffer_size_empty() {
+        let members = Members::new();
+
+        assert!(members.is_empty());
+        assert_eq!(members.buffer_size(&HashMap::new()), 0);
+        assert_eq!(members.generate_serialization_size_expression(), "0");
+    }
+
     #[test]
     fn test_buffer_size_discontinuous() {
         let field_a = Field::BitField {
             bit_spec: parse("0[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let field_c = Field::BitField {
             bit_spec: parse("3[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let mut members = Members::new();
@@ -343,18 +623,33 @@ mod tests {
             bit_spec: parse("0[0..4]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let field_b = Field::BitField {
             bit_spec: parse("0[5..7]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let field_c = Field::BitField {
             bit_spec: parse("1[]").unwrap(),
             target_type: None,
             description: None,
+            min: None,
+            max: None,
+            scale: None,
+            offset: None,
+            unit: None,
         };
 
         let mut members = Members::new();
@@ -369,4 +664,65 @@ mod tests {
 
         assert_eq!(2, buf_size);
     }
+
+    #[test]
+    fn test_follows() {
+        let mut members = Members::new();
+        for (name, bits) in [
+            ("count", "0[]"),
+            ("values", "samples:1[]..2[];(0[])<=4"),
+            ("extra_count", "(>samples)[]"),
+            ("extra", "(>extra_count)[];((>samples)[])<=3"),
+            ("crc", "(>extra)[]"),
+        ] {
+            members.add(name, Field::new_bitfield(bits, None).unwrap());
+        }
+
+        let crc = members.get("crc").unwrap().bit_spec();
+        assert_eq!(
+            members.offset(crc, "self.").index(0),
+            "2 + 2 * self.count as usize + self.extra_count as usize"
+        );
+        assert_eq!(
+            members.offset(crc, "").index(1),
+            "3 + 2 * count as usize + extra_count as usize"
+        );
+
+        let names: Vec<&str> = members
+            .sorted()
+            .into_iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["count", "values", "extra_count", "extra", "crc"]);
+
+        assert_eq!(members.buffer_size(&HashMap::new()), 14);
+    }
+
+    #[test]
+    fn test_count_checks() {
+        let mut members = Members::new();
+        for (name, bits) in [
+            ("count", "0[]"),
+            ("values", "1[]..2[];(count)<=4"),
+            ("crc", "(>values)[]"),
+        ] {
+            members.add(name, Field::new_bitfield(bits, None).unwrap());
+        }
+        let device = Device::new("test", 8, Endian::Little);
+
+        let mut tokens = Tokens::<Rust>::new();
+        members.generate_deserializations(&mut tokens, "TestResponse", &device);
+        let deserialization = tokens.to_string().unwrap();
+        let check = "if count as usize > 4 { return Err(DeviceError::OutOfRange); }";
+        let check_position = deserialization.find(check).unwrap();
+        assert!(deserialization.find("let count").unwrap() < check_position);
+        assert!(check_position < deserialization.find("let values").unwrap());
+
+        let mut tokens = Tokens::<Rust>::new();
+        members.generate_serializations(&mut tokens, "TestRequest", &HashMap::new(), &device);
+        assert!(tokens
+            .to_string()
+            .unwrap()
+            .contains("if self.count as usize > 4 { return Err(DeviceError::OutOfRange); }"));
+    }
 }
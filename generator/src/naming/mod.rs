@@ -4,10 +4,12 @@
 
 mod command_name;
 mod common_structure_name;
+mod register_name;
 mod request_struct_name;
 mod response_struct_name;
 
 pub use command_name::CommandName;
 pub use common_structure_name::CommonStructureName;
+pub use register_name::RegisterName;
 pub use request_struct_name::RequestStructName;
 pub use response_struct_name::ResponseStructName;
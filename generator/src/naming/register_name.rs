@@ -0,0 +1,66 @@
+use genco::prelude::*;
+
+use convert_case::{Case, Casing};
+use std::fmt::Display;
+
+#[derive(Clone)]
+pub struct RegisterName(String);
+
+impl RegisterName {
+    pub fn to_file_name(&self) -> String {
+        let name = self.0.to_case(Case::Snake);
+        format!("{name}.rs")
+    }
+}
+
+impl From<String> for RegisterName {
+    fn from(name: String) -> Self {
+        let formatted_name = name.to_case(Case::UpperCamel);
+        RegisterName(formatted_name)
+    }
+}
+
+impl From<&str> for RegisterName {
+    fn from(name: &str) -> Self {
+        RegisterName::from(name.to_string())
+    }
+}
+
+impl Display for RegisterName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FormatInto<Rust> for RegisterName {
+    fn format_into(self, tokens: &mut Tokens<Rust>) {
+        quote_in!(*tokens => $(self.to_string()));
+    }
+}
+
+impl FormatInto<Rust> for &RegisterName {
+    fn format_into(self, tokens: &mut Tokens<Rust>) {
+        quote_in!(*tokens => $(self.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_register_name_str() {
+        let register_name = RegisterName::from("gpio_a");
+
+        let s = register_name.to_string();
+
+        assert_eq!(s, "GpioA");
+    }
+
+    #[test]
+    fn test_file_name() {
+        let register_name = RegisterName::from("IODIRA");
+        assert_eq!("iodira.rs", register_name.to_file_name());
+    }
+}
@@ -4,12 +4,13 @@ use genco::prelude::*;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 
+use crate::derives::optional_derives;
 use crate::doc_comment::DocComment;
 use crate::output::output_file;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-use crate::definition::Definition;
+use crate::definition::{Definition, Device};
 
 #[derive(Debug, Default)]
 pub(crate) struct Providers(Vec<String>);
@@ -32,7 +33,7 @@ impl Providers {
         self.0.len()
     }
 
-    pub(crate) fn generate(&self, out_path: &Path) -> anyhow::Result<()> {
+    pub(crate) fn generate(&self, out_path: &Path, device: &Device) -> anyhow::Result<()> {
         println!("Generating providers ...");
 
         for provider in self.0.iter() {
@@ -52,10 +53,11 @@ impl Providers {
                 $(generated_doc_comment)
 
                 #[derive(Debug, PartialEq, Copy, Clone, Default)]
+                $(optional_derives())
                 pub struct $(provider.to_case(Case::UpperCamel)) {}
 
                 impl Iterator for $(provider.to_case(Case::UpperCamel)) {
-                    type Item = u8;
+                    type Item = $(device.word_type());
 
                     fn next(&mut self) -> Option<Self::Item> {
                         todo!()
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Context;
+use convert_case::{Case, Casing};
+use genco::prelude::*;
+use serde::Deserialize;
+
+use crate::access::Access;
+use crate::bus::{
+    generate_bus_imports, generate_transmit_impls, generate_transmit_imports, BusParameters,
+    SendMode,
+};
+use crate::common_structure::CommonStructure;
+use crate::definition::Device;
+use crate::derives::optional_derives;
+use crate::doc_comment::DocComment;
+use crate::members::Members;
+use crate::naming::RegisterName;
+use crate::output::output_file;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A register of a register based device. Unlike a command, a register has only one
+/// field layout that is used for both reading and writing.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Register {
+    address: u8,
+
+    description: Option<String>,
+
+    #[serde(default)]
+    access: Access,
+
+    fields: Members,
+}
+
+impl Register {
+    pub fn generate_register(
+        &self,
+        register_name: &RegisterName,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+        out_path: &Path,
+    ) -> anyhow::Result<()> {
+        println!("Generating register file for {register_name}");
+
+        let register_file_name = register_name.to_file_name();
+        let target_path = out_path.join(register_file_name.clone());
+
+        let file = File::create(target_path)
+            .with_context(|| format!("Cannot open output file {}", register_file_name))?;
+
+        let mut tokens = rust::Tokens::new();
+
+        let register_doc_comment =
+            DocComment::from_string(&format!("Register {}", register_name)).as_string();
+        let description_doc_comment =
+            DocComment::from_string(self.description.as_ref().unwrap_or(&String::new()))
+                .as_string();
+        let generated_doc_comment =
+            DocComment::from_string(&format!("Generated with version {} of ddgen", VERSION))
+                .as_string();
+
+        quote_in!(tokens =>
+            #![allow(unused_imports)]$['\n']
+            #![allow(clippy::unnecessary_cast)]$['\n']
+            $(register_doc_comment)$['\r']
+
+            $(description_doc_comment)$['\r']
+
+            $(generated_doc_comment)$['\n']
+
+            $(ref toks => generate_bus_imports(toks, device))
+
+            use crate::command::Command;
+            use crate::deserialize::Deserialize;
+            use crate::error::DeviceError;
+            $(if self.fields.has_ranged() => use crate::ranged::Ranged;)
+            use crate::register::{RegisterRead, RegisterWritten};
+
+            use crate::request::{RequestArray, RequestBit, RequestField, RequestSpanningField, RequestWord, RequestStruct};
+            use crate::response::{ResponseArray, ResponseBit, ResponseField, ResponseSpanningField, ResponseWord};
+            $(if self.fields.has_scaled() => use crate::scaled;)
+            use crate::serialize::Serialize;
+            use crate::types::*;
+
+            $(ref toks => generate_transmit_imports(toks, device))
+
+            $(for name in common_structures.keys() => use crate::$(name.to_lowercase())::$(name.to_case(Case::UpperCamel));)
+
+            $['\n']
+            #[derive(Debug, PartialEq, Copy, Clone, Default)]$['\r']
+            $(optional_derives())
+            pub struct $register_name {$['\r']
+                $(ref toks => self.fields.generate_members(toks, device))$['\r']
+            }
+            $['\n']
+            impl $register_name {
+                pub const ADDRESS: u8 = $(format!("0x{:X}", self.address));
+            }
+            $['\n']
+
+            $(if self.access.is_writable() {
+                $(ref toks => self.fields.generate_serializations(toks, register_name, common_structures, device))$['\r']
+                $['\n']
+                impl Command for $register_name {
+                    fn opcode(&self) -> u8 {
+                        Self::ADDRESS
+                    }
+                }
+                $['\n']
+                $(ref toks => generate_transmit_impls(toks, register_name, "RegisterWritten", device))
+                $['\n']
+            })
+
+            $(if self.access.is_readable() {
+                $(ref toks => self.fields.generate_deserializations(toks, register_name, device))$['\r']
+                $['\n']
+            })
+
+            $(ref toks => self.generate_accessors(toks, register_name, common_structures, device))
+            $['\n']
+            $(ref toks => self.fields.generate_scaled_accessors(toks, register_name, device))
+        );
+
+        output_file(file, tokens)?;
+
+        Ok(())
+    }
+
+    // Generate `read()` for readable registers, `write()` for writable registers and
+    // `modify()` for registers that are both.
+    fn generate_accessors(
+        &self,
+        tokens: &mut Tokens<Rust>,
+        register_name: &RegisterName,
+        common_structures: &HashMap<String, CommonStructure>,
+        device: &Device,
+    ) {
+        let buf_size = self.fields.buffer_size(common_structures);
+        let bus = BusParameters::from(device);
+
+        for mode in SendMode::for_bus(device.bus) {
+            let await_suffix = mode.await_suffix();
+
+            quote_in!(*tokens =>
+                $(mode.cfg_attribute(device.bus))
+                impl $register_name {
+                    $(if self.access.is_readable() {
+                        $(DocComment::from_string("Reads the register from the device").as_string())
+                        pub $(if mode.is_async() => async) fn read<$(&bus.generic)>($(bus.argument)) -> Result<Self, DeviceError> {
+                            const BUF_LEN: usize = $buf_size;
+
+                            RegisterRead::new(Self::ADDRESS).transmit::<0, BUF_LEN>($(bus.transmit_args))$(await_suffix)
+                        }
+                        $['\n']
+                    })
+                    $(if self.access.is_writable() {
+                        $(DocComment::from_string("Writes the register to the device").as_string())
+                        pub $(if mode.is_async() => async) fn write<$(&bus.generic)>(&self, $(bus.argument)) -> Result<(), DeviceError> {
+                            const BUF_LEN: usize = $buf_size;
+
+                            self.transmit::<BUF_LEN, 0>($(bus.transmit_args))$(await_suffix)?;
+                            Ok(())
+                        }
+                        $['\n']
+                    })
+                    $(if self.access.is_readable() && self.access.is_writable() {
+                        $(DocComment::from_string("Reads the register, changes it with `f` and writes it back").as_string())
+                        pub $(if mode.is_async() => async) fn modify<$(&bus.generic), F: FnOnce(&mut Self)>($(bus.argument), f: F) -> Result<(), DeviceError> {
+                            let mut register = Self::read($(bus.name))$(await_suffix)?;
+                            f(&mut register);
+                            register.write($(bus.name))$(await_suffix)
+                        }
+                    })
+                }
+                $['\n']
+            );
+        }
+    }
+
+    pub fn members(&self) -> &Members {
+        &self.fields
+    }
+}
@@ -0,0 +1,1658 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use bit_lang::{BitRange, BitSpec, Repeat, RepeatCount, Word};
+
+use crate::command::Command;
+use crate::common_structure::CommonStructure;
+use crate::definition::{Bus, Definition, Device, Endian};
+use crate::error_reporting::Diagnostic;
+use crate::field::{Field, TargetType};
+use crate::flags::FlagSets;
+use crate::flow_control::{FlowControl, PollLimits};
+use crate::members::Members;
+use crate::Enumeration;
+
+/// Checks the definition before any code is generated, so that all the problems
+/// are reported together with where they are in the TOML file.
+/// The fields are rejected if they:
+/// - overlap another field,
+/// - use bit positions outside of the device word,
+/// - have a fixed repeat of zero,
+/// - are placed after a field with a variable or dependent repeat,
+/// - have a variable repeat, but no provider type, or are not in a request,
+/// - repeat bits or partial words rather than whole words,
+/// - have a dependent repeat where the count is not another field,
+/// - have a range that is incomplete, empty or does not fit into their bits,
+/// - are scaled, but are not integers, have no unit or have a scale of zero,
+/// - use a struct that does not exist, does not fit into the words given for it or
+///   is repeated a variable number of times.
+///
+/// Structs, commands and registers are rejected if the module generated for them has
+/// the same name as one of the modules of the generated crate, e.g. a command `POLL`.
+///
+/// Flags are rejected if they are empty, share their name with an enum or use bit
+/// positions outside of the device word, or outside of a field that holds them.
+///
+/// Polled flow control is rejected if the header struct does not exist, if its condition
+/// does not type check against the fields of the header, if its poll limits are zero
+/// or contradict each other or if the command has no response to read the header into.
+/// The error conditions of a command have to be conditions on its polled header, which
+/// also has to be part of the response. Those of a struct have to be conditions on its
+/// own fields, and the commands that poll on the struct and take them also need the
+/// struct in their response.
+pub fn validate(definition: &Definition) -> Vec<Diagnostic> {
+    let device = &definition.device;
+    let word_size = device.word_size;
+    let common_structures = &definition.common_structures;
+    let no_enumerations = HashMap::new();
+    let enumerations = definition.enumerations.as_ref().unwrap_or(&no_enumerations);
+
+    let mut diagnostics: Vec<Diagnostic> = definition
+        .device
+        .limitations()
+        .into_iter()
+        .map(|(limitation, span)| Diagnostic::new(format!("device: {limitation}"), span))
+        .collect();
+
+    diagnostics.extend(validate_flags(&definition.flags, enumerations, word_size));
+
+    for (section, names) in [
+        ("struct", common_structures.keys().collect::<Vec<_>>()),
+        ("commands", definition.commands.keys().collect()),
+        ("registers", definition.registers.keys().collect()),
+    ] {
+        diagnostics.extend(validate_module_names(
+            section,
+            names
+                .into_iter()
+                .map(|name| (name, definition.name_span(section, name))),
+        ));
+    }
+
+    for (name, structure) in sorted(common_structures) {
+        let key = format!("struct.{name}");
+        diagnostics.extend(validate_members(
+            &key,
+            structure.members(),
+            common_structures,
+            device,
+        ));
+        diagnostics.extend(validate_flag_fields(
+            &key,
+            structure.members(),
+            &definition.flags,
+            word_size,
+        ));
+        diagnostics.extend(validate_variable_repeats(&key, structure.members()));
+        diagnostics.extend(validate_struct_errors(
+            name,
+            structure,
+            enumerations,
+            &definition.flags,
+            device,
+        ));
+    }
+
+    for (name, command) in sorted(&definition.commands) {
+        for (members_name, members) in command.members() {
+            let key = format!("commands.{name}.{members_name}");
+            diagnostics.extend(validate_members(&key, members, common_structures, device));
+            diagnostics.extend(validate_flag_fields(
+                &key,
+                members,
+                &definition.flags,
+                word_size,
+            ));
+            if members_name == "response" {
+                diagnostics.extend(validate_variable_repeats(&key, members));
+            }
+        }
+
+        diagnostics.extend(validate_flow_control(
+            &format!("commands.{name}.flow_control"),
+            command,
+            common_structures,
+            enumerations,
+            &definition.flags,
+            device,
+        ));
+
+        diagnostics.extend(validate_errors(
+            &format!("commands.{name}.errors"),
+            command,
+            common_structures,
+            enumerations,
+            &definition.flags,
+            device,
+        ));
+    }
+
+    for (name, register) in sorted(&definition.registers) {
+        let key = format!("registers.{name}.fields");
+        diagnostics.extend(validate_members(
+            &key,
+            register.members(),
+            common_structures,
+            device,
+        ));
+        diagnostics.extend(validate_flag_fields(
+            &key,
+            register.members(),
+            &definition.flags,
+            word_size,
+        ));
+        diagnostics.extend(validate_variable_repeats(&key, register.members()));
+    }
+
+    diagnostics
+}
+
+fn validate_flow_control(
+    key: &str,
+    command: &Command,
+    common_structures: &HashMap<String, CommonStructure>,
+    enumerations: &HashMap<String, Enumeration>,
+    flags: &FlagSets,
+    device: &Device,
+) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let span = command.flow_control().span();
+    let mut error = |message: String| {
+        diagnostics.push(Diagnostic::new(
+            format!("{key}: {message}"),
+            Some(span.clone()),
+        ))
+    };
+
+    let (on, condition) = match command.flow_control().get_ref() {
+        FlowControl::Direct | FlowControl::Timed { .. } | FlowControl::ReadyPin { .. } => {
+            return diagnostics
+        }
+        FlowControl::MisoReady { max_retries, .. } => {
+            if device.bus != Bus::Spi {
+                error("miso_ready can only be used with spi devices".to_string());
+            }
+            if *max_retries == 0 {
+                error("max_retries should be at least 1".to_string());
+            }
+            return diagnostics;
+        }
+        FlowControl::Polled {
+            on,
+            condition,
+            limits,
+        } => {
+            validate_poll_limits(limits, &mut error);
+            (on, condition)
+        }
+        FlowControl::Chunked {
+            chunk_size,
+            on,
+            condition,
+            limits,
+        } => {
+            validate_poll_limits(limits, &mut error);
+            match command.chunk_field() {
+                None => error(
+                    "chunked commands need a request field with a variable repeat for the data"
+                        .to_string(),
+                ),
+                Some((field_name, field)) => {
+                    let max_words = max_words(field.bit_spec());
+                    if *chunk_size == 0 || *chunk_size > max_words {
+                        error(format!(
+                            "chunk_size should be between 1 and the {max_words} words of {field_name}"
+                        ));
+                    }
+                }
+            }
+            (on, condition)
+        }
+    };
+
+    if !command.has_response() {
+        error(format!(
+            "polled commands need a response, as the {on} struct is read into it"
+        ));
+    }
+    match common_structures.get(on) {
+        Some(structure) => {
+            for message in condition.check(on, structure.members(), enumerations, flags, device) {
+                error(message);
+            }
+        }
+        None => error(format!("polls on unknown struct {on}")),
+    }
+
+    diagnostics
+}
+
+fn validate_errors(
+    key: &str,
+    command: &Command,
+    common_structures: &HashMap<String, CommonStructure>,
+    enumerations: &HashMap<String, Enumeration>,
+    flags: &FlagSets,
+    device: &Device,
+) -> Vec<Diagnostic> {
+    let Some(errors) = command.errors() else {
+        return validate_inherited_errors(key, command, common_structures);
+    };
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut error = |message: String| {
+        diagnostics.push(Diagnostic::new(
+            format!("{key}: {message}"),
+            Some(errors.span()),
+        ))
+    };
+
+    let on = match command.flow_control().get_ref() {
+        FlowControl::Polled { on, .. } | FlowControl::Chunked { on, .. } => on,
+        _ => {
+            error("errors can only be used with a polled or chunked flow control".to_string());
+            return diagnostics;
+        }
+    };
+
+    if errors.get_ref().is_empty() {
+        error("errors should have at least one condition".to_string());
+    }
+    if command.header_field(on).is_none() {
+        error(format!("errors need a response field with struct {on}"));
+    }
+    // An unknown struct is reported for the flow control
+    if let Some(structure) = common_structures.get(on) {
+        for condition in errors.get_ref() {
+            for message in condition.check(on, structure.members(), enumerations, flags, device) {
+                error(message);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+// A command without errors of its own takes those of the struct it polls on, which are
+// checked with the struct. The command still needs a response field to hold the header.
+fn validate_inherited_errors(
+    key: &str,
+    command: &Command,
+    common_structures: &HashMap<String, CommonStructure>,
+) -> Vec<Diagnostic> {
+    let (FlowControl::Polled { on, .. } | FlowControl::Chunked { on, .. }) =
+        command.flow_control().get_ref()
+    else {
+        return Vec::new();
+    };
+    if command.error_conditions(common_structures).is_none() || command.header_field(on).is_some() {
+        return Vec::new();
+    }
+
+    vec![Diagnostic::new(
+        format!("{key}: the errors of struct.{on} need a response field with struct {on}"),
+        Some(command.flow_control().span()),
+    )]
+}
+
+// The errors of a struct are conditions on its own fields
+fn validate_struct_errors(
+    name: &str,
+    structure: &CommonStructure,
+    enumerations: &HashMap<String, Enumeration>,
+    flags: &FlagSets,
+    device: &Device,
+) -> Vec<Diagnostic> {
+    let Some(errors) = structure.errors() else {
+        return Vec::new();
+    };
+    let mut messages = Vec::new();
+    if errors.get_ref().is_empty() {
+        messages.push("errors should have at least one condition".to_string());
+    }
+    for condition in errors.get_ref() {
+        messages.extend(condition.check(name, structure.members(), enumerations, flags, device));
+    }
+
+    messages
+        .into_iter()
+        .map(|message| {
+            Diagnostic::new(
+                format!("struct.{name}.errors: {message}"),
+                Some(errors.span()),
+            )
+        })
+        .collect()
+}
+
+fn validate_poll_limits(limits: &PollLimits, error: &mut impl FnMut(String)) {
+    if limits.max_polls.is_some() && limits.timeout_us.is_some() {
+        error("use either max_polls or timeout_us, not both".to_string());
+    }
+    if limits.poll_interval_us.is_some() && limits.timeout_us.is_none() {
+        error("poll_interval_us can only be used with a timeout_us".to_string());
+    }
+    if limits.max_polls == Some(0) {
+        error("max_polls should be at least 1".to_string());
+    }
+    if limits.poll_interval_us == Some(0) {
+        error("poll_interval_us should be at least 1".to_string());
+    }
+}
+
+fn validate_flags(
+    flags: &FlagSets,
+    enumerations: &HashMap<String, Enumeration>,
+    word_size: u8,
+) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    let mut flag_sets: Vec<_> = flags.iter().collect();
+    flag_sets.sort_by_key(|(name, _)| name.get_ref());
+
+    for (key, flag_set) in flag_sets {
+        let name = key.get_ref();
+        let mut error =
+            |message: String| diagnostics.push(Diagnostic::new(message, Some(key.span())));
+        if flag_set.is_empty() {
+            error(format!("flags.{name}: should have at least one flag"));
+        }
+        if enumerations.contains_key(name) {
+            error(format!("flags.{name}: is also the name of an enum"));
+        }
+        for (flag, position) in flag_set.sorted() {
+            if position >= word_size {
+                diagnostics.push(Diagnostic::new(
+                    format!(
+                        "flags.{name}.{flag}: uses bit {position}, but words only have {word_size} bits"
+                    ),
+                    flag_set.span(flag),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+// Flags are held in a single word, so can only be the type of fields within one word
+// that have enough bits for all the flags
+fn validate_flag_fields(
+    key: &str,
+    members: &Members,
+    flags: &FlagSets,
+    word_size: u8,
+) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    let mut fields: Vec<(&String, &Field)> = members.to_vec();
+    fields.sort_by(|(name_a, field_a), (name_b, field_b)| {
+        field_a.cmp(field_b).then(name_a.cmp(name_b))
+    });
+
+    for (name, field) in fields {
+        let Field::BitField {
+            bit_spec,
+            target_type: Some(TargetType::TypeName(type_name)),
+            ..
+        } = field
+        else {
+            continue;
+        };
+        let Some(flag_set) = flags.get(type_name.as_str()) else {
+            continue;
+        };
+
+        let bits = match (&bit_spec.start.bit_range, &bit_spec.end, &bit_spec.repeat) {
+            (BitRange::Range(start, end), None, Repeat::None) => Some(end - start + 1),
+            (BitRange::WholeWord, None, Repeat::None) => Some(word_size as u16),
+            _ => None,
+        };
+        let message = match (bits, flag_set.highest_position()) {
+            (None, _) => Some(format!(
+                "flags {type_name} can only be held by a field within a single word that is not repeated"
+            )),
+            (Some(bits), Some(position)) if position as u16 >= bits => Some(format!(
+                "flags {type_name} use bit {position}, but the field only has {bits} bits"
+            )),
+            _ => None,
+        };
+        if let Some(message) = message {
+            diagnostics.push(Diagnostic::new(
+                format!("{key}.{name}: {message}"),
+                members.span(name),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+// The words of a variable repeat come from a provider when a request is serialized.
+// They cannot be deserialized, so are only allowed in requests.
+fn validate_variable_repeats(key: &str, members: &Members) -> Vec<Diagnostic> {
+    let mut names: Vec<&String> = members
+        .iter()
+        .filter(|(_, field)| matches!(field.bit_spec().repeat, Repeat::Variable { .. }))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            Diagnostic::new(
+                format!("{key}.{name}: has a variable repeat, which can only be used in a request"),
+                members.span(name),
+            )
+        })
+        .collect()
+}
+
+// Each struct, command and register has a module named after it, which cannot
+// be one of the modules the generated crate already has. The names are given with
+// the spans of their keys in the definition.
+fn validate_module_names<'a>(
+    section: &str,
+    names: impl Iterator<Item = (&'a String, Option<Range<usize>>)>,
+) -> Vec<Diagnostic> {
+    let mut names: Vec<(&String, Option<Range<usize>>)> = names.collect();
+    names.sort_by_key(|(name, _)| *name);
+    names
+        .into_iter()
+        .filter(|(name, _)| Definition::RUNTIME_MODULES.contains(&name.to_lowercase().as_str()))
+        .map(|(name, span)| {
+            Diagnostic::new(
+                format!(
+                    "{section}.{name}: is generated as the module {}, which the generated crate already has",
+                    name.to_lowercase()
+                ),
+                span,
+            )
+        })
+        .collect()
+}
+
+// Sorted so that the error messages are always in the same order
+fn sorted<T>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+    entries
+}
+
+fn validate_members(
+    key: &str,
+    members: &Members,
+    common_structures: &HashMap<String, CommonStructure>,
+    device: &Device,
+) -> Vec<Diagnostic> {
+    let word_size = device.word_size;
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut error = |name: &str, message: String| {
+        diagnostics.push(Diagnostic::new(
+            format!("{key}.{name}: {message}"),
+            members.span(name),
+        ))
+    };
+
+    let mut fields: Vec<(&String, &Field)> = members.to_vec();
+    fields.sort_by(|(name_a, field_a), (name_b, field_b)| {
+        field_a.cmp(field_b).then(name_a.cmp(name_b))
+    });
+
+    for (name, field) in &fields {
+        let bit_spec = field.bit_spec();
+
+        if let Some(position) = bit_spec.highest_bit_position() {
+            if position >= word_size as u16 {
+                error(
+                    name,
+                    format!("uses bit {position}, but words only have {word_size} bits"),
+                );
+            }
+        }
+
+        if let (Some(end), Some(bits)) = (&bit_spec.end, device.word_range_bit_count(bit_spec)) {
+            // The first word holds the least significant bits for little endian devices
+            // and the most significant bits for big endian devices, so the bits have to
+            // continue from it into the next word at its last bit or at bit 0.
+            let last_bit = word_size as u16 - 1;
+            let (gap, first_word, last_word) = match device.endian {
+                Endian::Little => (
+                    matches!(bit_spec.start.bit_range, BitRange::Range(_, high) if high != last_bit)
+                        || matches!(end.bit_range, BitRange::Range(low, _) if low != 0),
+                    format!("up to bit {last_bit}"),
+                    "from bit 0".to_string(),
+                ),
+                Endian::Big => (
+                    matches!(bit_spec.start.bit_range, BitRange::Range(low, _) if low != 0)
+                        || matches!(end.bit_range, BitRange::Range(_, high) if high != last_bit),
+                    "from bit 0".to_string(),
+                    format!("up to bit {last_bit}"),
+                ),
+            };
+            if gap {
+                error(
+                    name,
+                    format!("has a gap in its word range, as the bits have to continue {first_word} of its first word and {last_word} of its last word"),
+                );
+            } else if bits > 64 && bit_spec.spans_partial_words() {
+                error(
+                    name,
+                    format!("spans {bits} bits, but a word range that does not start and end with whole words can only span up to 64 bits"),
+                );
+            }
+        }
+
+        if let (
+            BitRange::Single(_) | BitRange::Range(..),
+            Repeat::Fixed { .. } | Repeat::Dependent { .. },
+        ) = (&bit_spec.start.bit_range, &bit_spec.repeat)
+        {
+            error(
+                name,
+                "is repeated, but only whole words can be repeated".to_string(),
+            );
+        }
+
+        match &bit_spec.repeat {
+            Repeat::Fixed { number: 0 } => error(name, "repeats zero times".to_string()),
+            Repeat::Variable { .. } if field.provider().is_none() => error(
+                name,
+                "has a variable repeat, but no provider type is given".to_string(),
+            ),
+            Repeat::Dependent { count, .. } if members.find_count_field(count).is_none() => {
+                match count {
+                    RepeatCount::BitSpec(_) => error(
+                        name,
+                        format!("is repeated by {count}, which is not a field"),
+                    ),
+                    RepeatCount::Symbol(_) => error(
+                        name,
+                        format!("is repeated by {count}, which is not a field or label"),
+                    ),
+                }
+            }
+            _ => (),
+        }
+
+        if let Field::BitField {
+            min,
+            max,
+            scale,
+            offset,
+            unit,
+            ..
+        } = field
+        {
+            match (min, max) {
+                (None, None) => (),
+                (Some(min), Some(max)) => {
+                    if min > max {
+                        error(
+                            name,
+                            format!("has a min of {min}, which is greater than its max of {max}"),
+                        );
+                    } else if let Some((lowest, highest)) = field.value_limits(device) {
+                        if (*min as i128) < lowest || (*max as i128) > highest {
+                            error(name, format!("has a range of {min}..={max}, but can only hold {lowest}..={highest}"));
+                        }
+                    } else {
+                        error(
+                            name,
+                            "can only have a range if it is an integer that is not repeated"
+                                .to_string(),
+                        );
+                    }
+                }
+                _ => error(name, "needs both a min and a max".to_string()),
+            }
+
+            if scale.is_some() || offset.is_some() || unit.is_some() {
+                if field.value_limits(device).is_none() {
+                    error(
+                        name,
+                        "can only have a scale, offset or unit if it is an integer that is not repeated"
+                            .to_string(),
+                    );
+                }
+                match unit {
+                    None => error(name, "needs a unit, as it has a scale or offset".to_string()),
+                    Some(unit)
+                        if unit.is_empty()
+                            || !unit.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+                    {
+                        error(
+                            name,
+                            format!("has the unit \"{unit}\", which is part of the accessor names, so can only contain ASCII letters, digits and underscores"),
+                        )
+                    }
+                    Some(_) => (),
+                }
+                match scale {
+                    Some(scale) if *scale == 0.0 || !scale.is_finite() => error(
+                        name,
+                        format!(
+                            "has a scale of {scale}, which has to be a finite number other than 0"
+                        ),
+                    ),
+                    _ => (),
+                }
+                match offset {
+                    Some(offset) if !offset.is_finite() => error(
+                        name,
+                        format!("has an offset of {offset}, which has to be a finite number"),
+                    ),
+                    _ => (),
+                }
+            }
+        }
+
+        if let Field::Structure {
+            common_structure_name,
+            ..
+        } = field
+        {
+            match common_structures.get(common_structure_name) {
+                Some(structure) if structure.buffer_size() > declared_words(bit_spec) => error(
+                    name,
+                    format!(
+                        "struct {common_structure_name} needs {} words, but only {} are given",
+                        structure.buffer_size(),
+                        declared_words(bit_spec)
+                    ),
+                ),
+                Some(_) => (),
+                None => error(name, format!("uses unknown struct {common_structure_name}")),
+            }
+
+            if matches!(
+                bit_spec.repeat,
+                Repeat::Dependent { .. } | Repeat::Variable { .. }
+            ) {
+                error(
+                    name,
+                    format!("struct {common_structure_name} can only have a fixed repeat"),
+                );
+            }
+
+            if bit_spec.follows.is_some() {
+                error(
+                    name,
+                    format!("struct {common_structure_name} cannot follow another field"),
+                );
+            }
+        }
+
+        if let Some(symbol) = &bit_spec.follows {
+            match members.find_field_by_symbol(symbol) {
+                None => error(
+                    name,
+                    format!("follows {symbol}, which is not a field or label"),
+                ),
+                Some(_)
+                    if members
+                        .followed_bit_specs(bit_spec)
+                        .iter()
+                        .any(|followed| std::ptr::eq(*followed, bit_spec)) =>
+                {
+                    error(
+                        name,
+                        format!("follows {symbol}, which leads back to {name}"),
+                    )
+                }
+                Some((followed_name, followed)) => {
+                    if let Repeat::Variable { .. } = followed.bit_spec().repeat {
+                        error(
+                            name,
+                            format!("follows {followed_name}, which has a variable repeat"),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(label) = &bit_spec.label {
+            if let Some((other_name, _)) = fields.iter().find(|(other_name, other_field)| {
+                other_name < name && other_field.bit_spec().label.as_ref() == Some(label)
+            }) {
+                error(
+                    name,
+                    format!("has the label {label}, which is already used by {other_name}"),
+                );
+            }
+        }
+    }
+
+    // Fields with a variable number of words must be the last, as otherwise
+    // the position of the fields after them is unknown. Fields that follow
+    // them are placed from their end instead, so only fields positioned from
+    // the same place are compared.
+    for (name, field) in &fields {
+        let bit_spec = field.bit_spec();
+        if matches!(
+            bit_spec.repeat,
+            Repeat::Dependent { .. } | Repeat::Variable { .. }
+        ) {
+            let last_word = bit_spec.end.as_ref().unwrap_or(&bit_spec.start).index;
+            for (other_name, other_field) in &fields {
+                if other_name != name
+                    && other_field.bit_spec().follows == bit_spec.follows
+                    && other_field.bit_spec().start.index > last_word
+                {
+                    error(
+                        other_name,
+                        format!("is placed after {name}, which has a variable number of words"),
+                    );
+                }
+            }
+        }
+    }
+
+    let occupied: Vec<BTreeSet<(usize, u16)>> = fields
+        .iter()
+        .map(|(_, field)| occupied_bits(field, common_structures, device))
+        .collect();
+
+    for (i, (name, field)) in fields.iter().enumerate() {
+        for (j, (other_name, other_field)) in fields.iter().enumerate().take(i) {
+            if field.bit_spec().follows != other_field.bit_spec().follows {
+                continue;
+            }
+            if let Some((word, bit)) = occupied[i].intersection(&occupied[j]).next() {
+                error(
+                    name,
+                    format!("overlaps {other_name} at bit {bit} of word {word}"),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+// The number of words spanned by one element of a bit spec
+fn element_words(bit_spec: &BitSpec) -> usize {
+    let end = bit_spec.end.as_ref().unwrap_or(&bit_spec.start).index;
+    end.saturating_sub(bit_spec.start.index) + 1
+}
+
+// The largest number of words a variable repeat can have
+fn max_words(bit_spec: &BitSpec) -> usize {
+    match bit_spec.repeat {
+        Repeat::Variable { limit } => element_words(bit_spec) * limit,
+        _ => element_words(bit_spec),
+    }
+}
+
+// The number of words given to a bit spec, including any fixed repeats
+fn declared_words(bit_spec: &BitSpec) -> usize {
+    match bit_spec.repeat {
+        Repeat::Fixed { number } => element_words(bit_spec) * number,
+        _ => element_words(bit_spec),
+    }
+}
+
+// The bits, as (word index, bit position) pairs, used by a field. For variable and
+// dependent repeats only the first element is known.
+fn occupied_bits(
+    field: &Field,
+    common_structures: &HashMap<String, CommonStructure>,
+    device: &Device,
+) -> BTreeSet<(usize, u16)> {
+    let word_size = device.word_size;
+    let whole_word = |index: usize| (0..word_size as u16).map(move |bit| (index, bit));
+    let word_bits = |word: &Word| -> Vec<(usize, u16)> {
+        match word.bit_range {
+            BitRange::Single(position) => vec![(word.index, position)],
+            BitRange::Range(start, end) => (start..=end).map(|bit| (word.index, bit)).collect(),
+            BitRange::WholeWord | BitRange::Literal(_) => whole_word(word.index).collect(),
+        }
+    };
+
+    let bit_spec = field.bit_spec();
+
+    if let Field::Structure {
+        common_structure_name,
+        ..
+    } = field
+    {
+        let size = common_structures
+            .get(common_structure_name)
+            .map_or(declared_words(bit_spec), |structure| {
+                structure.buffer_size().max(declared_words(bit_spec))
+            });
+        let start = bit_spec.start.index;
+        return (start..start + size).flat_map(whole_word).collect();
+    }
+
+    let start = &bit_spec.start;
+    let element: Vec<(usize, u16)> = match (&bit_spec.end, device.word_range_bits(bit_spec)) {
+        // For little endian devices the first word is used from its bit given and the last
+        // word up to its bit given. It is the other way around for big endian devices.
+        (Some(end), Some((start_bit, end_bit))) if end.index > start.index => {
+            let (first_bits, last_bits) = match device.endian {
+                Endian::Little => (start_bit..=word_size as u16 - 1, 0..=end_bit),
+                Endian::Big => (0..=start_bit, end_bit..=word_size as u16 - 1),
+            };
+            first_bits
+                .map(|bit| (start.index, bit))
+                .chain((start.index + 1..end.index).flat_map(whole_word))
+                .chain(last_bits.map(|bit| (end.index, bit)))
+                .collect()
+        }
+        (Some(end), _) => word_bits(start)
+            .into_iter()
+            .chain((start.index + 1..end.index).flat_map(whole_word))
+            .chain(word_bits(end))
+            .collect(),
+        (None, _) => word_bits(start),
+    };
+
+    let repeats = match bit_spec.repeat {
+        Repeat::Fixed { number } => number,
+        _ => 1,
+    };
+    let element_words = element_words(bit_spec);
+
+    (0..repeats)
+        .flat_map(|repeat| {
+            element
+                .iter()
+                .map(move |(index, bit)| (index + repeat * element_words, *bit))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::Device;
+
+    fn le_device() -> Device {
+        Device::new("test", 8, Endian::Little)
+    }
+
+    fn errors(fields: &[(&str, &str)]) -> Vec<String> {
+        device_errors(fields, &le_device())
+    }
+
+    fn device_errors(fields: &[(&str, &str)], device: &Device) -> Vec<String> {
+        let mut members = Members::new();
+        for (name, bits) in fields {
+            members.add(name, Field::new_bitfield(bits, None).unwrap());
+        }
+
+        validate_members("commands.TEST.request", &members, &HashMap::new(), device)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_layout() {
+        let fields = [
+            ("a_bit", "0[0]"),
+            ("a_field", "0[1..7]"),
+            ("a_u16", "1[]..2[]"),
+            ("a_count", "3[]"),
+            ("a_repeat", "4[]..5[];(3[])<=4"),
+        ];
+
+        assert!(errors(&fields).is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_fields() {
+        let fields = [("a_field", "0[0..4]"), ("b_field", "0[4..7]"), ("c", "1[]")];
+
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.b_field: overlaps a_field at bit 4 of word 0"]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_repeat() {
+        let fields = [("a_repeat", "0[];3"), ("a_u8", "2[]")];
+
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.a_u8: overlaps a_repeat at bit 0 of word 2"]
+        );
+    }
+
+    #[test]
+    fn test_bit_positions() {
+        let fields = [("a_bit", "0[15]"), ("a_field", "1[4..11]")];
+
+        assert_eq!(
+            errors(&fields),
+            vec![
+                "commands.TEST.request.a_bit: uses bit 15, but words only have 8 bits",
+                "commands.TEST.request.a_field: uses bit 11, but words only have 8 bits"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spanning_fields() {
+        let fields = [
+            ("a_flag", "0[3]"),
+            ("a_u12", "0[4]..1[7]"),
+            ("a_u20", "2[]..4[3]"),
+            ("b_flag", "4[4]"),
+        ];
+        assert!(errors(&fields).is_empty());
+
+        let fields = [
+            ("a_u12", "0[4]..1[7]"),
+            ("a_flag", "0[5]"),
+            ("b_flag", "1[2]"),
+        ];
+        assert_eq!(
+            errors(&fields),
+            vec![
+                "commands.TEST.request.a_flag: overlaps a_u12 at bit 5 of word 0",
+                "commands.TEST.request.b_flag: overlaps a_u12 at bit 2 of word 1"
+            ]
+        );
+
+        let fields = [("a_field", "0[4..6]..1[7]"), ("b_field", "2[4]..3[2..7]")];
+        assert_eq!(
+            errors(&fields),
+            vec![
+                "commands.TEST.request.a_field: has a gap in its word range, as the bits have to continue up to bit 7 of its first word and from bit 0 of its last word",
+                "commands.TEST.request.b_field: has a gap in its word range, as the bits have to continue up to bit 7 of its first word and from bit 0 of its last word"
+            ]
+        );
+
+        let fields = [("a_field", "0[4]..9[7]")];
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.a_field: spans 76 bits, but a word range that does not start and end with whole words can only span up to 64 bits"]
+        );
+    }
+
+    #[test]
+    fn test_big_endian_spanning_fields() {
+        let device = Device::new("test", 8, Endian::Big);
+
+        // The first word holds the most significant bits from bit 0
+        let fields = [
+            ("a_u12", "0[0..3]..1[]"),
+            ("a_flag", "0[4]"),
+            ("a_u20", "2[]..4[4..7]"),
+            ("b_flag", "4[3]"),
+        ];
+        assert!(device_errors(&fields, &device).is_empty());
+
+        let fields = [("a_field", "0[4..7]..1[]"), ("b_field", "2[]..3[0..3]")];
+        assert_eq!(
+            device_errors(&fields, &device),
+            vec![
+                "commands.TEST.request.a_field: has a gap in its word range, as the bits have to continue from bit 0 of its first word and up to bit 7 of its last word",
+                "commands.TEST.request.b_field: has a gap in its word range, as the bits have to continue from bit 0 of its first word and up to bit 7 of its last word"
+            ]
+        );
+
+        let fields = [("a_u12", "0[3]..1[]"), ("a_flag", "0[2]")];
+        assert_eq!(
+            device_errors(&fields, &device),
+            vec!["commands.TEST.request.a_u12: overlaps a_flag at bit 2 of word 0"]
+        );
+    }
+
+    #[test]
+    fn test_zero_repeat() {
+        let fields = [("a_repeat", "0[];0")];
+
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.a_repeat: repeats zero times"]
+        );
+    }
+
+    #[test]
+    fn test_repeated_bits() {
+        let fields = [
+            ("a_count", "0[]"),
+            ("some_words", "1[];2"),
+            ("some_bits", "3[0..3];2"),
+            ("a_bit", "5[0];(a_count)<=4"),
+        ];
+
+        assert_eq!(
+            errors(&fields),
+            vec![
+                "commands.TEST.request.some_bits: is repeated, but only whole words can be repeated",
+                "commands.TEST.request.a_bit: is repeated, but only whole words can be repeated"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variable_repeats_outside_requests() {
+        let mut members = Members::new();
+        members.add("a_u8", Field::new_bitfield("0[]", None).unwrap());
+        members.add(
+            "data",
+            Field::new_bitfield("1[];<=4", Some("DataProvider")).unwrap(),
+        );
+
+        let errors: Vec<String> = validate_variable_repeats("commands.TEST.response", &members)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec!["commands.TEST.response.data: has a variable repeat, which can only be used in a request"]
+        );
+    }
+
+    #[test]
+    fn test_device_limitations() {
+        let device_table = r#"
+            name = "test"
+            word_size = 12
+            endian = "le"
+            bus = "i2c"
+            address = 0x80
+        "#;
+        let device: Device = toml::from_str(device_table).unwrap();
+
+        let limitations: Vec<(&str, &str)> = device
+            .limitations()
+            .into_iter()
+            .map(|(limitation, span)| (limitation, &device_table[span.unwrap()]))
+            .collect();
+
+        assert_eq!(
+            limitations,
+            vec![
+                ("word_size should be 8, 16 or 32", "12"),
+                ("i2c address should be a 7 bit address", "0x80"),
+                ("i2c devices should have a word_size of 8", "12"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variable_repeat_not_last() {
+        let fields = [
+            ("a_count", "0[]"),
+            ("a_repeat", "1[];(0[])<=4"),
+            ("a_u8", "2[]"),
+        ];
+
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.a_u8: is placed after a_repeat, which has a variable number of words"]
+        );
+    }
+
+    #[test]
+    fn test_follows() {
+        let fields = [
+            ("a_count", "0[]"),
+            ("a_repeat", "1[];(0[])<=4"),
+            ("a_u8", "(>a_repeat)[]"),
+            ("a_bit", "(>a_u8)[7]"),
+        ];
+        assert!(errors(&fields).is_empty());
+
+        let fields = [
+            ("a_u8", "(>a_bit)[]"),
+            ("a_bit", "(>a_u8)[7]"),
+            ("a_missing", "(>nothing)[]"),
+            ("a_overlap", "(>a_u8)[0..7]"),
+            ("a_first", "first:0[]"),
+            ("a_second", "first:1[]"),
+        ];
+        assert_eq!(
+            errors(&fields),
+            vec![
+                "commands.TEST.request.a_bit: follows a_u8, which leads back to a_bit",
+                "commands.TEST.request.a_u8: follows a_bit, which leads back to a_u8",
+                "commands.TEST.request.a_missing: follows nothing, which is not a field or label",
+                "commands.TEST.request.a_second: has the label first, which is already used by a_first",
+                "commands.TEST.request.a_overlap: overlaps a_bit at bit 7 of word 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeat_counts() {
+        let mut members = Members::new();
+        members.add("a_count", Field::new_bitfield("0[]", None).unwrap());
+        members.add(
+            "a_dependent_repeat",
+            Field::new_bitfield("1[];(2[])<=4", None).unwrap(),
+        );
+        members.add(
+            "a_variable_repeat",
+            Field::new_bitfield("5[];<=8", None).unwrap(),
+        );
+
+        let errors: Vec<String> = validate_members(
+            "commands.TEST.request",
+            &members,
+            &HashMap::new(),
+            &le_device(),
+        )
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+
+        assert!(errors.contains(
+            &"commands.TEST.request.a_dependent_repeat: is repeated by 2[], which is not a field"
+                .to_string()
+        ));
+        assert!(errors.contains(&"commands.TEST.request.a_variable_repeat: has a variable repeat, but no provider type is given".to_string()));
+    }
+
+    #[test]
+    fn test_named_repeat_counts() {
+        let fields = [
+            ("a_count", "0[]"),
+            ("a_repeat", "1[];(a_count)<=4"),
+            ("b_repeat", "(>a_repeat)[];(b_count)<=4"),
+        ];
+        assert_eq!(
+            errors(&fields),
+            vec!["commands.TEST.request.b_repeat: is repeated by b_count, which is not a field or label"]
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let ranged = |bits: &str, target_type: Option<&str>, min, max| {
+            let mut field = Field::new_bitfield(bits, target_type).unwrap();
+            if let Field::BitField {
+                min: field_min,
+                max: field_max,
+                ..
+            } = &mut field
+            {
+                *field_min = min;
+                *field_max = max;
+            }
+            field
+        };
+
+        let mut members = Members::new();
+        members.add("tr_size", ranged("0[0..3]", None, Some(1), Some(15)));
+        members.add("ctun", ranged("1[]", Some("i8"), Some(-128), Some(63)));
+        members.add("a_u16", ranged("2[]..3[]", None, Some(0), Some(1000)));
+        members.add("a_u12", ranged("4[4]..5[7]", None, Some(0), Some(4095)));
+
+        assert!(validate_members(
+            "commands.TEST.request",
+            &members,
+            &HashMap::new(),
+            &le_device()
+        )
+        .is_empty());
+
+        let mut members = Members::new();
+        members.add("too_wide", ranged("0[0..3]", None, Some(1), Some(16)));
+        members.add("reversed", ranged("1[]", None, Some(10), Some(1)));
+        members.add("no_max", ranged("2[]", None, Some(1), None));
+        members.add("a_bit", ranged("3[0]", None, Some(0), Some(1)));
+        members.add("an_enum", ranged("4[0..3]", Some("mode"), Some(0), Some(1)));
+        members.add(
+            "an_i12",
+            ranged("5[4]..6[7]", Some("i16"), Some(-2049), Some(0)),
+        );
+
+        let errors: Vec<String> = validate_members(
+            "commands.TEST.request",
+            &members,
+            &HashMap::new(),
+            &le_device(),
+        )
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "commands.TEST.request.too_wide: has a range of 1..=16, but can only hold 0..=15",
+                "commands.TEST.request.reversed: has a min of 10, which is greater than its max of 1",
+                "commands.TEST.request.no_max: needs both a min and a max",
+                "commands.TEST.request.a_bit: can only have a range if it is an integer that is not repeated",
+                "commands.TEST.request.an_enum: can only have a range if it is an integer that is not repeated",
+                "commands.TEST.request.an_i12: has a range of -2049..=0, but can only hold -2048..=2047",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scaled_fields() {
+        let scaled = |bits: &str, target_type: Option<&str>, scale, offset, unit: Option<&str>| {
+            let mut field = Field::new_bitfield(bits, target_type).unwrap();
+            if let Field::BitField {
+                scale: field_scale,
+                offset: field_offset,
+                unit: field_unit,
+                ..
+            } = &mut field
+            {
+                *field_scale = scale;
+                *field_offset = offset;
+                *field_unit = unit.map(str::to_string);
+            }
+            field
+        };
+
+        let mut members = Members::new();
+        members.add(
+            "capacitance",
+            scaled("0[]..1[]", None, Some(0.25), None, Some("pF")),
+        );
+        members.add(
+            "temp",
+            scaled("2[]", Some("i8"), Some(0.5), Some(-40.0), Some("degC")),
+        );
+        members.add("level", scaled("3[0..3]", None, None, None, Some("dB")));
+
+        assert!(validate_members(
+            "commands.TEST.request",
+            &members,
+            &HashMap::new(),
+            &le_device()
+        )
+        .is_empty());
+
+        let mut members = Members::new();
+        members.add("no_unit", scaled("0[]", None, Some(0.25), None, None));
+        members.add(
+            "zero_scale",
+            scaled("1[]", None, Some(0.0), None, Some("V")),
+        );
+        members.add("bad_unit", scaled("2[]", None, Some(0.1), None, Some("°C")));
+        members.add("a_bit", scaled("3[0]", None, Some(0.5), None, Some("V")));
+        members.add(
+            "a_repeat",
+            scaled("4[];2", None, Some(0.5), None, Some("V")),
+        );
+        members.add(
+            "bad_offset",
+            scaled("6[]", None, None, Some(f64::NAN), Some("V")),
+        );
+
+        let errors: Vec<String> = validate_members(
+            "commands.TEST.request",
+            &members,
+            &HashMap::new(),
+            &le_device(),
+        )
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "commands.TEST.request.no_unit: needs a unit, as it has a scale or offset",
+                "commands.TEST.request.zero_scale: has a scale of 0, which has to be a finite number other than 0",
+                "commands.TEST.request.bad_unit: has the unit \"°C\", which is part of the accessor names, so can only contain ASCII letters, digits and underscores",
+                "commands.TEST.request.a_bit: can only have a scale, offset or unit if it is an integer that is not repeated",
+                "commands.TEST.request.a_repeat: can only have a scale, offset or unit if it is an integer that is not repeated",
+                "commands.TEST.request.bad_offset: has an offset of NaN, which has to be a finite number",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        let definition = r#"
+            status = { cts = 7, err_cmd = 6 }
+            mode = { fast = 8 }
+            none = {}
+        "#;
+        let flags: FlagSets = toml::from_str(definition).unwrap();
+        let enumerations = HashMap::from([("mode".to_string(), Enumeration(HashMap::new()))]);
+
+        let errors: Vec<(String, &str)> = validate_flags(&flags, &enumerations, 8)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.message, &definition[diagnostic.span.unwrap()]))
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "flags.mode: is also the name of an enum".to_string(),
+                    "mode"
+                ),
+                (
+                    "flags.mode.fast: uses bit 8, but words only have 8 bits".to_string(),
+                    "fast"
+                ),
+                (
+                    "flags.none: should have at least one flag".to_string(),
+                    "none"
+                ),
+            ]
+        );
+
+        let mut members = Members::new();
+        members.add(
+            "status",
+            Field::new_bitfield("0[]", Some("status")).unwrap(),
+        );
+        members.add(
+            "low",
+            Field::new_bitfield("1[0..7]", Some("status")).unwrap(),
+        );
+        members.add(
+            "narrow",
+            Field::new_bitfield("2[0..3]", Some("status")).unwrap(),
+        );
+        members.add(
+            "wide",
+            Field::new_bitfield("3[]..4[]", Some("status")).unwrap(),
+        );
+        members.add(
+            "repeat",
+            Field::new_bitfield("5[];2", Some("status")).unwrap(),
+        );
+        members.add(
+            "an_enum",
+            Field::new_bitfield("7[0..1]", Some("injection")).unwrap(),
+        );
+
+        let errors: Vec<String> = validate_flag_fields("struct.header", &members, &flags, 8)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "struct.header.narrow: flags status use bit 7, but the field only has 4 bits",
+                "struct.header.wide: flags status can only be held by a field within a single word that is not repeated",
+                "struct.header.repeat: flags status can only be held by a field within a single word that is not repeated",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_too_large() {
+        let mut header_members = Members::new();
+        header_members.add("status", Field::new_bitfield("0[]", None).unwrap());
+        header_members.add("error", Field::new_bitfield("1[]", None).unwrap());
+        let common_structures =
+            HashMap::from([("header".to_string(), CommonStructure::new(header_members))]);
+
+        let header_field = |bits: &str| Field::Structure {
+            common_structure_name: "header".to_string(),
+            description: None,
+            bit_spec: bit_lang::parse(bits).unwrap(),
+        };
+
+        let mut members = Members::new();
+        members.add("header", header_field("0[]"));
+        members.add("a_u8", Field::new_bitfield("1[]", None).unwrap());
+
+        let errors: Vec<String> = validate_members(
+            "commands.TEST.response",
+            &members,
+            &common_structures,
+            &le_device(),
+        )
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "commands.TEST.response.header: struct header needs 2 words, but only 1 are given",
+                "commands.TEST.response.a_u8: overlaps header at bit 0 of word 1"
+            ]
+        );
+
+        let mut members = Members::new();
+        members.add("header", header_field("0[];2"));
+        members.add("a_u8", Field::new_bitfield("2[]", None).unwrap());
+
+        assert!(validate_members(
+            "commands.TEST.response",
+            &members,
+            &common_structures,
+            &le_device()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_polled_without_response() {
+        let mut header_members = Members::new();
+        header_members.add("cts", Field::new_bitfield("0[7]", None).unwrap());
+        let common_structures =
+            HashMap::from([("header".to_string(), CommonStructure::new(header_members))]);
+
+        let flow_control_errors = |command: &str| -> Vec<String> {
+            let command: Command = toml::from_str(command).unwrap();
+            validate_flow_control(
+                "commands.TEST.flow_control",
+                &command,
+                &common_structures,
+                &HashMap::new(),
+                &FlagSets::new(),
+                &le_device(),
+            )
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+        };
+
+        assert!(flow_control_errors(
+            r#"
+            opcode = 0x01
+            flow_control = { type = "polled", on = "header", condition = "cts" }
+            response.header = { bits = "0[]", struct = "header" }
+            "#
+        )
+        .is_empty());
+        assert_eq!(
+            flow_control_errors(
+                r#"
+                opcode = 0x01
+                flow_control = { type = "polled", on = "header", condition = "cts" }
+                request.arg = { bits = "0[]" }
+                "#
+            ),
+            vec!["commands.TEST.flow_control: polled commands need a response, as the header struct is read into it"]
+        );
+        // Direct commands can do without a response
+        assert!(flow_control_errors("opcode = 0x01").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_flow_control_keys() {
+        let parse_error = |flow_control: &str| {
+            toml::from_str::<Command>(&format!("opcode = 0x01\nflow_control = {flow_control}"))
+                .err()
+                .map(|error| error.message().to_string())
+        };
+
+        assert!(parse_error(
+            r#"{ type = "polled", on = "header", condition = "cts", max_polls = 10 }"#
+        )
+        .is_none());
+        assert!(parse_error(
+            r#"{ type = "polled", on = "header", condition = "cts", max_pols = 10 }"#
+        )
+        .unwrap()
+        .starts_with("unknown field `max_pols`"));
+        assert!(parse_error(
+            r#"{ type = "chunked", chunk_size = 8, on = "header", condition = "cts", timeout = 10 }"#
+        )
+        .unwrap()
+        .starts_with("unknown field `timeout`"));
+        assert!(
+            parse_error(r#"{ type = "timed", delay_us = 10, max_polls = 10 }"#)
+                .unwrap()
+                .starts_with("unknown field `max_polls`")
+        );
+    }
+
+    #[test]
+    fn test_module_names() {
+        let names = [
+            "POLL".to_string(),
+            "READ_ID".to_string(),
+            "Scaled".to_string(),
+        ];
+
+        let spans = [Some(10..14), Some(30..37), Some(50..56)];
+
+        let diagnostics: Vec<(String, Option<Range<usize>>)> =
+            validate_module_names("commands", names.iter().zip(spans))
+                .into_iter()
+                .map(|diagnostic| (diagnostic.message, diagnostic.span))
+                .collect();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                ("commands.POLL: is generated as the module poll, which the generated crate already has".to_string(), Some(10..14)),
+                ("commands.Scaled: is generated as the module scaled, which the generated crate already has".to_string(), Some(50..56)),
+            ]
+        );
+
+        // The spans are those of the names in the definition
+        let definition_toml = r#"
+            version = "0.0.1"
+
+            [device]
+            name = "test"
+            word_size = 8
+            endian = "le"
+
+            [commands.POLL]
+            opcode = 0x01
+
+            [struct.Scaled]
+            value = { bits = "0[]" }
+        "#;
+        let definition: Definition = toml::from_str(definition_toml).unwrap();
+
+        let names: Vec<&str> = validate(&definition)
+            .into_iter()
+            .map(|diagnostic| &definition_toml[diagnostic.span.unwrap()])
+            .collect();
+
+        assert_eq!(names, vec!["Scaled", "POLL"]);
+    }
+
+    #[test]
+    fn test_struct_errors() {
+        let definition: Definition = toml::from_str(
+            r#"
+            version = "0.0.1"
+
+            [device]
+            name = "test"
+            word_size = 8
+            endian = "le"
+
+            [struct.header]
+            errors = ["err_cmd", "st == 300"]
+            cts = { bits = "0[7]" }
+            err_cmd = { bits = "0[6]" }
+            st = { bits = "0[0..5]" }
+
+            [commands.READ]
+            opcode = 0x01
+            flow_control = { type = "polled", on = "header", condition = "cts" }
+            response.header = { bits = "0[]", struct = "header" }
+
+            [commands.WRITE]
+            opcode = 0x02
+            flow_control = { type = "polled", on = "header", condition = "cts" }
+            response.value = { bits = "0[]" }
+
+            [commands.RESET]
+            opcode = 0x03
+            flow_control = { type = "polled", on = "header", condition = "cts" }
+            errors = ["st == 1"]
+            response.header = { bits = "0[]", struct = "header" }
+            "#,
+        )
+        .unwrap();
+
+        // The commands without errors of their own take those of the struct
+        let command = &definition.commands["READ"];
+        assert!(command.errors().is_none());
+        assert_eq!(
+            command
+                .error_conditions(&definition.common_structures)
+                .map(|errors| errors.get_ref().len()),
+            Some(2)
+        );
+        let command = &definition.commands["RESET"];
+        assert_eq!(
+            command
+                .error_conditions(&definition.common_structures)
+                .map(|errors| errors.get_ref().len()),
+            Some(1)
+        );
+
+        let messages: Vec<String> = validate(&definition)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "struct.header.errors: condition field st holds 0 to 63, so cannot be compared with 300",
+                "commands.WRITE.errors: the errors of struct.header need a response field with struct header",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_poll_limits() {
+        let poll_errors = |limits: PollLimits| {
+            let mut errors = Vec::new();
+            validate_poll_limits(&limits, &mut |message| errors.push(message));
+            errors
+        };
+
+        assert!(poll_errors(PollLimits::default()).is_empty());
+        assert!(poll_errors(PollLimits {
+            poll_interval_us: Some(50),
+            timeout_us: Some(1000),
+            ..Default::default()
+        })
+        .is_empty());
+
+        assert_eq!(
+            poll_errors(PollLimits {
+                max_polls: Some(0),
+                poll_interval_us: Some(0),
+                timeout_us: None,
+            }),
+            vec![
+                "poll_interval_us can only be used with a timeout_us",
+                "max_polls should be at least 1",
+                "poll_interval_us should be at least 1"
+            ]
+        );
+        assert_eq!(
+            poll_errors(PollLimits {
+                max_polls: Some(10),
+                poll_interval_us: None,
+                timeout_us: Some(1000),
+            }),
+            vec!["use either max_polls or timeout_us, not both"]
+        );
+    }
+}